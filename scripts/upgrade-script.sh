@@ -0,0 +1,86 @@
+#!/usr/bin/env bash
+# Drafts sql/pg_strict--<old>--<new>.sql from the difference between the
+# `cargo pgrx schema` output of a previous release and the working tree.
+#
+#   scripts/upgrade-script.sh v1.0.5 [pg17]
+#
+# Objects that exist only in the new schema are copied as they are. Objects
+# whose definition changed are listed at the end as comments, since they need
+# a hand-written CREATE OR REPLACE or ALTER. Review the draft before committing.
+set -euo pipefail
+
+old_ref=${1:?usage: $0 <previous-tag> [pg-feature]}
+feature=${2:-pg17}
+root=$(git rev-parse --show-toplevel)
+version() { sed -n 's/^version = "\(.*\)"/\1/p' "$1/Cargo.toml" | head -n 1; }
+
+work=$(mktemp -d)
+trap 'git -C "$root" worktree remove --force "$work/old" >/dev/null 2>&1 || true; rm -rf "$work"' EXIT
+
+git -C "$root" worktree add --detach "$work/old" "$old_ref" >/dev/null
+old_version=$(version "$work/old")
+new_version=$(version "$root")
+if [ "$old_version" = "$new_version" ]; then
+    echo "Cargo.toml still says $new_version; bump the version first." >&2
+    exit 1
+fi
+
+(cd "$work/old" && cargo pgrx schema "$feature" --out "$work/old.sql")
+(cd "$root" && cargo pgrx schema "$feature" --out "$work/new.sql")
+
+out="$root/sql/pg_strict--$old_version--$new_version.sql"
+mkdir -p "$root/sql"
+python3 - "$work/old.sql" "$work/new.sql" "$old_version" "$new_version" > "$out" <<'EOF'
+import re
+import sys
+
+old_path, new_path, old_version, new_version = sys.argv[1:]
+
+
+def objects(path):
+    """Splits a pgrx schema into its objects, keyed by what they create."""
+    text = open(path).read()
+    found = {}
+    for chunk in re.split(r"/\* <begin connected objects> \*/", text)[1:]:
+        body = chunk.split("/* </end connected objects> */")[0]
+        # Source locations move between versions; they are not part of the object.
+        body = "\n".join(
+            line for line in body.splitlines() if not line.lstrip().startswith("-- ")
+        ).strip()
+        if body:
+            found[identity(body)] = body
+    return found
+
+
+def identity(body):
+    match = re.search(
+        r"CREATE\s+(?:OR REPLACE\s+)?(FUNCTION|TABLE|VIEW|SCHEMA|TYPE|TRIGGER)\s+([^\s(]+)",
+        body,
+    )
+    return f"{match.group(1)} {match.group(2)}" if match else body
+
+
+old = objects(old_path)
+new = objects(new_path)
+
+print(f"-- Upgrades pg_strict from {old_version} to {new_version}.")
+print("--")
+print("-- Drafted with scripts/upgrade-script.sh from the difference between the")
+print("-- `cargo pgrx schema` output of both versions.")
+print(f"\\echo Use \"ALTER EXTENSION pg_strict UPDATE TO '{new_version}'\" to load this file. \\quit")
+for key, body in new.items():
+    if key not in old:
+        print()
+        print(body)
+
+changed = [key for key, body in new.items() if key in old and old[key] != body]
+removed = [key for key in old if key not in new]
+for title, keys in (("Changed", changed), ("Removed", removed)):
+    if keys:
+        print()
+        print(f"-- {title} since {old_version}; write the upgrade for these by hand:")
+        for key in keys:
+            print(f"--   {key}")
+EOF
+
+echo "wrote $out"
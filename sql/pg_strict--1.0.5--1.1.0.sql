@@ -0,0 +1,1199 @@
+-- Upgrades pg_strict from 1.0.5 to 1.1.0.
+--
+-- Drafted with scripts/upgrade-script.sh from the difference between the
+-- `cargo pgrx schema` output of both versions.
+\echo Use "ALTER EXTENSION pg_strict UPDATE TO '1.1.0'" to load this file. \quit
+
+CREATE FUNCTION "pg_strict_count_affected"(
+	"query" TEXT
+) RETURNS TABLE (
+	"operation" TEXT,
+	"target_table" TEXT,
+	"estimated_rows" double precision,
+	"table_rows" double precision,
+	"percent" double precision
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_count_affected_wrapper';
+
+CREATE FUNCTION "pg_strict_preview"(
+	"query" TEXT,
+	"row_limit" INT DEFAULT 10
+) RETURNS SETOF jsonb
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_preview_wrapper';
+
+CREATE FUNCTION "pg_strict_lint"(
+	"sql" TEXT
+) RETURNS TABLE (
+	"statement_index" INT,
+	"operation" TEXT,
+	"target_table" TEXT,
+	"rule" TEXT,
+	"severity" TEXT,
+	"suggested_fix" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_lint_wrapper';
+
+CREATE FUNCTION "pg_strict_check_batch"(
+	"statements" TEXT[]
+) RETURNS TABLE (
+	"statement_index" INT,
+	"verdict" TEXT,
+	"rules" TEXT[],
+	"detail" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_check_batch_wrapper';
+
+CREATE FUNCTION "pg_strict_explain"(
+	"query" TEXT
+) RETURNS TABLE (
+	"statement_index" INT,
+	"operation" TEXT,
+	"target_schema" TEXT,
+	"target_table" TEXT,
+	"has_where" bool,
+	"where_text" TEXT,
+	"uses_from_or_using" bool,
+	"verdict" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_explain_wrapper';
+
+CREATE FUNCTION "pg_strict_is_safe"(
+	"query" TEXT
+) RETURNS bool
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_is_safe_wrapper';
+
+CREATE FUNCTION "pg_strict_audit_functions"() RETURNS TABLE (
+	"routine" TEXT,
+	"language" TEXT,
+	"line" INT,
+	"operation" TEXT,
+	"statement" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_audit_functions_wrapper';
+
+CREATE FUNCTION "pg_strict_audit_triggers"() RETURNS TABLE (
+	"table_name" TEXT,
+	"trigger_name" TEXT,
+	"function" TEXT,
+	"line" INT,
+	"operation" TEXT,
+	"target_table" TEXT,
+	"statement" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_audit_triggers_wrapper';
+
+CREATE FUNCTION "pg_strict_audit_rules"() RETURNS TABLE (
+	"table_name" TEXT,
+	"rule_name" TEXT,
+	"event" TEXT,
+	"is_instead" bool,
+	"operation" TEXT,
+	"target_table" TEXT,
+	"definition" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_audit_rules_wrapper';
+
+CREATE FUNCTION "pg_strict_validate_migration"(
+	"sql" TEXT
+) RETURNS TABLE (
+	"statement_index" INT,
+	"severity" TEXT,
+	"rule" TEXT,
+	"operation" TEXT,
+	"target_table" TEXT,
+	"message" TEXT,
+	"suggested_fix" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_validate_migration_wrapper';
+
+CREATE FUNCTION "pg_strict_valid_pattern"(
+	"pattern" TEXT
+) RETURNS bool
+IMMUTABLE STRICT PARALLEL SAFE
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_valid_pattern_wrapper';
+
+CREATE FUNCTION "pg_strict_analyze"(
+	"query" TEXT
+) RETURNS jsonb
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_analyze_wrapper';
+
+CREATE FUNCTION "pg_strict_lint_function"(
+	"function" oid
+) RETURNS INT
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_lint_function_wrapper';
+
+CREATE FUNCTION "pg_strict_transaction_summary"() RETURNS TEXT
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_transaction_summary_wrapper';
+
+CREATE FUNCTION "pg_strict_effective_config"() RETURNS TABLE (
+	"setting" TEXT,
+	"scope" TEXT,
+	"value" TEXT,
+	"source" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_effective_config_wrapper';
+
+CREATE FUNCTION "pg_strict_status"() RETURNS TABLE (
+	"item" TEXT,
+	"value" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_status_wrapper';
+
+CREATE FUNCTION "pg_strict_set_mode"(
+	"operation" TEXT,
+	"mode" TEXT,
+	"scope" TEXT DEFAULT 'session'
+) RETURNS bool
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_set_mode_wrapper';
+
+CREATE FUNCTION "pg_strict_apply_role_policy"(
+	"role" TEXT,
+	"policy" TEXT
+) RETURNS SETOF TEXT
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_apply_role_policy_wrapper';
+
+CREATE FUNCTION "pg_strict_persist"(
+	"setting" TEXT,
+	"value" TEXT,
+	"reload" bool DEFAULT true
+) RETURNS bool
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_persist_wrapper';
+
+CREATE FUNCTION "pg_strict_invalidate_rules"() RETURNS void
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_invalidate_rules_wrapper';
+
+CREATE FUNCTION "pg_strict_allow_next"(
+	"reason" TEXT DEFAULT NULL
+) RETURNS bool
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_allow_next_wrapper';
+
+CREATE FUNCTION "pg_strict_snooze"(
+	"duration" interval,
+	"reason" TEXT
+) RETURNS timestamp with time zone
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_snooze_wrapper';
+
+CREATE FUNCTION "pg_strict_unsnooze"() RETURNS bool
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_unsnooze_wrapper';
+
+CREATE FUNCTION "pg_strict_request_approval"(
+	"query" TEXT
+) RETURNS bigint
+STRICT
+SECURITY DEFINER
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_request_approval_wrapper';
+
+CREATE FUNCTION "pg_strict_approve"(
+	"request_id" bigint
+) RETURNS bool
+STRICT
+SECURITY DEFINER
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_approve_wrapper';
+
+CREATE FUNCTION "pg_strict_allow_queryid"(
+	"query_id" bigint
+) RETURNS bool
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_allow_queryid_wrapper';
+
+CREATE FUNCTION "pg_strict_disallow_queryid"(
+	"query_id" bigint
+) RETURNS bool
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_disallow_queryid_wrapper';
+
+CREATE FUNCTION "pg_strict_allowed_queryids"() RETURNS SETOF bigint
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_allowed_queryids_wrapper';
+
+CREATE FUNCTION "pg_strict_stats"() RETURNS TABLE (
+	"statements_checked" bigint,
+	"violations_warned" bigint,
+	"violations_blocked" bigint,
+	"parse_failures" bigint,
+	"bypasses" bigint
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_stats_wrapper';
+
+CREATE FUNCTION "pg_strict_table_stats"() RETURNS TABLE (
+	"database" oid,
+	"relid" oid,
+	"relation" TEXT,
+	"update_violations" bigint,
+	"delete_violations" bigint,
+	"blocked" bigint
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_table_stats_wrapper';
+
+CREATE FUNCTION "pg_strict_top_offenders"(
+	"limit" INT DEFAULT 10
+) RETURNS TABLE (
+	"queryid" bigint,
+	"query" TEXT,
+	"violations" bigint,
+	"last_seen" timestamp with time zone
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_top_offenders_wrapper';
+
+CREATE FUNCTION "pg_strict_reset_stats"() RETURNS bool
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_reset_stats_wrapper';
+
+CREATE FUNCTION "pg_strict_audit_statements"() RETURNS TABLE (
+	"queryid" bigint,
+	"operation" TEXT,
+	"calls" bigint,
+	"query" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_audit_statements_wrapper';
+
+CREATE FUNCTION "pg_strict_active_violations"() RETURNS TABLE (
+	"pid" INT,
+	"usename" TEXT,
+	"datname" TEXT,
+	"operation" TEXT,
+	"duration" interval,
+	"query" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_active_violations_wrapper';
+
+CREATE FUNCTION "pg_strict_recommendations"() RETURNS TABLE (
+	"category" TEXT,
+	"target" TEXT,
+	"recommendation" TEXT,
+	"evidence" TEXT
+)
+STRICT
+LANGUAGE c
+AS 'MODULE_PATHNAME', 'pg_strict_recommendations_wrapper';
+
+-- strict_schema
+CREATE SCHEMA strict;
+GRANT USAGE ON SCHEMA strict TO PUBLIC;
+
+-- strict_approvals
+CREATE TABLE strict.approvals (
+    id bigserial PRIMARY KEY,
+    fingerprint text NOT NULL,
+    query text NOT NULL,
+    requested_by name NOT NULL DEFAULT session_user,
+    requested_at timestamptz NOT NULL DEFAULT now(),
+    approved_by name,
+    approved_at timestamptz,
+    consumed_at timestamptz
+);
+REVOKE ALL ON strict.approvals FROM PUBLIC;
+GRANT SELECT ON strict.approvals TO PUBLIC;
+
+-- An approval is used only by the role that requested it, and expires after
+-- pg_strict.approval_ttl, read here so that callers cannot stretch it.
+CREATE FUNCTION strict.consume_approval(p_fingerprint text)
+RETURNS boolean
+LANGUAGE sql
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+    WITH next AS (
+        SELECT id
+        FROM strict.approvals
+        WHERE fingerprint = p_fingerprint
+          AND requested_by = session_user
+          AND approved_at IS NOT NULL
+          AND consumed_at IS NULL
+          AND approved_at > now() - make_interval(secs => coalesce(
+              (SELECT s.setting::int FROM pg_settings s WHERE s.name = 'pg_strict.approval_ttl'), 3600))
+        ORDER BY approved_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+    ), consumed AS (
+        UPDATE strict.approvals AS a
+        SET consumed_at = now()
+        FROM next
+        WHERE a.id = next.id
+        RETURNING a.id
+    )
+    SELECT count(*) > 0 FROM consumed
+$$;
+REVOKE EXECUTE ON FUNCTION strict.consume_approval(text) FROM PUBLIC;
+
+-- strict_bypass_log
+CREATE TABLE strict.bypass_log (
+    id bigserial PRIMARY KEY,
+    logged_at timestamptz NOT NULL DEFAULT now(),
+    username name NOT NULL DEFAULT session_user,
+    mechanism text NOT NULL,
+    reason text,
+    statement text NOT NULL,
+    rows_affected bigint
+);
+REVOKE ALL ON strict.bypass_log FROM PUBLIC;
+
+CREATE FUNCTION strict.log_bypass(p_mechanism text, p_reason text, p_statement text)
+RETURNS bigint
+LANGUAGE sql
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+    INSERT INTO strict.bypass_log (mechanism, reason, statement)
+    VALUES (p_mechanism, nullif(p_reason, ''), p_statement)
+    RETURNING id
+$$;
+
+CREATE FUNCTION strict.set_bypass_rows(p_id bigint, p_rows bigint)
+RETURNS void
+LANGUAGE sql
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+    UPDATE strict.bypass_log
+    SET rows_affected = p_rows
+    WHERE id = p_id AND username = session_user AND rows_affected IS NULL
+$$;
+REVOKE EXECUTE ON FUNCTION strict.log_bypass(text, text, text) FROM PUBLIC;
+REVOKE EXECUTE ON FUNCTION strict.set_bypass_rows(bigint, bigint) FROM PUBLIC;
+
+-- strict_violations
+CREATE TABLE strict.violations (
+    id bigserial PRIMARY KEY,
+    occurred_at timestamptz NOT NULL DEFAULT now(),
+    username name NOT NULL DEFAULT session_user,
+    database name NOT NULL DEFAULT current_database(),
+    application_name text,
+    mode text NOT NULL,
+    operation text NOT NULL,
+    queryid bigint,
+    query text NOT NULL
+);
+REVOKE ALL ON strict.violations FROM PUBLIC;
+
+CREATE FUNCTION strict.log_violation(
+    p_occurred_at timestamptz,
+    p_application_name text,
+    p_mode text,
+    p_operation text,
+    p_queryid bigint,
+    p_query text,
+    p_username name DEFAULT NULL
+)
+RETURNS void
+LANGUAGE sql
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+    INSERT INTO strict.violations (occurred_at, username, application_name, mode, operation, queryid, query)
+    VALUES (p_occurred_at,
+            CASE WHEN p_username IS NOT NULL
+                      AND (SELECT r.rolsuper FROM pg_roles r WHERE r.rolname = session_user)
+                 THEN p_username ELSE session_user END,
+            nullif(p_application_name, ''), p_mode, p_operation, nullif(p_queryid, 0), p_query)
+$$;
+REVOKE EXECUTE ON FUNCTION strict.log_violation(timestamptz, text, text, text, bigint, text, name) FROM PUBLIC;
+
+-- strict_learned_statements
+CREATE TABLE strict.learned_statements (
+    id bigserial PRIMARY KEY,
+    observed_at timestamptz NOT NULL DEFAULT now(),
+    username name NOT NULL DEFAULT session_user,
+    application_name text,
+    backend_type text,
+    operation text NOT NULL,
+    table_name text,
+    query text NOT NULL
+);
+REVOKE ALL ON strict.learned_statements FROM PUBLIC;
+
+CREATE FUNCTION strict.log_learned_statement(
+    p_application_name text,
+    p_backend_type text,
+    p_operation text,
+    p_table_name text,
+    p_query text
+)
+RETURNS void
+LANGUAGE sql
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+    INSERT INTO strict.learned_statements (application_name, backend_type, operation, table_name, query)
+    VALUES (nullif(p_application_name, ''), nullif(p_backend_type, ''), p_operation, p_table_name, p_query)
+$$;
+
+-- pg_strict_export_audit
+CREATE FUNCTION strict.csv_field(value text)
+RETURNS text
+LANGUAGE sql
+IMMUTABLE
+AS $$
+    SELECT CASE WHEN value IS NULL THEN '' ELSE '"' || replace(value, '"', '""') || '"' END
+$$;
+
+CREATE FUNCTION pg_strict_export_audit(since timestamptz, format text DEFAULT 'json')
+RETURNS SETOF text
+LANGUAGE plpgsql
+STABLE
+SET search_path = pg_catalog, pg_temp
+AS $$
+DECLARE
+    export_format text := lower(format);
+BEGIN
+    IF export_format NOT IN ('json', 'csv') THEN
+        RAISE EXCEPTION 'pg_strict: unsupported export format "%". Use json or csv.', format;
+    END IF;
+
+    IF export_format = 'csv' THEN
+        RETURN NEXT 'event_time,event_type,username,database,application_name,mode,operation,mechanism,reason,queryid,statement,rows_affected';
+    END IF;
+
+    RETURN QUERY
+    WITH events AS (
+        SELECT occurred_at AS event_time, 'violation'::text AS event_type,
+               username::text AS username, database::text AS database, application_name,
+               mode, operation, NULL::text AS mechanism, NULL::text AS reason,
+               queryid, query AS statement, NULL::bigint AS rows_affected
+        FROM strict.violations
+        WHERE occurred_at >= since
+        UNION ALL
+        SELECT logged_at, 'bypass', username::text, current_database()::text, NULL,
+               NULL, NULL, mechanism, reason, NULL, statement, rows_affected
+        FROM strict.bypass_log
+        WHERE logged_at >= since
+    ), formatted AS (
+        SELECT *, to_char(event_time AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS event_time_utc
+        FROM events
+    )
+    SELECT CASE export_format
+        WHEN 'json' THEN jsonb_build_object(
+            'event_time', event_time_utc,
+            'event_type', event_type,
+            'username', username,
+            'database', database,
+            'application_name', application_name,
+            'mode', mode,
+            'operation', operation,
+            'mechanism', mechanism,
+            'reason', reason,
+            'queryid', queryid,
+            'statement', statement,
+            'rows_affected', rows_affected
+        )::text
+        ELSE concat_ws(',',
+            strict.csv_field(event_time_utc),
+            strict.csv_field(event_type),
+            strict.csv_field(username),
+            strict.csv_field(database),
+            strict.csv_field(application_name),
+            strict.csv_field(mode),
+            strict.csv_field(operation),
+            strict.csv_field(mechanism),
+            strict.csv_field(reason),
+            coalesce(queryid::text, ''),
+            strict.csv_field(statement),
+            coalesce(rows_affected::text, '')
+        )
+    END
+    FROM formatted
+    ORDER BY event_time;
+END
+$$;
+
+-- pg_strict_cascade_impact
+CREATE FUNCTION strict.cascade_targets(p_relid regclass)
+RETURNS TABLE (
+    depth int,
+    target_table text,
+    referenced_table text,
+    constraint_name text,
+    on_delete text,
+    estimated_rows float8
+)
+LANGUAGE sql
+STABLE
+SET search_path = pg_catalog, pg_temp
+AS $$
+    WITH RECURSIVE walk(depth, relid, parent, conname, action, path) AS (
+        SELECT 1, c.conrelid, c.confrelid, c.conname, c.confdeltype,
+               ARRAY[c.confrelid, c.conrelid]
+        FROM pg_constraint c
+        WHERE c.contype = 'f'
+          AND c.conparentid = 0
+          AND c.confrelid = p_relid
+          AND c.confdeltype IN ('c', 'n', 'd')
+        UNION ALL
+        SELECT w.depth + 1, c.conrelid, c.confrelid, c.conname, c.confdeltype,
+               w.path || c.conrelid
+        FROM walk w
+        JOIN pg_constraint c ON c.confrelid = w.relid
+        WHERE w.action = 'c'
+          AND c.contype = 'f'
+          AND c.conparentid = 0
+          AND c.confdeltype IN ('c', 'n', 'd')
+          AND c.conrelid <> ALL (w.path)
+    )
+    SELECT w.depth,
+           w.relid::regclass::text,
+           w.parent::regclass::text,
+           w.conname::text,
+           CASE w.action WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' ELSE 'SET DEFAULT' END,
+           nullif(cl.reltuples, -1)::float8
+    FROM walk w
+    JOIN pg_class cl ON cl.oid = w.relid
+    ORDER BY w.depth, 2, 4
+$$;
+
+CREATE FUNCTION pg_strict_cascade_impact(rel regclass)
+RETURNS TABLE (
+    depth int,
+    target_table text,
+    referenced_table text,
+    constraint_name text,
+    on_delete text,
+    estimated_rows float8
+)
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT * FROM strict.cascade_targets(rel)
+$$;
+
+-- strict_recycle_bin
+CREATE TABLE strict.recycle_bin (
+    bin_id bigserial PRIMARY KEY,
+    captured_at timestamptz NOT NULL DEFAULT now(),
+    username name NOT NULL DEFAULT session_user,
+    operation text NOT NULL,
+    relid oid NOT NULL,
+    table_name text NOT NULL,
+    statement text NOT NULL,
+    row_count bigint NOT NULL
+);
+REVOKE ALL ON strict.recycle_bin FROM PUBLIC;
+
+CREATE TABLE strict.recycle_bin_rows (
+    bin_id bigint NOT NULL REFERENCES strict.recycle_bin ON DELETE CASCADE,
+    row_data jsonb NOT NULL
+);
+CREATE INDEX ON strict.recycle_bin_rows (bin_id);
+REVOKE ALL ON strict.recycle_bin_rows FROM PUBLIC;
+
+-- Returns the new bin, or NULL when there are more than p_max_rows rows.
+CREATE FUNCTION strict.capture_pre_images(
+    p_operation text,
+    p_relid oid,
+    p_statement text,
+    p_max_rows int,
+    p_rows jsonb[]
+)
+RETURNS bigint
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+DECLARE
+    new_bin bigint;
+BEGIN
+    IF cardinality(p_rows) > p_max_rows THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO strict.recycle_bin (operation, relid, table_name, statement, row_count)
+    VALUES (p_operation, p_relid, p_relid::regclass::text, p_statement, coalesce(cardinality(p_rows), 0))
+    RETURNING bin_id INTO new_bin;
+
+    INSERT INTO strict.recycle_bin_rows (bin_id, row_data)
+    SELECT new_bin, r FROM unnest(p_rows) AS r;
+
+    RETURN new_bin;
+END
+$$;
+
+-- pg_strict_restore
+CREATE FUNCTION strict.recycle_bin_entry(p_bin_id bigint, OUT operation text, OUT relid oid)
+LANGUAGE plpgsql
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+DECLARE
+    bin_owner name;
+BEGIN
+    SELECT b.operation, b.relid, b.username INTO operation, relid, bin_owner
+    FROM strict.recycle_bin b
+    WHERE b.bin_id = p_bin_id;
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'pg_strict: recycle bin % does not exist.', p_bin_id;
+    END IF;
+    IF bin_owner <> session_user
+       AND NOT (SELECT r.rolsuper FROM pg_roles r WHERE r.rolname = session_user) THEN
+        RAISE EXCEPTION 'pg_strict: recycle bin % belongs to role %.', p_bin_id, bin_owner;
+    END IF;
+END
+$$;
+
+CREATE FUNCTION strict.recycle_bin_rows_of(p_bin_id bigint)
+RETURNS SETOF jsonb
+LANGUAGE plpgsql
+STABLE
+SECURITY DEFINER
+SET search_path = pg_catalog, pg_temp
+AS $$
+BEGIN
+    PERFORM strict.recycle_bin_entry(p_bin_id);
+    RETURN QUERY SELECT r.row_data FROM strict.recycle_bin_rows r WHERE r.bin_id = p_bin_id;
+END
+$$;
+
+CREATE FUNCTION pg_strict_restore(bin_id bigint, where_clause text DEFAULT NULL, on_conflict text DEFAULT 'error')
+RETURNS bigint
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    bin_operation text;
+    bin_relid oid;
+    conflict_mode text := lower(on_conflict);
+    target text;
+    col_list text;
+    src_list text;
+    excluded_list text;
+    pk_list text;
+    pk_match text;
+    source text;
+    conflict_clause text := '';
+    restored bigint;
+BEGIN
+    IF conflict_mode NOT IN ('error', 'skip', 'overwrite') THEN
+        RAISE EXCEPTION 'pg_strict: unsupported on_conflict value "%". Use error, skip or overwrite.', on_conflict;
+    END IF;
+
+    SELECT e.operation, e.relid INTO bin_operation, bin_relid
+    FROM strict.recycle_bin_entry(pg_strict_restore.bin_id) e;
+    target := bin_relid::regclass::text;
+
+    SELECT string_agg(pg_catalog.quote_ident(a.attname), ', ' ORDER BY a.attnum),
+           string_agg('src.' || pg_catalog.quote_ident(a.attname), ', ' ORDER BY a.attnum),
+           string_agg('EXCLUDED.' || pg_catalog.quote_ident(a.attname), ', ' ORDER BY a.attnum)
+    INTO col_list, src_list, excluded_list
+    FROM pg_catalog.pg_attribute a
+    WHERE a.attrelid = bin_relid AND a.attnum > 0 AND NOT a.attisdropped AND a.attgenerated = '';
+
+    SELECT string_agg(pg_catalog.quote_ident(a.attname), ', ' ORDER BY a.attnum),
+           string_agg(format('t.%1$I = src.%1$I', a.attname), ' AND ' ORDER BY a.attnum)
+    INTO pk_list, pk_match
+    FROM pg_catalog.pg_index i
+    JOIN pg_catalog.pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY (i.indkey)
+    WHERE i.indrelid = bin_relid AND i.indisprimary;
+
+    source := format(
+        '(SELECT p.* FROM (SELECT (pg_catalog.jsonb_populate_record(NULL::%s, r)).* '
+        'FROM strict.recycle_bin_rows_of(%s) AS r) AS p WHERE %s) AS src',
+        target, pg_strict_restore.bin_id, coalesce(where_clause, 'true'));
+
+    IF bin_operation = 'UPDATE' THEN
+        IF pk_match IS NULL THEN
+            RAISE EXCEPTION 'pg_strict: % has no primary key, so updated rows cannot be matched.', target;
+        END IF;
+        EXECUTE format('UPDATE %s AS t SET (%s) = ROW(%s) FROM %s WHERE %s',
+                       target, col_list, src_list, source, pk_match);
+    ELSE
+        IF conflict_mode = 'skip' THEN
+            conflict_clause := ' ON CONFLICT DO NOTHING';
+        ELSIF conflict_mode = 'overwrite' THEN
+            IF pk_list IS NULL THEN
+                RAISE EXCEPTION 'pg_strict: % has no primary key to resolve conflicts on.', target;
+            END IF;
+            conflict_clause := format(' ON CONFLICT (%s) DO UPDATE SET (%s) = ROW(%s)',
+                                      pk_list, col_list, excluded_list);
+        END IF;
+        EXECUTE format('INSERT INTO %s (%s) OVERRIDING SYSTEM VALUE SELECT %s FROM %s%s',
+                       target, col_list, src_list, source, conflict_clause);
+    END IF;
+
+    GET DIAGNOSTICS restored = ROW_COUNT;
+    RETURN restored;
+END
+$$;
+
+-- strict_purge_recycle_bin
+CREATE FUNCTION strict.purge_recycle_bin(p_retention_seconds int)
+RETURNS TABLE (bins bigint, row_count bigint, bytes bigint)
+LANGUAGE plpgsql
+SET search_path = pg_catalog, pg_temp
+AS $$
+#variable_conflict use_column
+BEGIN
+    RETURN QUERY
+    WITH expired AS (
+        SELECT b.bin_id, b.row_count
+        FROM strict.recycle_bin b
+        WHERE b.captured_at < now() - make_interval(secs => p_retention_seconds)
+    ), sizes AS (
+        SELECT coalesce(sum(pg_column_size(r.row_data)), 0)::bigint AS bytes
+        FROM strict.recycle_bin_rows r
+        WHERE r.bin_id IN (SELECT e.bin_id FROM expired e)
+    ), purged AS (
+        DELETE FROM strict.recycle_bin b
+        USING expired e
+        WHERE b.bin_id = e.bin_id
+        RETURNING b.row_count
+    )
+    SELECT count(*), coalesce(sum(p.row_count), 0)::bigint, (SELECT s.bytes FROM sizes s)
+    FROM purged p;
+END
+$$;
+REVOKE EXECUTE ON FUNCTION strict.purge_recycle_bin(int) FROM PUBLIC;
+
+-- pg_strict_batches
+CREATE PROCEDURE pg_strict_delete_in_batches(
+    tbl regclass,
+    where_sql text,
+    batch_size int DEFAULT 1000,
+    pause interval DEFAULT '0'
+)
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    batch_rows bigint;
+    total bigint := 0;
+BEGIN
+    IF where_sql IS NULL OR btrim(where_sql) = '' THEN
+        RAISE EXCEPTION 'pg_strict: where_sql must not be empty. Use TRUNCATE to remove every row.';
+    END IF;
+    IF batch_size IS NULL OR batch_size < 1 THEN
+        RAISE EXCEPTION 'pg_strict: batch_size must be at least 1.';
+    END IF;
+
+    LOOP
+        -- The condition is checked again because ctids of different
+        -- partitions may coincide.
+        EXECUTE format(
+            'DELETE FROM %1$s WHERE ctid = ANY (ARRAY(SELECT ctid FROM %1$s WHERE (%2$s) LIMIT %3$s)) AND (%2$s)',
+            tbl, where_sql, batch_size);
+        GET DIAGNOSTICS batch_rows = ROW_COUNT;
+        EXIT WHEN batch_rows = 0;
+
+        total := total + batch_rows;
+        COMMIT;
+        RAISE NOTICE 'pg_strict: deleted % rows from % so far.', total, tbl;
+        IF pause > interval '0' THEN
+            PERFORM pg_catalog.pg_sleep_for(pause);
+        END IF;
+    END LOOP;
+
+    RAISE NOTICE 'pg_strict: deleted % rows from % in total.', total, tbl;
+END
+$$;
+
+CREATE PROCEDURE pg_strict_update_in_batches(
+    tbl regclass,
+    set_sql text,
+    where_sql text,
+    batch_size int DEFAULT 1000,
+    pause interval DEFAULT '0'
+)
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    key_columns text;
+    last_key jsonb;
+    batch_rows bigint;
+    total bigint := 0;
+BEGIN
+    IF where_sql IS NULL OR btrim(where_sql) = '' THEN
+        RAISE EXCEPTION 'pg_strict: where_sql must not be empty. Use WHERE true to update every row intentionally.';
+    END IF;
+    IF set_sql IS NULL OR btrim(set_sql) = '' THEN
+        RAISE EXCEPTION 'pg_strict: set_sql must not be empty.';
+    END IF;
+    IF batch_size IS NULL OR batch_size < 1 THEN
+        RAISE EXCEPTION 'pg_strict: batch_size must be at least 1.';
+    END IF;
+
+    SELECT string_agg(pg_catalog.quote_ident(a.attname), ', '
+                      ORDER BY array_position(i.indkey::int2[], a.attnum))
+    INTO key_columns
+    FROM pg_catalog.pg_index i
+    JOIN pg_catalog.pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY (i.indkey)
+    WHERE i.indrelid = tbl AND i.indisprimary;
+    IF key_columns IS NULL THEN
+        RAISE EXCEPTION 'pg_strict: % has no primary key to walk the table by.', tbl;
+    END IF;
+
+    -- Updated rows may still match where_sql, so batches follow the primary
+    -- key instead of looking for matching rows again.
+    LOOP
+        EXECUTE format(
+            'WITH batch AS ('
+            '    SELECT %1$s FROM %2$s'
+            '    WHERE (%3$s) AND ($1 IS NULL OR (%1$s) > (SELECT %1$s FROM pg_catalog.jsonb_populate_record(NULL::%2$s, $1)))'
+            '    ORDER BY %1$s LIMIT %4$s'
+            '), updated AS ('
+            '    UPDATE %2$s SET %5$s WHERE (%1$s) IN (SELECT %1$s FROM batch) RETURNING 1'
+            ')'
+            'SELECT (SELECT count(*) FROM updated),'
+            '       (SELECT pg_catalog.to_jsonb(k) FROM (SELECT %1$s FROM batch ORDER BY %1$s DESC LIMIT 1) AS k)',
+            key_columns, tbl, where_sql, batch_size, set_sql)
+        INTO batch_rows, last_key
+        USING last_key;
+        EXIT WHEN last_key IS NULL;
+
+        total := total + batch_rows;
+        COMMIT;
+        RAISE NOTICE 'pg_strict: updated % rows in % so far.', total, tbl;
+        IF pause > interval '0' THEN
+            PERFORM pg_catalog.pg_sleep_for(pause);
+        END IF;
+    END LOOP;
+
+    RAISE NOTICE 'pg_strict: updated % rows in % in total.', total, tbl;
+END
+$$;
+
+-- strict_rules
+CREATE TABLE strict.rules (
+    id bigserial PRIMARY KEY,
+    target text NOT NULL DEFAULT '*',
+    operation text NOT NULL DEFAULT '*' CHECK (operation IN ('update', 'delete', '*')),
+    mode text CHECK (mode IN ('off', 'log', 'notice', 'warn', 'on', 'learn')),
+    max_affected_rows bigint CHECK (max_affected_rows >= 0),
+    max_affected_percent double precision CHECK (max_affected_percent BETWEEN 0 AND 100),
+    role_name text,
+    application_name text,
+    valid_from timestamptz,
+    valid_until timestamptz,
+    note text,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    created_by name NOT NULL DEFAULT session_user,
+    CHECK (valid_until IS NULL OR valid_from IS NULL OR valid_until > valid_from)
+);
+REVOKE ALL ON strict.rules FROM PUBLIC;
+GRANT SELECT ON strict.rules TO PUBLIC;
+
+CREATE FUNCTION strict.rules_changed()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    PERFORM pg_strict_invalidate_rules();
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER rules_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON strict.rules
+FOR EACH STATEMENT EXECUTE FUNCTION strict.rules_changed();
+
+CREATE FUNCTION pg_strict_add_rule(
+    target text DEFAULT '*',
+    operation text DEFAULT '*',
+    mode text DEFAULT NULL,
+    max_affected_rows bigint DEFAULT NULL,
+    max_affected_percent double precision DEFAULT NULL,
+    role_name text DEFAULT NULL,
+    application_name text DEFAULT NULL,
+    valid_from timestamptz DEFAULT NULL,
+    valid_until timestamptz DEFAULT NULL,
+    note text DEFAULT NULL
+)
+RETURNS bigint
+LANGUAGE sql
+AS $$
+    INSERT INTO strict.rules (target, operation, mode, max_affected_rows, max_affected_percent,
+                              role_name, application_name, valid_from, valid_until, note)
+    VALUES ($1, lower($2), lower($3), $4, $5, $6, $7, $8, $9, $10)
+    RETURNING id
+$$;
+
+CREATE FUNCTION pg_strict_drop_rule(rule_id bigint)
+RETURNS boolean
+LANGUAGE sql
+AS $$
+    WITH dropped AS (
+        DELETE FROM strict.rules WHERE id = rule_id RETURNING 1
+    )
+    SELECT count(*) > 0 FROM dropped
+$$;
+REVOKE EXECUTE ON FUNCTION pg_strict_add_rule(text, text, text, bigint, double precision, text, text, timestamptz, timestamptz, text) FROM PUBLIC;
+REVOKE EXECUTE ON FUNCTION pg_strict_drop_rule(bigint) FROM PUBLIC;
+
+-- strict_custom_rules
+CREATE TABLE strict.custom_rules (
+    id bigserial PRIMARY KEY,
+    pattern text NOT NULL CHECK (pg_strict_valid_pattern(pattern)),
+    action text NOT NULL DEFAULT 'block' CHECK (action IN ('warn', 'block')),
+    message text NOT NULL,
+    enabled boolean NOT NULL DEFAULT true,
+    note text,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    created_by name NOT NULL DEFAULT session_user
+);
+REVOKE ALL ON strict.custom_rules FROM PUBLIC;
+GRANT SELECT ON strict.custom_rules TO PUBLIC;
+
+CREATE TRIGGER custom_rules_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON strict.custom_rules
+FOR EACH STATEMENT EXECUTE FUNCTION strict.rules_changed();
+
+-- pg_strict_config_transfer
+CREATE FUNCTION pg_strict_export_config()
+RETURNS jsonb
+LANGUAGE sql
+STABLE
+SET search_path = pg_catalog, pg_temp
+AS $$
+    SELECT jsonb_build_object(
+        'version', 1,
+        'settings', (
+            SELECT coalesce(jsonb_object_agg(name, current_setting(name) ORDER BY name), '{}')
+            FROM pg_settings
+            WHERE name LIKE 'pg_strict.%'
+        ),
+        'rules', (
+            SELECT coalesce(jsonb_agg(jsonb_build_object(
+                'target', target,
+                'operation', operation,
+                'mode', mode,
+                'max_affected_rows', max_affected_rows,
+                'max_affected_percent', max_affected_percent,
+                'role_name', role_name,
+                'application_name', application_name,
+                'valid_from', valid_from,
+                'valid_until', valid_until,
+                'note', note
+            ) ORDER BY id), '[]')
+            FROM strict.rules
+        ),
+        'role_settings', (
+            SELECT coalesce(jsonb_agg(jsonb_build_object(
+                'role', r.rolname,
+                'database', d.datname,
+                'setting', split_part(c, '=', 1),
+                'value', substr(c, strpos(c, '=') + 1)
+            ) ORDER BY r.rolname NULLS FIRST, d.datname NULLS FIRST, c), '[]')
+            FROM pg_db_role_setting s
+            LEFT JOIN pg_roles r ON r.oid = s.setrole
+            LEFT JOIN pg_database d ON d.oid = s.setdatabase
+            CROSS JOIN LATERAL unnest(s.setconfig) AS c
+            WHERE c LIKE 'pg_strict.%'
+        )
+    )
+$$;
+
+-- Settings are written with pg_strict_persist() and rules replace the current
+-- ones; stored role and database settings are added or overwritten. Returns
+-- what was changed. Writing postgresql.auto.conf cannot be rolled back, so the
+-- setting names are checked first and the settings are written last, once
+-- nothing else can raise an error.
+CREATE FUNCTION pg_strict_import_config(config jsonb)
+RETURNS SETOF text
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    setting record;
+    entry jsonb;
+    command text;
+    persisted boolean := false;
+    rule_count bigint;
+BEGIN
+    IF NOT (SELECT r.rolsuper FROM pg_catalog.pg_roles r WHERE r.rolname = current_user) THEN
+        RAISE EXCEPTION 'pg_strict: pg_strict_import_config requires superuser privileges.';
+    END IF;
+    IF config->>'version' IS DISTINCT FROM '1' THEN
+        RAISE EXCEPTION 'pg_strict: unsupported configuration version %.', coalesce(config->>'version', 'NULL');
+    END IF;
+
+    FOR setting IN
+        SELECT key, value FROM pg_catalog.jsonb_each_text(coalesce(config->'settings', '{}')) ORDER BY key
+    LOOP
+        IF setting.key !~ '^pg_strict\.[a-z0-9_]+$' THEN
+            RAISE EXCEPTION 'pg_strict: invalid setting name "%".', setting.key;
+        END IF;
+    END LOOP;
+
+    IF config ? 'rules' THEN
+        DELETE FROM strict.rules WHERE true;
+        INSERT INTO strict.rules (target, operation, mode, max_affected_rows, max_affected_percent,
+                                  role_name, application_name, valid_from, valid_until, note)
+        SELECT coalesce(r.target, '*'), coalesce(r.operation, '*'), r.mode, r.max_affected_rows,
+               r.max_affected_percent, r.role_name, r.application_name, r.valid_from,
+               r.valid_until, r.note
+        FROM pg_catalog.jsonb_populate_recordset(NULL::strict.rules, config->'rules') AS r;
+        GET DIAGNOSTICS rule_count = ROW_COUNT;
+        RETURN NEXT pg_catalog.format('replaced strict.rules with %s rules', rule_count);
+    END IF;
+
+    FOR entry IN SELECT * FROM pg_catalog.jsonb_array_elements(coalesce(config->'role_settings', '[]')) LOOP
+        IF entry->>'setting' IS NULL OR entry->>'setting' !~ '^pg_strict\.[a-z0-9_]+$' THEN
+            RAISE EXCEPTION 'pg_strict: invalid setting name "%".', entry->>'setting';
+        END IF;
+        IF entry->>'role' IS NULL AND entry->>'database' IS NULL THEN
+            RAISE EXCEPTION 'pg_strict: role setting % names neither a role nor a database.', entry->>'setting';
+        END IF;
+        IF entry->>'role' IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM pg_catalog.pg_roles r WHERE r.rolname = entry->>'role') THEN
+            RAISE WARNING 'pg_strict: skipping % for missing role %.', entry->>'setting', entry->>'role';
+            CONTINUE;
+        END IF;
+        IF entry->>'database' IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM pg_catalog.pg_database d WHERE d.datname = entry->>'database') THEN
+            RAISE WARNING 'pg_strict: skipping % for missing database %.', entry->>'setting', entry->>'database';
+            CONTINUE;
+        END IF;
+
+        command := CASE
+            WHEN entry->>'role' IS NULL THEN pg_catalog.format('ALTER DATABASE %I', entry->>'database')
+            WHEN entry->>'database' IS NULL THEN pg_catalog.format('ALTER ROLE %I', entry->>'role')
+            ELSE pg_catalog.format('ALTER ROLE %I IN DATABASE %I', entry->>'role', entry->>'database')
+        END || pg_catalog.format(' SET %s = %L', entry->>'setting', entry->>'value');
+        EXECUTE command;
+        RETURN NEXT command;
+    END LOOP;
+
+    FOR setting IN
+        SELECT key, value FROM pg_catalog.jsonb_each_text(coalesce(config->'settings', '{}')) ORDER BY key
+    LOOP
+        CONTINUE WHEN pg_catalog.current_setting(setting.key, true) IS NOT DISTINCT FROM setting.value;
+        PERFORM pg_strict_persist(setting.key, setting.value, false);
+        persisted := true;
+        RETURN NEXT pg_catalog.format('ALTER SYSTEM SET %s = %L', setting.key, setting.value);
+    END LOOP;
+    IF persisted THEN
+        PERFORM pg_catalog.pg_reload_conf();
+    END IF;
+END
+$$;
+REVOKE EXECUTE ON FUNCTION pg_strict_import_config(jsonb) FROM PUBLIC;
+
+-- pg_strict_config_snapshot
+CREATE FUNCTION pg_strict_config_snapshot()
+RETURNS jsonb
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT pg_strict_export_config()
+        || pg_catalog.jsonb_build_object('taken_at', pg_catalog.now(), 'taken_by', session_user)
+$$;
+
+CREATE FUNCTION pg_strict_config_diff(a jsonb, b jsonb)
+RETURNS TABLE (section text, item text, before text, after text)
+LANGUAGE sql
+IMMUTABLE
+SET search_path = pg_catalog, pg_temp
+AS $$
+    WITH role_settings AS (
+        SELECT side, e->>'setting' AS setting, e->>'role' AS role, e->>'database' AS database,
+               e->>'value' AS value
+        FROM (VALUES ('a', a), ('b', b)) AS s(side, config)
+        CROSS JOIN LATERAL jsonb_array_elements(coalesce(config->'role_settings', '[]')) AS e
+    )
+    SELECT 'settings', coalesce(x.key, y.key), x.value, y.value
+    FROM jsonb_each_text(coalesce(a->'settings', '{}')) AS x
+    FULL JOIN jsonb_each_text(coalesce(b->'settings', '{}')) AS y ON x.key = y.key
+    WHERE x.value IS DISTINCT FROM y.value
+    UNION ALL
+    SELECT 'rules', coalesce(x.rule, y.rule)->>'target', x.rule::text, y.rule::text
+    FROM jsonb_array_elements(coalesce(a->'rules', '[]')) AS x(rule)
+    FULL JOIN jsonb_array_elements(coalesce(b->'rules', '[]')) AS y(rule) ON x.rule = y.rule
+    WHERE x.rule IS NULL OR y.rule IS NULL
+    UNION ALL
+    SELECT 'role_settings',
+           concat_ws(' ', coalesce(x.setting, y.setting),
+                     'for role ' || coalesce(x.role, y.role),
+                     'in database ' || coalesce(x.database, y.database)),
+           x.value, y.value
+    FROM (SELECT * FROM role_settings WHERE side = 'a') AS x
+    FULL JOIN (SELECT * FROM role_settings WHERE side = 'b') AS y
+        ON x.setting = y.setting
+       AND x.role IS NOT DISTINCT FROM y.role
+       AND x.database IS NOT DISTINCT FROM y.database
+    WHERE x.value IS DISTINCT FROM y.value
+    ORDER BY 1, 2
+$$;
+
+-- strict_lint_function_bodies
+-- Function bodies created by extension scripts are their authors' business.
+CREATE FUNCTION strict.lint_function_bodies()
+RETURNS event_trigger
+LANGUAGE plpgsql
+AS $$
+DECLARE
+    command record;
+BEGIN
+    FOR command IN
+        SELECT objid FROM pg_event_trigger_ddl_commands()
+        WHERE object_type IN ('function', 'procedure') AND NOT in_extension
+    LOOP
+        PERFORM pg_strict_lint_function(command.objid);
+    END LOOP;
+END
+$$;
+
+CREATE EVENT TRIGGER pg_strict_lint_functions ON ddl_command_end
+WHEN TAG IN ('CREATE FUNCTION', 'CREATE PROCEDURE')
+EXECUTE FUNCTION strict.lint_function_bodies();
+
+-- pg_strict_preload_check
+DO $$
+BEGIN
+    IF (SELECT value FROM pg_strict_status() WHERE item = 'shared_memory') <> 'allocated' THEN
+        RAISE WARNING 'pg_strict: the library is not in shared_preload_libraries, so statements are only checked in sessions that happen to load it.'
+            USING HINT = 'Add pg_strict to shared_preload_libraries in postgresql.conf and restart PostgreSQL.';
+    END IF;
+END
+$$;
@@ -0,0 +1,54 @@
+use crate::analyzer::QueryAnalyzer;
+use pgrx::datum::Interval;
+use pgrx::prelude::*;
+
+/// A backend currently running an UPDATE or DELETE without a WHERE clause.
+pub struct ActiveViolation {
+    pub pid: i32,
+    pub username: Option<String>,
+    pub database: Option<String>,
+    pub application_name: Option<String>,
+    pub operation: &'static str,
+    pub duration: Option<Interval>,
+    pub running_seconds: f64,
+    pub query: String,
+}
+
+/// Scans pg_stat_activity for active statements without a WHERE clause. Texts
+/// truncated by track_activity_query_size that no longer parse are skipped.
+pub fn active_violations() -> Vec<ActiveViolation> {
+    Spi::connect(|client| {
+        let mut violations = Vec::new();
+        let rows = client.select(
+            "SELECT pid, usename::text, datname::text, now() - query_start, \
+                    extract(epoch FROM now() - query_start)::float8, query, application_name \
+             FROM pg_stat_activity \
+             WHERE state = 'active' AND pid <> pg_backend_pid() AND query IS NOT NULL",
+            None,
+            &[],
+        )?;
+        for row in rows {
+            let Some(query) = row.get::<String>(6)? else {
+                continue;
+            };
+            let Ok(analyzer) = QueryAnalyzer::new(&query) else {
+                continue;
+            };
+            let Some(operation) = analyzer.missing_where_operations().first().copied() else {
+                continue;
+            };
+            violations.push(ActiveViolation {
+                pid: row.get::<i32>(1)?.unwrap_or_default(),
+                username: row.get::<String>(2)?,
+                database: row.get::<String>(3)?,
+                application_name: row.get::<String>(7)?,
+                operation: operation.as_str(),
+                duration: row.get::<Interval>(4)?,
+                running_seconds: row.get::<f64>(5)?.unwrap_or_default(),
+                query,
+            });
+        }
+        Ok::<_, pgrx::spi::Error>(violations)
+    })
+    .unwrap_or_else(|error| pgrx::error!("pg_strict: could not read pg_stat_activity: {}", error))
+}
@@ -1,9 +1,12 @@
+use crate::stats::{self, Counter};
 use pgrx::PgSqlErrorCode;
 use pgrx::PgTryBuilder;
 use pgrx::list::List;
 use pgrx::memcx;
 use pgrx::memcx::MemCx;
 use pgrx::pg_sys;
+use pgrx::spi::quote_identifier;
+use std::ffi::CStr;
 use std::ffi::CString;
 use std::ffi::c_void;
 
@@ -22,14 +25,18 @@ impl Operation {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct ParsedStmt {
     operation: Operation,
     has_where: bool,
+    /// How the target table is referred to in the statement: its alias, or
+    /// its (possibly qualified) name, quoted as needed.
+    target: String,
 }
 
 pub struct QueryAnalyzer {
     statements: Vec<ParsedStmt>,
+    statement_count: usize,
 }
 
 impl QueryAnalyzer {
@@ -37,17 +44,41 @@ impl QueryAnalyzer {
         let c_query =
             CString::new(query_string).map_err(|_| Box::new(PgSqlErrorCode::ERRCODE_WARNING))?;
 
-        let statements = PgTryBuilder::new(|| {
+        let (statements, statement_count) = PgTryBuilder::new(|| {
             let statements = memcx::current_context(|mcx| unsafe {
                 let raw_list = pg_sys::pg_parse_query(c_query.as_ptr());
                 collect_parsed_statements(raw_list, mcx)
             });
             Ok(statements)
         })
-        .catch_others(|_| Err(Box::new(PgSqlErrorCode::ERRCODE_WARNING)))
+        .catch_others(|_| {
+            stats::increment(Counter::ParseFailures);
+            Err(Box::new(PgSqlErrorCode::ERRCODE_WARNING))
+        })
         .execute()?;
 
-        Ok(Self { statements })
+        Ok(Self {
+            statements,
+            statement_count,
+        })
+    }
+
+    /// The operation of a query consisting of exactly one UPDATE or DELETE.
+    pub fn single_operation(&self) -> Option<Operation> {
+        self.single_statement().map(|stmt| stmt.operation)
+    }
+
+    /// How the target of a single UPDATE or DELETE is referenced, for example
+    /// `o` in `DELETE FROM orders o`.
+    pub fn single_target(&self) -> Option<&str> {
+        self.single_statement().map(|stmt| stmt.target.as_str())
+    }
+
+    fn single_statement(&self) -> Option<&ParsedStmt> {
+        match self.statements.as_slice() {
+            [stmt] if self.statement_count == 1 => Some(stmt),
+            _ => None,
+        }
     }
 
     pub fn has_where_clause(&self, operation: Operation) -> bool {
@@ -73,10 +104,203 @@ impl QueryAnalyzer {
     }
 }
 
-fn collect_parsed_statements(raw_list: *mut pg_sys::List, memcx: &MemCx<'_>) -> Vec<ParsedStmt> {
+/// Collapses whitespace and trailing semicolons so equivalent statement texts compare equal.
+pub fn normalize_statement(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(';')
+        .trim_end()
+        .to_string()
+}
+
+/// Replaces literal constants with `$n` placeholders, numbered after any
+/// parameters already in the statement, the way pg_stat_statements shows
+/// queries. Comments are dropped as well, since they can carry the same data.
+pub fn redact_literals(query: &str) -> String {
+    enum Token {
+        Text(String),
+        Literal,
+    }
+
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut highest_param = 0u32;
+    let mut i = 0;
+
+    let is_ident_char = |ch: char| ch.is_alphanumeric() || ch == '_' || ch == '$';
+
+    while i < chars.len() {
+        let ch = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if ch == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            text.push(' ');
+        } else if ch == '/' && next == Some('*') {
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            text.push(' ');
+        } else if ch == '\'' {
+            i = skip_string(&chars, i, false);
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+            tokens.push(Token::Literal);
+        } else if ch == '"' {
+            let end = skip_quoted(&chars, i, '"');
+            text.extend(&chars[i..end]);
+            i = end;
+        } else if ch == '$' && next.is_some_and(|c| c.is_ascii_digit()) {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            highest_param = highest_param.max(number.parse().unwrap_or(0));
+            text.push('$');
+            text.push_str(&number);
+        } else if ch == '$' {
+            match dollar_quote_end(&chars, i) {
+                Some(end) => {
+                    i = end;
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                    tokens.push(Token::Literal);
+                }
+                None => {
+                    text.push(ch);
+                    i += 1;
+                }
+            }
+        } else if ch.is_alphabetic() || ch == '_' {
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let prefix = word.to_ascii_lowercase();
+            if chars.get(i) == Some(&'\'') && matches!(prefix.as_str(), "e" | "b" | "x" | "n") {
+                i = skip_string(&chars, i, prefix == "e");
+                tokens.push(Token::Text(std::mem::take(&mut text)));
+                tokens.push(Token::Literal);
+            } else if prefix == "u" && chars.get(i) == Some(&'&') && chars.get(i + 1) == Some(&'\'')
+            {
+                i = skip_string(&chars, i + 1, false);
+                tokens.push(Token::Text(std::mem::take(&mut text)));
+                tokens.push(Token::Literal);
+            } else {
+                text.push_str(&word);
+            }
+        } else if ch.is_ascii_digit() || (ch == '.' && next.is_some_and(|c| c.is_ascii_digit())) {
+            while i < chars.len() {
+                let c = chars[i];
+                if (c == 'e' || c == 'E') && matches!(chars.get(i + 1), Some('+') | Some('-')) {
+                    i += 2;
+                } else if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+            tokens.push(Token::Literal);
+        } else {
+            text.push(ch);
+            i += 1;
+        }
+    }
+    tokens.push(Token::Text(text));
+
+    let mut next_param = highest_param;
+    let mut out = String::with_capacity(query.len());
+    for token in tokens {
+        match token {
+            Token::Text(text) => out.push_str(&text),
+            Token::Literal => {
+                next_param += 1;
+                out.push('$');
+                out.push_str(&next_param.to_string());
+            }
+        }
+    }
+    out
+}
+
+/// Index just past the single-quoted string starting at `start`, including
+/// adjacent continuation quotes ('') and, for E'' strings, backslash escapes.
+fn skip_string(chars: &[char], start: usize, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if backslash_escapes => i += 2,
+            '\'' if chars.get(i + 1) == Some(&'\'') => i += 2,
+            '\'' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+fn skip_quoted(chars: &[char], start: usize, quote: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Index just past the dollar-quoted string starting at `start`, if `start`
+/// opens one (`$$` or `$tag$`).
+fn dollar_quote_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+        i += 1;
+    }
+    if chars.get(i) != Some(&'$') {
+        return None;
+    }
+    let tag = &chars[start..=i];
+    let mut j = i + 1;
+    while j + tag.len() <= chars.len() {
+        if &chars[j..j + tag.len()] == tag {
+            return Some(j + tag.len());
+        }
+        j += 1;
+    }
+    Some(chars.len())
+}
+
+/// Returns the UPDATE/DELETE statements in `raw_list` and the total number of statements.
+fn collect_parsed_statements(
+    raw_list: *mut pg_sys::List,
+    memcx: &MemCx<'_>,
+) -> (Vec<ParsedStmt>, usize) {
     let list = unsafe { List::<*mut c_void>::downcast_ptr_in_memcx(raw_list, memcx) };
     let Some(list) = list else {
-        return Vec::new();
+        return (Vec::new(), 0);
     };
 
     let mut parsed = Vec::new();
@@ -85,7 +309,7 @@ fn collect_parsed_statements(raw_list: *mut pg_sys::List, memcx: &MemCx<'_>) ->
             parsed.push(stmt);
         }
     }
-    parsed
+    (parsed, list.len())
 }
 
 #[cfg(any(test, feature = "pg_test"))]
@@ -118,6 +342,7 @@ fn parsed_stmt_from_node(stmt: *mut pg_sys::Node) -> Option<ParsedStmt> {
             Some(ParsedStmt {
                 operation: Operation::Update,
                 has_where,
+                target: range_var_reference(unsafe { (*update).relation }),
             })
         }
         pg_sys::NodeTag::T_DeleteStmt => {
@@ -126,8 +351,231 @@ fn parsed_stmt_from_node(stmt: *mut pg_sys::Node) -> Option<ParsedStmt> {
             Some(ParsedStmt {
                 operation: Operation::Delete,
                 has_where,
+                target: range_var_reference(unsafe { (*delete).relation }),
             })
         }
         _ => None,
     }
 }
+
+fn range_var_reference(relation: *mut pg_sys::RangeVar) -> String {
+    if relation.is_null() {
+        return String::new();
+    }
+    let text = |ptr: *mut std::ffi::c_char| {
+        (!ptr.is_null()).then(|| {
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+
+    let alias = unsafe { (*relation).alias };
+    if !alias.is_null() {
+        if let Some(name) = text(unsafe { (*alias).aliasname }) {
+            return quote_identifier(&name);
+        }
+    }
+    let name = quote_identifier(&text(unsafe { (*relation).relname }).unwrap_or_default());
+    match text(unsafe { (*relation).schemaname }) {
+        Some(schema) => format!("{}.{}", quote_identifier(&schema), name),
+        None => name,
+    }
+}
+
+/// Rewrites a single UPDATE or DELETE into the SELECT of the rows it would
+/// modify: the target's columns, read from the same FROM/USING list and
+/// filtered by the same WHERE clause. `target` is the statement's reference to
+/// its target table (see `QueryAnalyzer::single_target`).
+pub fn affected_rows_select(query: &str, target: &str) -> Result<String, &'static str> {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let chars: Vec<char> = query.chars().collect();
+    let words = top_level_words(&chars);
+    let text = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+    let find = |from: usize, wanted: &[&str]| {
+        words
+            .iter()
+            .find(|(start, word)| *start >= from && wanted.contains(&word.as_str()))
+            .map(|(start, _)| *start)
+    };
+
+    let Some(&(verb_start, ref verb)) = words
+        .iter()
+        .find(|(_, word)| word == "update" || word == "delete")
+    else {
+        return Err("expected an UPDATE or DELETE statement");
+    };
+    let with_prefix = text(0, verb_start);
+    let after_verb = verb_start + verb.len();
+
+    let (target_start, target_end, joined) = if verb == "delete" {
+        let Some(from) = find(after_verb, &["from"]) else {
+            return Err("expected DELETE FROM");
+        };
+        let end = find(from, &["using", "where", "returning"]).unwrap_or(chars.len());
+        let joined = match find(from, &["using"]) {
+            Some(using) => {
+                let using_end = find(using, &["where", "returning"]).unwrap_or(chars.len());
+                Some(text(using + "using".len(), using_end))
+            }
+            None => None,
+        };
+        (from + "from".len(), end, joined)
+    } else {
+        let Some(set) = find(after_verb, &["set"]) else {
+            return Err("expected UPDATE ... SET");
+        };
+        let joined = match find(set, &["from"]) {
+            Some(from) => {
+                let from_end = find(from, &["where", "returning"]).unwrap_or(chars.len());
+                Some(text(from + "from".len(), from_end))
+            }
+            None => None,
+        };
+        (after_verb, set, joined)
+    };
+
+    let where_clause = match find(target_end, &["where"]) {
+        Some(start) => {
+            let end = find(start, &["returning"]).unwrap_or(chars.len());
+            let mut following = words.iter().filter(|(pos, _)| *pos > start);
+            if following.next().is_some_and(|(_, word)| word == "current")
+                && following.next().is_some_and(|(_, word)| word == "of")
+            {
+                return Err("WHERE CURRENT OF cannot be previewed");
+            }
+            text(start, end)
+        }
+        None => String::new(),
+    };
+
+    let mut sql = format!(
+        "{}SELECT {}.* FROM {}",
+        with_prefix,
+        target,
+        text(target_start, target_end).trim()
+    );
+    if let Some(joined) = joined {
+        sql.push_str(", ");
+        sql.push_str(joined.trim());
+    }
+    if !where_clause.is_empty() {
+        sql.push(' ');
+        sql.push_str(where_clause.trim());
+    }
+    Ok(sql)
+}
+
+/// The condition of the top-level WHERE clause of an UPDATE or DELETE, as
+/// written.
+pub fn where_condition(statement: &str) -> Option<String> {
+    let chars: Vec<char> = statement.chars().collect();
+    let words = top_level_words(&chars);
+    let start = words.iter().position(|(_, word)| word == "where")?;
+    let from = words[start].0 + "where".len();
+    let end = words[start..]
+        .iter()
+        .find(|(_, word)| word == "returning" || word == ";")
+        .map_or(chars.len(), |(end, _)| *end);
+    Some(
+        chars[from..end]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_string(),
+    )
+}
+
+/// The UPDATE and DELETE statements, with or without a WITH clause, written
+/// directly in a SQL or PL/pgSQL function body, in order, with the body line
+/// they start on (counted as in PL/pgSQL error context). A statement starts
+/// the body or follows a semicolon or BEGIN, THEN, ELSE or LOOP. Statements
+/// built at run time for EXECUTE are not seen.
+pub fn body_statements(body: &str) -> Vec<(i32, String)> {
+    let chars: Vec<char> = body.chars().collect();
+    let words = top_level_words(&chars);
+    let mut statements = Vec::new();
+    let mut at_start = true;
+    for (index, (start, word)) in words.iter().enumerate() {
+        if at_start && matches!(word.as_str(), "update" | "delete" | "with") {
+            let end = words[index..]
+                .iter()
+                .find(|(_, word)| word == ";")
+                .map_or(chars.len(), |(end, _)| *end);
+            let line = 1 + chars[..*start].iter().filter(|&&ch| ch == '\n').count() as i32;
+            statements.push((line, chars[*start..end].iter().collect()));
+        }
+        at_start = matches!(
+            word.as_str(),
+            ";" | "begin" | "then" | "else" | "loop" | "atomic"
+        );
+    }
+    statements
+}
+
+/// Lower-cased bare words outside parentheses, strings, quoted identifiers and
+/// comments, with the index of their first character. Statement-ending
+/// semicolons are included as `;`.
+fn top_level_words(chars: &[char]) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut depth = 0i32;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if ch == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if ch == '/' && next == Some('*') {
+            let mut comment_depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    comment_depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    comment_depth -= 1;
+                    i += 2;
+                    if comment_depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+        } else if ch == '\'' {
+            i = skip_string(chars, i, false);
+        } else if ch == '"' {
+            i = skip_quoted(chars, i, '"');
+        } else if ch == '$' && next.is_some_and(|c| !c.is_ascii_digit()) {
+            i = dollar_quote_end(chars, i).unwrap_or(i + 1);
+        } else if ch == ';' && depth == 0 {
+            words.push((i, ";".to_string()));
+            i += 1;
+        } else if ch == '(' || ch == '[' {
+            depth += 1;
+            i += 1;
+        } else if ch == ')' || ch == ']' {
+            depth -= 1;
+            i += 1;
+        } else if ch.is_alphabetic() || ch == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect::<String>().to_lowercase();
+            if chars.get(i) == Some(&'\'') && word == "e" {
+                i = skip_string(chars, i, true);
+            } else if depth == 0 {
+                words.push((start, word));
+            }
+        } else {
+            i += 1;
+        }
+    }
+    words
+}
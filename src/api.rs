@@ -1,8 +1,70 @@
-use crate::analyzer::{Operation, QueryAnalyzer};
-use crate::guc::{current_modes, mode_to_str};
+use crate::activity;
+use crate::analyzer::{self, Operation, QueryAnalyzer};
+use crate::approval;
+use crate::bypass;
+use crate::catalog;
+use crate::custom_rules;
+use crate::estimate;
+use crate::guc::{
+    StrictMode, audit_enabled, current_enforce, current_modes, current_preset, enforce_to_str,
+    exempt_application_names, exempt_backend_types, guard_truncate_mode, mode_to_str,
+    preset_to_str, protect_catalogs_mode, trusted_functions,
+};
+use crate::hooks;
+use crate::learn;
+use crate::lint;
+use crate::migration;
+use crate::policy::{
+    self, DELETE_MODE_GUC, GUARD_TRUNCATE_GUC, PROTECT_CATALOGS_GUC, UPDATE_MODE_GUC,
+};
+use crate::report::Violation;
+use crate::rules;
+use crate::shmem;
+use crate::stats;
+use crate::summary;
+use pgrx::PgList;
+use pgrx::datum::Interval;
 use pgrx::prelude::*;
+use pgrx::spi::{quote_identifier, quote_literal};
 
-const VALID_MODES: [&str; 3] = ["off", "warn", "on"];
+const VALID_MODES: [&str; 6] = ["off", "log", "notice", "warn", "on", "learn"];
+
+/// The settings each `pg_strict_apply_role_policy()` policy binds to a role.
+/// Superuser-only settings are left out so role administrators can apply them.
+const ROLE_POLICIES: [(&str, [(&str, &str); 3]); 3] = [
+    (
+        "strict",
+        [
+            (UPDATE_MODE_GUC, "on"),
+            (DELETE_MODE_GUC, "on"),
+            ("pg_strict.guard_truncate", "on"),
+        ],
+    ),
+    (
+        "warn-only",
+        [
+            (UPDATE_MODE_GUC, "warn"),
+            (DELETE_MODE_GUC, "warn"),
+            ("pg_strict.guard_truncate", "warn"),
+        ],
+    ),
+    (
+        "exempt",
+        [
+            (UPDATE_MODE_GUC, "off"),
+            (DELETE_MODE_GUC, "off"),
+            ("pg_strict.guard_truncate", "off"),
+        ],
+    ),
+];
+
+/// The mode settings `pg_strict_set_mode()` changes, by operation.
+const MODE_SETTINGS: [(&str, &str); 4] = [
+    ("update", UPDATE_MODE_GUC),
+    ("delete", DELETE_MODE_GUC),
+    ("truncate", "pg_strict.guard_truncate"),
+    ("catalogs", PROTECT_CATALOGS_GUC),
+];
 
 #[pg_extern]
 pub(crate) fn pg_strict_version() -> &'static str {
@@ -29,6 +91,297 @@ pub(crate) fn pg_strict_validate_delete(query: &str) -> Result<bool, Box<pgrx::P
     validate_operation(query, Operation::Delete)
 }
 
+#[pg_extern]
+pub(crate) fn pg_strict_count_affected(
+    query: &str,
+) -> TableIterator<
+    'static,
+    (
+        name!(operation, String),
+        name!(target_table, String),
+        name!(estimated_rows, f64),
+        name!(table_rows, Option<f64>),
+        name!(percent, Option<f64>),
+    ),
+> {
+    require_single_dml(query);
+    let estimate = hooks::without_checks(|| estimate::explain_dml(query))
+        .unwrap_or_else(|e| pgrx::error!("pg_strict: could not plan query: {}", e));
+    let percent = estimate
+        .table_rows
+        .map(|rows| estimate::affected_percent(estimate.estimated_rows, rows));
+    TableIterator::once((
+        estimate.operation,
+        estimate.target_table,
+        estimate.estimated_rows,
+        estimate.table_rows,
+        percent,
+    ))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_preview(
+    query: &str,
+    row_limit: default!(i32, 10),
+) -> SetOfIterator<'static, pgrx::JsonB> {
+    if row_limit < 0 {
+        pgrx::error!("pg_strict: row_limit must not be negative.");
+    }
+    let analyzer = require_single_dml(query);
+    let target = analyzer.single_target().unwrap_or_default();
+    let select = analyzer::affected_rows_select(query, target)
+        .unwrap_or_else(|reason| pgrx::error!("pg_strict: {}.", reason));
+
+    let sql = format!("SELECT pg_catalog.to_jsonb(preview) FROM ({select}) AS preview LIMIT $1");
+    let rows = Spi::connect(|client| {
+        let mut rows = Vec::new();
+        for row in client.select(&sql, None, &[row_limit.into()])? {
+            if let Some(value) = row.get::<pgrx::JsonB>(1)? {
+                rows.push(value);
+            }
+        }
+        Ok::<_, pgrx::spi::Error>(rows)
+    })
+    .unwrap_or_else(|e| pgrx::error!("pg_strict: could not preview query: {}", e));
+    SetOfIterator::new(rows)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_lint(
+    sql: &str,
+) -> TableIterator<
+    'static,
+    (
+        name!(statement_index, i32),
+        name!(operation, String),
+        name!(target_table, Option<String>),
+        name!(rule, String),
+        name!(severity, String),
+        name!(suggested_fix, String),
+    ),
+> {
+    TableIterator::new(lint::lint(sql).into_iter().map(|finding| {
+        (
+            finding.statement,
+            finding.operation.to_string(),
+            finding.table,
+            finding.rule.to_string(),
+            lint::severity(finding.mode).to_string(),
+            finding.fix,
+        )
+    }))
+}
+
+/// One verdict per array element, for tools that check many statements at
+/// once. `detail` carries the fix for the most severe finding, or why the
+/// statement could not be checked.
+#[pg_extern]
+pub(crate) fn pg_strict_check_batch(
+    statements: Vec<Option<String>>,
+) -> TableIterator<
+    'static,
+    (
+        name!(statement_index, i32),
+        name!(verdict, String),
+        name!(rules, Vec<String>),
+        name!(detail, Option<String>),
+    ),
+> {
+    let verdicts = statements
+        .into_iter()
+        .enumerate()
+        .map(|(index, statement)| {
+            let index = index as i32 + 1;
+            let Some(statement) = statement else {
+                return (
+                    index,
+                    "invalid".into(),
+                    Vec::new(),
+                    Some("statement is NULL".into()),
+                );
+            };
+            // Parsed once on its own first, so a syntax error is reported as
+            // a verdict instead of failing the whole batch.
+            if QueryAnalyzer::new(&statement).is_err() {
+                return (
+                    index,
+                    "invalid".into(),
+                    Vec::new(),
+                    Some("statement could not be parsed".into()),
+                );
+            }
+            let findings = lint::lint(&statement);
+            let verdict = lint::verdict(&findings).to_string();
+            let detail = findings
+                .iter()
+                .find(|finding| finding.mode == StrictMode::On)
+                .or(findings.first())
+                .map(|finding| finding.fix.clone());
+            let rules = findings
+                .into_iter()
+                .map(|finding| finding.rule.to_string())
+                .collect();
+            (index, verdict, rules, detail)
+        })
+        .collect::<Vec<_>>();
+    TableIterator::new(verdicts)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_explain(
+    query: &str,
+) -> TableIterator<
+    'static,
+    (
+        name!(statement_index, i32),
+        name!(operation, String),
+        name!(target_schema, Option<String>),
+        name!(target_table, Option<String>),
+        name!(has_where, Option<bool>),
+        name!(where_text, Option<String>),
+        name!(uses_from_or_using, Option<bool>),
+        name!(verdict, String),
+    ),
+> {
+    TableIterator::new(lint::explain(query).into_iter().map(|explanation| {
+        (
+            explanation.statement,
+            explanation.operation,
+            explanation.schema,
+            explanation.table,
+            explanation.has_where,
+            explanation.where_text,
+            explanation.joined,
+            explanation.verdict.to_string(),
+        )
+    }))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_analyze(query: &str) -> pgrx::JsonB {
+    pgrx::JsonB(lint::analyze(query))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_is_safe(query: &str) -> bool {
+    lint::is_safe(query)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_audit_functions() -> TableIterator<
+    'static,
+    (
+        name!(routine, String),
+        name!(language, String),
+        name!(line, i32),
+        name!(operation, String),
+        name!(statement, String),
+    ),
+> {
+    TableIterator::new(lint::audit_functions().into_iter().map(|found| {
+        (
+            found.routine,
+            found.language,
+            found.line,
+            found.operation.as_str().to_string(),
+            found.statement,
+        )
+    }))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_audit_triggers() -> TableIterator<
+    'static,
+    (
+        name!(table_name, String),
+        name!(trigger_name, String),
+        name!(function, String),
+        name!(line, i32),
+        name!(operation, String),
+        name!(target_table, Option<String>),
+        name!(statement, String),
+    ),
+> {
+    TableIterator::new(lint::audit_triggers().into_iter().map(|found| {
+        (
+            found.table,
+            found.trigger,
+            found.function,
+            found.line,
+            found.operation.as_str().to_string(),
+            found.target,
+            found.statement,
+        )
+    }))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_audit_rules() -> TableIterator<
+    'static,
+    (
+        name!(table_name, String),
+        name!(rule_name, String),
+        name!(event, String),
+        name!(is_instead, bool),
+        name!(operation, String),
+        name!(target_table, Option<String>),
+        name!(definition, String),
+    ),
+> {
+    TableIterator::new(lint::audit_rules().into_iter().map(|found| {
+        (
+            found.table,
+            found.rule,
+            found.event,
+            found.instead,
+            found.operation.as_str().to_string(),
+            found.target,
+            found.definition,
+        )
+    }))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_validate_migration(
+    sql: &str,
+) -> TableIterator<
+    'static,
+    (
+        name!(statement_index, i32),
+        name!(severity, String),
+        name!(rule, String),
+        name!(operation, String),
+        name!(target_table, Option<String>),
+        name!(message, String),
+        name!(suggested_fix, String),
+    ),
+> {
+    TableIterator::new(migration::validate(sql).into_iter().map(|finding| {
+        (
+            finding.statement,
+            finding.severity.as_str().to_string(),
+            finding.rule.to_string(),
+            finding.operation,
+            finding.table,
+            finding.message,
+            finding.fix,
+        )
+    }))
+}
+
+/// Checks a function body as the event trigger does on CREATE FUNCTION.
+#[pg_extern]
+pub(crate) fn pg_strict_lint_function(function: pg_sys::Oid) -> i32 {
+    lint::function_body(function)
+}
+
+/// The summary `pg_strict.summary_at_commit` would send if the transaction
+/// committed now, so it can be reviewed while ROLLBACK is still possible.
+#[pg_extern]
+pub(crate) fn pg_strict_transaction_summary() -> Option<String> {
+    summary::current()
+}
+
 #[pg_extern]
 pub(crate) fn pg_strict_config() -> TableIterator<
     'static,
@@ -51,61 +404,888 @@ pub(crate) fn pg_strict_config() -> TableIterator<
             mode_to_str(delete_mode).to_string(),
             "Require WHERE clause on DELETE statements".to_string(),
         ),
+        (
+            "enforce".to_string(),
+            enforce_to_str(current_enforce()).to_string(),
+            "Whether non-superusers may relax enforcement".to_string(),
+        ),
+        (
+            "preset".to_string(),
+            preset_to_str(current_preset()).to_string(),
+            "Environment preset supplying defaults for unset settings".to_string(),
+        ),
+        (
+            "protect_catalogs".to_string(),
+            mode_to_str(protect_catalogs_mode()).to_string(),
+            "Flag DML on pg_catalog relations regardless of WHERE clause".to_string(),
+        ),
+        (
+            "audit".to_string(),
+            if audit_enabled() { "on" } else { "off" }.to_string(),
+            "Record violations in strict.violations".to_string(),
+        ),
+        (
+            "max_affected_rows".to_string(),
+            policy::effective_max_affected_rows().to_string(),
+            "Most rows one UPDATE or DELETE may modify (0 = no limit)".to_string(),
+        ),
+        (
+            "max_affected_percent".to_string(),
+            policy::effective_max_affected_percent().to_string(),
+            "Largest estimated share of a table one UPDATE or DELETE may modify (0 = off)"
+                .to_string(),
+        ),
+        (
+            "exempt_application_names".to_string(),
+            exempt_application_names().join(","),
+            "application_name patterns exempt from enforcement".to_string(),
+        ),
+        (
+            "exempt_backend_types".to_string(),
+            exempt_backend_types().join(","),
+            "Backend type patterns exempt from enforcement".to_string(),
+        ),
+        (
+            "trusted_functions".to_string(),
+            trusted_functions().join(","),
+            "Functions whose internal DML is not checked".to_string(),
+        ),
     ];
 
     TableIterator::new(config)
 }
 
+/// The policy that applies to the current role, database and application, one
+/// row per setting and per matching row of strict.rules, with where each
+/// value comes from.
+#[pg_extern]
+pub(crate) fn pg_strict_effective_config() -> TableIterator<
+    'static,
+    (
+        name!(setting, String),
+        name!(scope, String),
+        name!(value, String),
+        name!(source, String),
+    ),
+> {
+    let settings = Spi::connect(|client| {
+        let mut settings = Vec::new();
+        for row in client.select(
+            "SELECT name, current_setting(name), source, sourcefile, sourceline \
+             FROM pg_catalog.pg_settings WHERE name LIKE 'pg_strict.%' ORDER BY name",
+            None,
+            &[],
+        )? {
+            settings.push((
+                row.get::<String>(1)?.unwrap_or_default(),
+                row.get::<String>(2)?.unwrap_or_default(),
+                row.get::<String>(3)?.unwrap_or_default(),
+                row.get::<String>(4)?,
+                row.get::<i32>(5)?,
+            ));
+        }
+        Ok::<_, pgrx::spi::Error>(settings)
+    })
+    .unwrap_or_else(|error| pgrx::error!("pg_strict: could not read pg_settings: {}", error));
+
+    let role_policy = ROLE_POLICIES.iter().find(|(_, policy)| {
+        policy.iter().all(|(guc_name, mode)| {
+            settings.iter().any(|(name, value, source, _, _)| {
+                name == guc_name && value == mode && source == "user"
+            })
+        })
+    });
+    let preset = current_preset();
+
+    let mut rows = Vec::new();
+    for (name, value, source, file, line) in &settings {
+        let mut value = value.clone();
+        let mut source = match source.as_str() {
+            "configuration file" => match (file, line) {
+                (Some(file), Some(line)) => format!("configuration file {file}:{line}"),
+                _ => source.clone(),
+            },
+            "user" => match role_policy {
+                Some((policy, settings))
+                    if settings
+                        .iter()
+                        .any(|(guc_name, _)| *guc_name == name.as_str()) =>
+                {
+                    format!("role policy {policy}")
+                }
+                _ => "ALTER ROLE".to_string(),
+            },
+            "database" => "ALTER DATABASE".to_string(),
+            "database user" => "ALTER ROLE IN DATABASE".to_string(),
+            "session" => "SET".to_string(),
+            _ => source.clone(),
+        };
+        // Settings nobody has set take the preset's value, as in policy::resolve().
+        let preset_value = policy::preset_mode(preset, name)
+            .map(|mode| mode_to_str(mode).to_string())
+            .or_else(|| policy::preset_threshold(preset, name).map(|value| value.to_string()))
+            .filter(|_| source == "default");
+        if let Some(preset_value) = preset_value {
+            value = preset_value;
+            source = format!("preset {}", preset_to_str(preset));
+        }
+        let operation = match name.as_str() {
+            UPDATE_MODE_GUC => Some(Operation::Update),
+            DELETE_MODE_GUC => Some(Operation::Delete),
+            _ => None,
+        };
+        if let Some(operation) = operation {
+            (value, source) = effective_mode_source(operation, value, source);
+        }
+        rows.push((name.clone(), "*".to_string(), value, source));
+    }
+
+    for rule in rules::applicable() {
+        let source = format!("strict.rules id {}", rule.id);
+        let operations = match rule.operation {
+            Some(operation) => vec![operation],
+            None => vec![Operation::Update, Operation::Delete],
+        };
+        if let Some(mode) = rule.mode {
+            for operation in &operations {
+                let guc_name = match operation {
+                    Operation::Update => UPDATE_MODE_GUC,
+                    Operation::Delete => DELETE_MODE_GUC,
+                };
+                rows.push((
+                    guc_name.to_string(),
+                    rule.target.clone(),
+                    mode_to_str(mode).to_string(),
+                    source.clone(),
+                ));
+            }
+        }
+        let scope = match rule.operation {
+            Some(operation) => format!("{} ({})", rule.target, operation.as_str()),
+            None => rule.target.clone(),
+        };
+        if let Some(limit) = rule.max_affected_rows {
+            rows.push((
+                "pg_strict.max_affected_rows".to_string(),
+                scope.clone(),
+                limit.to_string(),
+                source.clone(),
+            ));
+        }
+        if let Some(percent) = rule.max_affected_percent {
+            rows.push((
+                "pg_strict.max_affected_percent".to_string(),
+                scope,
+                percent.to_string(),
+                source,
+            ));
+        }
+    }
+
+    TableIterator::new(rows)
+}
+
+/// Applies what `policy::effective_mode()` layers over the configured mode
+/// for statements on any table: force mode, a rule for every table,
+/// escalation and maintenance windows.
+fn effective_mode_source(operation: Operation, value: String, source: String) -> (String, String) {
+    let rule_mode = rules::mode(operation, None);
+    let (mut mode, mut source) = if policy::forced() {
+        (StrictMode::On, "pg_strict.enforce = force".to_string())
+    } else if let Some((id, mode)) = rule_mode {
+        (mode, format!("strict.rules id {id}"))
+    } else {
+        let Some(mode) = rules::parse_mode(&value) else {
+            return (value, source);
+        };
+        (mode, source)
+    };
+
+    if mode.is_warning() && policy::escalation() == Some(policy::Escalation::Reached) {
+        mode = StrictMode::On;
+        source.push_str(", escalated by pg_strict.escalate_at");
+    }
+    if mode == StrictMode::On && policy::in_maintenance_window() {
+        mode = StrictMode::Warn;
+        source.push_str(", relaxed in a maintenance window");
+    }
+    (mode_to_str(mode).to_string(), source)
+}
+
+/// Health of pg_strict in this backend, one row per item.
+#[pg_extern]
+pub(crate) fn pg_strict_status()
+-> TableIterator<'static, (name!(item, String), name!(value, String))> {
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    let mut status: Vec<(String, String)> = vec![
+        ("version".to_string(), pg_strict_version().to_string()),
+        ("server_version".to_string(), server_version()),
+        ("built_for".to_string(), built_for().to_string()),
+        (
+            "initialized".to_string(),
+            yes_no(crate::initialized()).to_string(),
+        ),
+        ("loaded_via".to_string(), crate::loaded_via().to_string()),
+        (
+            "features".to_string(),
+            if shmem::available() {
+                "full"
+            } else {
+                "degraded: per-session enforcement only, without statistics, the query identifier allowlist, background workers or shared rule invalidation"
+            }
+            .to_string(),
+        ),
+        (
+            "shared_memory".to_string(),
+            if shmem::available() {
+                "allocated"
+            } else {
+                "not allocated"
+            }
+            .to_string(),
+        ),
+    ];
+    let installed = hooks::hooks_installed();
+    status.extend(hooks::hook_chain().into_iter().map(|(hook, first)| {
+        let state = match (installed, first) {
+            (false, _) => "not installed",
+            (true, true) => "first",
+            (true, false) => "not first",
+        };
+        (hook.to_string(), state.to_string())
+    }));
+    status.push((
+        "hook_reinstalls".to_string(),
+        hooks::hook_reinstalls().to_string(),
+    ));
+    status.extend([
+        (
+            "update_mode".to_string(),
+            mode_to_str(policy::effective_mode(Operation::Update, None)).to_string(),
+        ),
+        (
+            "delete_mode".to_string(),
+            mode_to_str(policy::effective_mode(Operation::Delete, None)).to_string(),
+        ),
+        (
+            "truncate_mode".to_string(),
+            mode_to_str(policy::resolve(GUARD_TRUNCATE_GUC, guard_truncate_mode())).to_string(),
+        ),
+        (
+            "protect_catalogs".to_string(),
+            mode_to_str(protect_catalogs_mode()).to_string(),
+        ),
+        (
+            "enforce".to_string(),
+            enforce_to_str(current_enforce()).to_string(),
+        ),
+        (
+            "preset".to_string(),
+            preset_to_str(current_preset()).to_string(),
+        ),
+    ]);
+    TableIterator::new(status)
+}
+
+fn server_version() -> String {
+    let version = unsafe { pg_sys::GetConfigOption(c"server_version".as_ptr(), false, false) };
+    if version.is_null() {
+        return String::new();
+    }
+    unsafe { std::ffi::CStr::from_ptr(version) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The PostgreSQL major version this library was compiled against.
+fn built_for() -> &'static str {
+    if cfg!(feature = "pg13") {
+        "PostgreSQL 13"
+    } else if cfg!(feature = "pg14") {
+        "PostgreSQL 14"
+    } else if cfg!(feature = "pg15") {
+        "PostgreSQL 15"
+    } else if cfg!(feature = "pg16") {
+        "PostgreSQL 16"
+    } else if cfg!(feature = "pg17") {
+        "PostgreSQL 17"
+    } else {
+        "PostgreSQL 18"
+    }
+}
+
+/// Sets the mode of the rule for `operation`. `session` and `local` behave
+/// like `SET` and `SET LOCAL`; `database` and `role` store the setting for new
+/// sessions of the current database or role, like `ALTER DATABASE ... SET`.
+#[pg_extern]
+pub(crate) fn pg_strict_set_mode(
+    operation: &str,
+    mode: &str,
+    scope: default!(&str, "'session'"),
+) -> bool {
+    let operation = operation.trim().to_ascii_lowercase();
+    let Some((_, guc_name)) = MODE_SETTINGS.iter().find(|(name, _)| *name == operation) else {
+        pgrx::warning!(
+            "Invalid operation '{}'. Use {}.",
+            operation,
+            MODE_SETTINGS
+                .iter()
+                .map(|(name, _)| format!("'{name}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        return false;
+    };
+    set_mode(guc_name, mode, scope)
+}
+
+/// Binds one of the predefined policies to `role` with `ALTER ROLE ... SET`,
+/// returning the commands it ran. The settings apply to the role's new sessions.
+#[pg_extern]
+pub(crate) fn pg_strict_apply_role_policy(
+    role: &str,
+    policy: &str,
+) -> SetOfIterator<'static, String> {
+    let policy = policy.trim().to_ascii_lowercase().replace('_', "-");
+    let Some((_, settings)) = ROLE_POLICIES.iter().find(|(name, _)| *name == policy) else {
+        pgrx::error!(
+            "pg_strict: unknown role policy '{}'. Use 'strict', 'warn-only', or 'exempt'.",
+            policy
+        );
+    };
+
+    let commands: Vec<String> = settings
+        .iter()
+        .map(|(guc_name, mode)| {
+            format!(
+                "ALTER ROLE {} SET {} = '{}'",
+                quote_identifier(role),
+                guc_name,
+                mode
+            )
+        })
+        .collect();
+    for command in &commands {
+        if let Err(error) = Spi::run(command) {
+            pgrx::error!("pg_strict: could not apply role policy: {}", error);
+        }
+    }
+    SetOfIterator::new(commands)
+}
+
+/// Writes a pg_strict setting to postgresql.auto.conf, as `ALTER SYSTEM` would,
+/// and by default reloads the configuration. A NULL value removes the setting.
+#[pg_extern]
+pub(crate) fn pg_strict_persist(
+    setting: &str,
+    value: Option<&str>,
+    reload: default!(bool, true),
+) -> bool {
+    require_superuser("pg_strict_persist");
+    let setting = setting.trim().to_ascii_lowercase();
+    let setting = if setting.starts_with("pg_strict.") {
+        setting
+    } else {
+        format!("pg_strict.{setting}")
+    };
+    if !setting
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    {
+        pgrx::error!("pg_strict: invalid setting name \"{}\".", setting);
+    }
+
+    // The file is not rolled back with the transaction, so refuse to write it
+    // from one that may still roll back, as ALTER SYSTEM does.
+    unsafe { pg_sys::PreventInTransactionBlock(true, c"pg_strict_persist".as_ptr()) };
+
+    let command = match value {
+        Some(value) => format!("ALTER SYSTEM SET {} = {}", setting, quote_literal(value)),
+        None => format!("ALTER SYSTEM RESET {}", setting),
+    };
+    unsafe { alter_system(&command) };
+    if reload {
+        Spi::run("SELECT pg_catalog.pg_reload_conf()").expect("pg_reload_conf() should not fail");
+    }
+    true
+}
+
+// ALTER SYSTEM refuses to run inside a function, so the statement is parsed
+// here and handed straight to the code that rewrites postgresql.auto.conf.
+// The file is written at once and not rolled back, which is why the caller
+// refuses to run inside a transaction block first.
+unsafe fn alter_system(command: &str) {
+    let command = std::ffi::CString::new(command).expect("setting values contain no NUL");
+    let statements =
+        unsafe { PgList::<pg_sys::RawStmt>::from_pg(pg_sys::pg_parse_query(command.as_ptr())) };
+    let Some(raw) = statements.get_ptr(0) else {
+        pgrx::error!("pg_strict: could not parse ALTER SYSTEM.");
+    };
+    let stmt = unsafe { (*raw).stmt };
+    if stmt.is_null() || unsafe { (*stmt).type_ } != pg_sys::NodeTag::T_AlterSystemStmt {
+        pgrx::error!("pg_strict: could not parse ALTER SYSTEM.");
+    }
+    unsafe { pg_sys::AlterSystemSetConfigFile(stmt as *mut pg_sys::AlterSystemStmt) };
+}
+
+/// Called by the triggers on strict.rules and strict.custom_rules.
+#[pg_extern]
+pub(crate) fn pg_strict_invalidate_rules() {
+    rules::invalidate();
+}
+
+/// The check constraint on strict.custom_rules.pattern: true, or an error
+/// saying what is wrong with the pattern.
+#[pg_extern(immutable, parallel_safe)]
+pub(crate) fn pg_strict_valid_pattern(pattern: &str) -> bool {
+    if let Err(error) = custom_rules::compile(pattern) {
+        pgrx::error!("pg_strict: invalid pattern: {}", error);
+    }
+    true
+}
+
+// Site-specific prohibitions matched against the text of every statement
+// after the built-in checks. Changes reach other backends like those to
+// strict.rules.
+extension_sql!(
+    r#"
+CREATE TABLE strict.custom_rules (
+    id bigserial PRIMARY KEY,
+    pattern text NOT NULL CHECK (pg_strict_valid_pattern(pattern)),
+    action text NOT NULL DEFAULT 'block' CHECK (action IN ('warn', 'block')),
+    message text NOT NULL,
+    enabled boolean NOT NULL DEFAULT true,
+    note text,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    created_by name NOT NULL DEFAULT session_user
+);
+REVOKE ALL ON strict.custom_rules FROM PUBLIC;
+GRANT SELECT ON strict.custom_rules TO PUBLIC;
+
+CREATE TRIGGER custom_rules_changed
+AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON strict.custom_rules
+FOR EACH STATEMENT EXECUTE FUNCTION strict.rules_changed();
+"#,
+    name = "strict_custom_rules",
+    requires = ["strict_rules", pg_strict_valid_pattern]
+);
+
 #[pg_extern]
 pub(crate) fn pg_strict_set_update_mode(mode: &str) -> bool {
-    set_mode("pg_strict.require_where_on_update", mode)
+    pg_strict_set_mode("update", mode, "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_set_delete_mode(mode: &str) -> bool {
-    set_mode("pg_strict.require_where_on_delete", mode)
+    pg_strict_set_mode("delete", mode, "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_enable_update() -> bool {
-    set_mode("pg_strict.require_where_on_update", "on")
+    pg_strict_set_mode("update", "on", "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_enable_delete() -> bool {
-    set_mode("pg_strict.require_where_on_delete", "on")
+    pg_strict_set_mode("delete", "on", "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_disable_update() -> bool {
-    set_mode("pg_strict.require_where_on_update", "off")
+    pg_strict_set_mode("update", "off", "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_disable_delete() -> bool {
-    set_mode("pg_strict.require_where_on_delete", "off")
+    pg_strict_set_mode("delete", "off", "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_warn_update() -> bool {
-    set_mode("pg_strict.require_where_on_update", "warn")
+    pg_strict_set_mode("update", "warn", "session")
 }
 
 #[pg_extern]
 pub(crate) fn pg_strict_warn_delete() -> bool {
-    set_mode("pg_strict.require_where_on_delete", "warn")
+    pg_strict_set_mode("delete", "warn", "session")
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_allow_next(reason: default!(Option<&str>, "NULL")) -> bool {
+    let reason = bypass::validate_reason(reason);
+    bypass::arm_allow_next(reason);
+    true
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_snooze(duration: Interval, reason: Option<&str>) -> TimestampWithTimeZone {
+    let reason = bypass::validate_reason(reason);
+    let usecs = bypass::interval_usecs(duration.months(), duration.days(), duration.micros());
+    let until = bypass::snooze(usecs, reason);
+    unsafe { TimestampWithTimeZone::from_datum(pg_sys::Datum::from(until), false) }
+        .expect("snooze expiry should be a valid timestamptz")
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_unsnooze() -> bool {
+    bypass::unsnooze()
+}
+
+#[pg_extern(security_definer)]
+pub(crate) fn pg_strict_request_approval(query: &str) -> i64 {
+    approval::request(query)
+}
+
+#[pg_extern(security_definer)]
+pub(crate) fn pg_strict_approve(request_id: i64) -> bool {
+    approval::approve(request_id)
+}
+
+extension_sql!(
+    r#"
+REVOKE EXECUTE ON FUNCTION pg_strict_approve(bigint) FROM PUBLIC;
+"#,
+    name = "pg_strict_approve_privileges",
+    requires = [pg_strict_approve]
+);
+
+#[pg_extern]
+pub(crate) fn pg_strict_allow_queryid(query_id: i64) -> bool {
+    require_superuser("pg_strict_allow_queryid");
+    shmem::require();
+    if crate::guc::query_ids_disabled() {
+        pgrx::error!(
+            "pg_strict: {} Set compute_query_id = on first.",
+            crate::guc::QUERY_IDS_DISABLED
+        );
+    }
+    let added = shmem::QUERYID_ALLOWLIST.exclusive().insert(query_id);
+    if !added {
+        pgrx::warning!(
+            "pg_strict: query identifier allowlist is full ({} entries).",
+            shmem::MAX_ALLOWED_QUERYIDS
+        );
+    }
+    added
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_disallow_queryid(query_id: i64) -> bool {
+    require_superuser("pg_strict_disallow_queryid");
+    shmem::require();
+    shmem::QUERYID_ALLOWLIST.exclusive().remove(query_id)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_allowed_queryids() -> SetOfIterator<'static, i64> {
+    let mut ids = crate::guc::configured_allowed_queryids();
+    if shmem::available() {
+        for id in shmem::QUERYID_ALLOWLIST.share().ids() {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    SetOfIterator::new(ids)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_stats() -> TableIterator<
+    'static,
+    (
+        name!(statements_checked, i64),
+        name!(violations_warned, i64),
+        name!(violations_blocked, i64),
+        name!(parse_failures, i64),
+        name!(bypasses, i64),
+    ),
+> {
+    shmem::require();
+    let stats = stats::snapshot();
+    TableIterator::once((
+        stats.statements_checked,
+        stats.violations_warned,
+        stats.violations_blocked,
+        stats.parse_failures,
+        stats.bypasses,
+    ))
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_table_stats() -> TableIterator<
+    'static,
+    (
+        name!(database, pg_sys::Oid),
+        name!(relid, pg_sys::Oid),
+        name!(relation, Option<String>),
+        name!(update_violations, i64),
+        name!(delete_violations, i64),
+        name!(blocked, i64),
+    ),
+> {
+    shmem::require();
+    let current_database = unsafe { pg_sys::MyDatabaseId };
+    let rows = stats::table_snapshot()
+        .into_iter()
+        .map(|entry| {
+            let database = pg_sys::Oid::from(entry.database);
+            let relid = pg_sys::Oid::from(entry.relid);
+            // OIDs of other databases' relations cannot be resolved from here.
+            let relation =
+                (database == current_database).then(|| catalog::qualified_relation_name(relid));
+            (
+                database,
+                relid,
+                relation,
+                entry.update_violations,
+                entry.delete_violations,
+                entry.blocked,
+            )
+        })
+        .collect::<Vec<_>>();
+    TableIterator::new(rows)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_top_offenders(
+    limit: default!(i32, 10),
+) -> TableIterator<
+    'static,
+    (
+        name!(queryid, i64),
+        name!(query, String),
+        name!(violations, i64),
+        name!(last_seen, TimestampWithTimeZone),
+    ),
+> {
+    shmem::require();
+    let rows = stats::top_offenders(limit.max(0) as usize)
+        .into_iter()
+        .map(|offender| {
+            let last_seen = unsafe {
+                TimestampWithTimeZone::from_datum(pg_sys::Datum::from(offender.last_seen), false)
+            }
+            .expect("timestamp datum is never null");
+            (
+                offender.query_id,
+                offender.query(),
+                offender.violations,
+                last_seen,
+            )
+        })
+        .collect::<Vec<_>>();
+    TableIterator::new(rows)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_reset_stats() -> bool {
+    let monitor = unsafe {
+        pg_sys::has_privs_of_role(
+            pg_sys::GetUserId(),
+            pg_sys::Oid::from(pg_sys::ROLE_PG_MONITOR),
+        )
+    };
+    if !monitor && !unsafe { pg_sys::superuser() } {
+        pgrx::error!(
+            "pg_strict: pg_strict_reset_stats requires superuser or pg_monitor privileges."
+        );
+    }
+    shmem::require();
+    stats::reset();
+    true
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_audit_statements() -> TableIterator<
+    'static,
+    (
+        name!(queryid, i64),
+        name!(operation, String),
+        name!(calls, i64),
+        name!(query, String),
+    ),
+> {
+    let schema = Spi::get_one::<String>(
+        "SELECT n.nspname::text FROM pg_extension e \
+         JOIN pg_namespace n ON n.oid = e.extnamespace \
+         WHERE e.extname = 'pg_stat_statements'",
+    )
+    .ok()
+    .flatten();
+    let Some(schema) = schema else {
+        pgrx::error!("pg_strict: pg_stat_statements is not installed in this database.");
+    };
+
+    let sql = format!(
+        "SELECT queryid, calls, query FROM {}.pg_stat_statements \
+         WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database()) \
+         AND query IS NOT NULL",
+        pgrx::spi::quote_identifier(&schema)
+    );
+    let rows = Spi::connect(|client| {
+        let mut rows = Vec::new();
+        for row in client.select(&sql, None, &[])? {
+            let query_id = row.get::<i64>(1)?.unwrap_or_default();
+            let calls = row.get::<i64>(2)?.unwrap_or_default();
+            let Some(query) = row.get::<String>(3)? else {
+                continue;
+            };
+            // Texts pg_stat_statements truncated or could not normalize are skipped.
+            let Ok(analyzer) = QueryAnalyzer::new(&query) else {
+                continue;
+            };
+            for operation in analyzer.missing_where_operations() {
+                rows.push((
+                    query_id,
+                    operation.as_str().to_string(),
+                    calls,
+                    query.clone(),
+                ));
+            }
+        }
+        Ok::<_, pgrx::spi::Error>(rows)
+    })
+    .unwrap_or_else(|error| {
+        pgrx::error!("pg_strict: could not read pg_stat_statements: {}", error)
+    });
+
+    TableIterator::new(rows)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_active_violations() -> TableIterator<
+    'static,
+    (
+        name!(pid, i32),
+        name!(usename, Option<String>),
+        name!(datname, Option<String>),
+        name!(operation, String),
+        name!(duration, Option<Interval>),
+        name!(query, String),
+    ),
+> {
+    let rows = activity::active_violations()
+        .into_iter()
+        .map(|active| {
+            (
+                active.pid,
+                active.username,
+                active.database,
+                active.operation.to_string(),
+                active.duration,
+                active.query,
+            )
+        })
+        .collect::<Vec<_>>();
+    TableIterator::new(rows)
+}
+
+#[pg_extern]
+pub(crate) fn pg_strict_recommendations() -> TableIterator<
+    'static,
+    (
+        name!(category, String),
+        name!(target, String),
+        name!(recommendation, String),
+        name!(evidence, String),
+    ),
+> {
+    TableIterator::new(learn::recommendations())
+}
+
+fn require_superuser(function_name: &str) {
+    if !unsafe { pg_sys::superuser() } {
+        pgrx::error!(
+            "pg_strict: {} requires superuser privileges.",
+            function_name
+        );
+    }
 }
 
-fn set_mode(guc_name: &str, mode: &str) -> bool {
+fn set_mode(guc_name: &str, mode: &str, scope: &str) -> bool {
     let normalized_mode = mode.trim().to_ascii_lowercase();
 
     if !VALID_MODES.contains(&normalized_mode.as_str()) {
-        pgrx::warning!("Invalid mode '{}'. Use 'off', 'warn', or 'on'.", mode);
+        pgrx::warning!(
+            "Invalid mode '{}'. Use 'off', 'log', 'notice', 'warn', 'on', or 'learn'.",
+            mode
+        );
         return false;
     }
 
-    let set_cmd = format!("SET {} = '{}'", guc_name, normalized_mode);
-    Spi::run(&set_cmd).is_ok()
+    let assignment = format!("{} = '{}'", guc_name, normalized_mode);
+    let alter_cmd = match scope.trim().to_ascii_lowercase().as_str() {
+        "session" => {
+            return set_config(
+                guc_name,
+                &normalized_mode,
+                pg_sys::GucAction::GUC_ACTION_SET,
+            );
+        }
+        "local" => {
+            if !unsafe { pg_sys::IsTransactionBlock() } {
+                pgrx::warning!(
+                    "pg_strict: the local scope only lasts until the end of the current statement outside a transaction block."
+                );
+            }
+            return set_config(
+                guc_name,
+                &normalized_mode,
+                pg_sys::GucAction::GUC_ACTION_LOCAL,
+            );
+        }
+        "database" => format!(
+            "ALTER DATABASE {} SET {assignment}",
+            quote_identifier(&current_database())
+        ),
+        "role" => format!("ALTER ROLE CURRENT_USER SET {assignment}"),
+        _ => {
+            pgrx::warning!(
+                "Invalid scope '{}'. Use 'session', 'local', 'database', or 'role'.",
+                scope
+            );
+            return false;
+        }
+    };
+    if let Err(error) = Spi::run(&alter_cmd) {
+        pgrx::error!("pg_strict: could not store {}: {}", guc_name, error);
+    }
+    true
+}
+
+/// Sets a GUC the way `SET` or `SET LOCAL` would, without going through SPI.
+/// PostgreSQL reports why a value is refused, such as a superuser-only setting.
+fn set_config(guc_name: &str, value: &str, action: pg_sys::GucAction::Type) -> bool {
+    let name = std::ffi::CString::new(guc_name).expect("setting names contain no NUL");
+    let value = std::ffi::CString::new(value).expect("modes contain no NUL");
+    let context = if unsafe { pg_sys::superuser() } {
+        pg_sys::GucContext::PGC_SUSET
+    } else {
+        pg_sys::GucContext::PGC_USERSET
+    };
+    let result = unsafe {
+        pg_sys::set_config_option(
+            name.as_ptr(),
+            value.as_ptr(),
+            context,
+            pg_sys::GucSource::PGC_S_SESSION,
+            action,
+            true,
+            pg_sys::ERROR as i32,
+            false,
+        )
+    };
+    result > 0
+}
+
+fn current_database() -> String {
+    let name = unsafe { pg_sys::get_database_name(pg_sys::MyDatabaseId) };
+    if name.is_null() {
+        pgrx::error!("pg_strict: could not determine the current database.");
+    }
+    unsafe { std::ffi::CStr::from_ptr(name) }
+        .to_string_lossy()
+        .into_owned()
 }
 
 fn parse_operation(stmt_type: &str) -> Option<Operation> {
@@ -116,6 +1296,14 @@ fn parse_operation(stmt_type: &str) -> Option<Operation> {
     }
 }
 
+fn require_single_dml(query: &str) -> QueryAnalyzer {
+    match QueryAnalyzer::new(query) {
+        Ok(analyzer) if analyzer.single_operation().is_some() => analyzer,
+        Ok(_) => pgrx::error!("pg_strict: expected a single UPDATE or DELETE statement."),
+        Err(_) => pgrx::error!("pg_strict: failed to parse query."),
+    }
+}
+
 fn validate_operation(
     query: &str,
     operation: Operation,
@@ -123,7 +1311,9 @@ fn validate_operation(
     match QueryAnalyzer::new(query) {
         Ok(analyzer) => {
             if !analyzer.has_where_clause(operation) {
-                pgrx::error!("{}", violation_message(operation));
+                let mut violation = Violation::missing_where(operation, None);
+                violation.message = violation_message(operation);
+                violation.raise(StrictMode::On);
             }
             Ok(true)
         }
@@ -0,0 +1,73 @@
+use crate::analyzer::normalize_statement;
+use crate::schema::{as_extension_owner, extension_installed};
+use pgrx::prelude::*;
+
+pub fn request(query: &str) -> i64 {
+    let fingerprint = normalize_statement(query);
+    if fingerprint.is_empty() {
+        pgrx::error!("pg_strict: cannot request approval for an empty statement.");
+    }
+
+    Spi::get_one_with_args::<i64>(
+        "INSERT INTO strict.approvals (fingerprint, query) VALUES ($1, $2) RETURNING id",
+        &[fingerprint.as_str().into(), query.into()],
+    )
+    .ok()
+    .flatten()
+    .unwrap_or_else(|| pgrx::error!("pg_strict: failed to record approval request."))
+}
+
+pub fn approve(request_id: i64) -> bool {
+    let requester = Spi::get_two_with_args::<String, bool>(
+        "SELECT requested_by::text, approved_at IS NOT NULL FROM strict.approvals WHERE id = $1",
+        &[request_id.into()],
+    );
+    let (requested_by, already_approved) = match requester {
+        Ok((Some(requested_by), Some(already_approved))) => (requested_by, already_approved),
+        _ => pgrx::error!("pg_strict: approval request {} does not exist.", request_id),
+    };
+
+    if already_approved {
+        pgrx::warning!(
+            "pg_strict: approval request {} was already approved.",
+            request_id
+        );
+        return false;
+    }
+
+    let approver = Spi::get_one::<String>("SELECT session_user::text")
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if approver == requested_by {
+        pgrx::error!(
+            "pg_strict: approval request {} must be approved by a role other than {}.",
+            request_id,
+            requested_by
+        );
+    }
+
+    Spi::run_with_args(
+        "UPDATE strict.approvals SET approved_by = session_user, approved_at = now() WHERE id = $1",
+        &[request_id.into()],
+    )
+    .is_ok()
+}
+
+/// Consumes one unexpired approval the session's role requested for
+/// `statement`, if any.
+pub fn consume(statement: &str) -> bool {
+    if !extension_installed() {
+        return false;
+    }
+
+    as_extension_owner(|| {
+        Spi::get_one_with_args::<bool>(
+            "SELECT strict.consume_approval($1)",
+            &[normalize_statement(statement).as_str().into()],
+        )
+    })
+    .ok()
+    .flatten()
+    .unwrap_or(false)
+}
@@ -0,0 +1,167 @@
+use crate::delivery::{self, BlockedViolation, Delivery};
+use crate::exemptions::current_application_name;
+use crate::guc::{StrictMode, audit_enabled, mode_to_str};
+use crate::notify::session_user_name;
+use crate::schema::{as_extension_owner, extension_installed};
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+
+struct Violation {
+    occurred_at: pg_sys::TimestampTz,
+    application_name: String,
+    mode: StrictMode,
+    operation: String,
+    query_id: i64,
+    query: String,
+}
+
+// Blocked violations abort their own transaction. Without shared memory for
+// the delivery worker, they are kept here and written by the next statement
+// the session runs.
+static mut PENDING_VIOLATIONS: Vec<Violation> = Vec::new();
+
+// strict.bypass_log rows still waiting for their statement's row count,
+// tagged with the executor nesting level the statement will finish at.
+static mut PENDING_BYPASS_ROWS: Vec<(u32, i64)> = Vec::new();
+
+/// Records a bypass in `strict.bypass_log`. The row count is filled in when the
+/// statement finishes executing.
+#[allow(static_mut_refs)]
+pub fn log_bypass(mechanism: &str, reason: &str, statement: &str, executor_depth: u32) {
+    if !extension_installed() {
+        return;
+    }
+
+    let id = as_extension_owner(|| {
+        Spi::get_one_with_args::<i64>(
+            "SELECT strict.log_bypass($1, $2, $3)",
+            &[mechanism.into(), reason.into(), statement.into()],
+        )
+    });
+    match id {
+        Ok(Some(id)) => unsafe { PENDING_BYPASS_ROWS.push((executor_depth, id)) },
+        _ => pgrx::warning!("pg_strict: failed to write strict.bypass_log entry."),
+    }
+}
+
+/// Called when an UPDATE/DELETE finishes at `executor_depth`.
+#[allow(static_mut_refs)]
+pub fn record_rows_affected(executor_depth: u32, rows: u64) {
+    let pending = unsafe {
+        let Some(index) = PENDING_BYPASS_ROWS
+            .iter()
+            .position(|(depth, _)| *depth == executor_depth)
+        else {
+            return;
+        };
+        PENDING_BYPASS_ROWS.remove(index)
+    };
+
+    let _ = as_extension_owner(|| {
+        Spi::run_with_args(
+            "SELECT strict.set_bypass_rows($1, $2)",
+            &[pending.1.into(), (rows as i64).into()],
+        )
+    });
+}
+
+#[allow(static_mut_refs)]
+pub fn reset_pending() {
+    unsafe { PENDING_BYPASS_ROWS.clear() };
+}
+
+/// Records a violation in `strict.violations` when `pg_strict.audit` is on.
+/// `statement` is already loggable text.
+#[allow(static_mut_refs)]
+pub fn record_violation(mode: StrictMode, operation: &str, query_id: i64, statement: &str) {
+    if !audit_enabled() || !extension_installed() {
+        return;
+    }
+
+    let violation = Violation {
+        occurred_at: unsafe { pg_sys::GetCurrentTimestamp() },
+        application_name: current_application_name().unwrap_or_default(),
+        mode,
+        operation: operation.to_string(),
+        query_id,
+        query: statement.to_string(),
+    };
+
+    if mode == StrictMode::On {
+        let blocked = BlockedViolation::new(
+            violation.occurred_at,
+            &session_user_name().unwrap_or_default(),
+            &violation.application_name,
+            &violation.operation,
+            violation.query_id,
+            &violation.query,
+        );
+        if !delivery::enqueue(Delivery::Violation(blocked)) {
+            unsafe { PENDING_VIOLATIONS.push(violation) };
+        }
+    } else {
+        insert_violation(&violation);
+    }
+}
+
+/// Writes violations left behind by statements that were blocked.
+#[allow(static_mut_refs)]
+pub fn flush_pending_violations() {
+    if unsafe { PENDING_VIOLATIONS.is_empty() } {
+        return;
+    }
+    if unsafe { pg_sys::IsAbortedTransactionBlockState() } || !extension_installed() {
+        return;
+    }
+
+    let pending = unsafe { std::mem::take(&mut PENDING_VIOLATIONS) };
+    for violation in &pending {
+        insert_violation(violation);
+    }
+}
+
+fn insert_violation(violation: &Violation) {
+    let occurred_at = unsafe {
+        TimestampWithTimeZone::from_datum(pg_sys::Datum::from(violation.occurred_at), false)
+    };
+    let result = as_extension_owner(|| {
+        Spi::run_with_args(
+            "SELECT strict.log_violation($1, $2, $3, $4, $5, $6)",
+            &[
+                occurred_at.into(),
+                violation.application_name.as_str().into(),
+                mode_to_str(violation.mode).into(),
+                violation.operation.as_str().into(),
+                violation.query_id.into(),
+                violation.query.as_str().into(),
+            ],
+        )
+    });
+    if result.is_err() {
+        pgrx::warning!("pg_strict: failed to write strict.violations entry.");
+    }
+}
+
+/// Writes a blocked violation from the delivery worker, on behalf of the
+/// session that ran the statement. The worker connects as the bootstrap
+/// superuser, the only kind of caller whose `p_username` is honoured.
+pub fn write_blocked_violation(violation: &BlockedViolation) {
+    let occurred_at = unsafe {
+        TimestampWithTimeZone::from_datum(pg_sys::Datum::from(violation.occurred_at), false)
+    };
+    let result = Spi::run_with_args(
+        "SELECT strict.log_violation($1, $2, $3, $4, $5, $6, $7)",
+        &[
+            occurred_at.into(),
+            violation.application_name().into(),
+            mode_to_str(StrictMode::On).into(),
+            violation.operation().into(),
+            violation.query_id.into(),
+            violation.query().into(),
+            violation.username().into(),
+        ],
+    );
+    if result.is_err() {
+        pgrx::log!("pg_strict: failed to write strict.violations entry.");
+    }
+}
@@ -0,0 +1,78 @@
+use crate::analyzer::Operation;
+use crate::catalog;
+use crate::exemptions::glob_match;
+use crate::guc::{
+    StrictMode, backup_probe, backup_protected_tables, last_backup_time, require_recent_backup,
+};
+use crate::report::Violation;
+use crate::stats::{self, Counter};
+use crate::subxact;
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+
+/// Rejects a flagged statement on a protected table when the last backup is
+/// older than `pg_strict.require_recent_backup`, or its time is unknown.
+pub fn require_recent(operation: Operation, relid: Option<pg_sys::Oid>) {
+    let window = require_recent_backup();
+    if window == 0 {
+        return;
+    }
+    let Some(relid) = relid else {
+        return;
+    };
+    let table = catalog::qualified_relation_name(relid);
+    let patterns = backup_protected_tables();
+    if !patterns.is_empty() && !patterns.iter().any(|pattern| glob_match(pattern, &table)) {
+        return;
+    }
+
+    let detail = match last_backup_age() {
+        Ok(age) if age <= window as f64 => return,
+        Ok(age) => format!(
+            "The last backup finished {} ago, which exceeds pg_strict.require_recent_backup ({}).",
+            describe_age(age),
+            describe_age(window as f64)
+        ),
+        Err(reason) => format!("The time of the last backup is unknown: {reason}."),
+    };
+    stats::increment(Counter::ViolationsBlocked);
+    Violation::stale_backup(operation, &table, detail).raise(StrictMode::On);
+}
+
+/// Seconds since the last backup, from `pg_strict.backup_probe` or else
+/// `pg_strict.last_backup_time`.
+fn last_backup_age() -> Result<f64, String> {
+    let age = if let Some(probe) = backup_probe() {
+        let sql = format!(
+            "SELECT extract(epoch FROM now() - probe.finished)::float8 FROM ({probe}) AS probe(finished)"
+        );
+        subxact::try_in_subtransaction(|| Spi::get_one::<f64>(&sql).ok())
+            .ok_or("pg_strict.backup_probe failed")?
+    } else if let Some(time) = last_backup_time() {
+        subxact::try_in_subtransaction(|| {
+            Spi::get_one_with_args::<f64>(
+                "SELECT extract(epoch FROM now() - $1::timestamptz)::float8",
+                &[time.as_str().into()],
+            )
+            .ok()
+        })
+        .ok_or("pg_strict.last_backup_time is not a valid timestamp")?
+    } else {
+        return Err("neither pg_strict.last_backup_time nor pg_strict.backup_probe is set".into());
+    };
+    age.ok_or_else(|| "no backup has been recorded".to_string())
+}
+
+/// Renders a number of seconds as, for example, `26 hours`.
+pub fn describe_age(seconds: f64) -> String {
+    let seconds = seconds.max(0.0);
+    if seconds < 120.0 {
+        format!("{seconds:.0} seconds")
+    } else if seconds < 7200.0 {
+        format!("{:.0} minutes", seconds / 60.0)
+    } else if seconds < 172800.0 {
+        format!("{:.0} hours", seconds / 3600.0)
+    } else {
+        format!("{:.0} days", seconds / 86400.0)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::analyzer::Operation;
+use crate::audit;
+use crate::guc::{
+    max_snooze_seconds, override_reason, override_set_locally, require_bypass_reason,
+};
+use crate::logging;
+use crate::stats::{self, Counter};
+use pgrx::pg_sys;
+use std::ffi::CStr;
+
+const USECS_PER_SEC: i64 = 1_000_000;
+const USECS_PER_DAY: i64 = 86_400 * USECS_PER_SEC;
+
+// Reason attached to a pending pg_strict_allow_next() call, if one is armed.
+static mut ALLOW_NEXT: Option<String> = None;
+// Expiry timestamp and reason of the current pg_strict_snooze(), if any.
+static mut SNOOZE: Option<(pg_sys::TimestampTz, String)> = None;
+// Start timestamp of the statement last warned about an ignored override, so
+// the checks evaluating it for one statement warn only once.
+static mut OVERRIDE_WARNED_AT: pg_sys::TimestampTz = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mechanism {
+    AllowNext,
+    Override,
+    Snooze,
+    Approval,
+}
+
+impl Mechanism {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mechanism::AllowNext => "allow_next",
+            Mechanism::Override => "override",
+            Mechanism::Snooze => "snooze",
+            Mechanism::Approval => "approval",
+        }
+    }
+}
+
+/// Normalizes a user-supplied reason, enforcing `pg_strict.require_bypass_reason`.
+pub fn validate_reason(reason: Option<&str>) -> String {
+    let reason = reason.map(str::trim).unwrap_or_default();
+    if reason.is_empty() && require_bypass_reason() {
+        pgrx::error!(
+            "pg_strict: a reason is required to bypass enforcement (pg_strict.require_bypass_reason is on)."
+        );
+    }
+    reason.to_string()
+}
+
+#[allow(static_mut_refs)]
+pub fn arm_allow_next(reason: String) {
+    unsafe { ALLOW_NEXT = Some(reason) };
+}
+
+#[allow(static_mut_refs)]
+pub fn take_allow_next() -> Option<String> {
+    unsafe { ALLOW_NEXT.take() }
+}
+
+/// Disables enforcement for this session until `duration_usecs` from now and
+/// returns the expiry timestamp.
+#[allow(static_mut_refs)]
+pub fn snooze(duration_usecs: i64, reason: String) -> pg_sys::TimestampTz {
+    if duration_usecs <= 0 {
+        pgrx::error!("pg_strict: snooze duration must be positive.");
+    }
+
+    let max_seconds = max_snooze_seconds();
+    if duration_usecs > i64::from(max_seconds) * USECS_PER_SEC {
+        pgrx::error!(
+            "pg_strict: snooze duration exceeds pg_strict.max_snooze_duration ({} seconds).",
+            max_seconds
+        );
+    }
+
+    let until = unsafe { pg_sys::GetCurrentTimestamp() } + duration_usecs;
+    pgrx::notice!(
+        "pg_strict: enforcement snoozed until {}.",
+        format_timestamptz(until)
+    );
+    unsafe { SNOOZE = Some((until, reason)) };
+    until
+}
+
+#[allow(static_mut_refs)]
+pub fn unsnooze() -> bool {
+    unsafe { SNOOZE.take().is_some() }
+}
+
+/// The reason of an unexpired snooze. Expired snoozes are cleared, re-enabling enforcement.
+#[allow(static_mut_refs)]
+pub fn active_snooze() -> Option<String> {
+    let (until, reason) = unsafe { SNOOZE.clone()? };
+    if unsafe { pg_sys::GetCurrentTimestamp() } >= until {
+        unsafe { SNOOZE = None };
+        return None;
+    }
+    Some(reason)
+}
+
+/// Converts an interval to microseconds, counting a month as 30 days like PostgreSQL does.
+pub fn interval_usecs(months: i32, days: i32, micros: i64) -> i64 {
+    (i64::from(months) * 30 + i64::from(days)) * USECS_PER_DAY + micros
+}
+
+fn format_timestamptz(timestamp: pg_sys::TimestampTz) -> String {
+    unsafe {
+        let text = pg_sys::timestamptz_to_str(timestamp);
+        CStr::from_ptr(text).to_string_lossy().into_owned()
+    }
+}
+
+/// The `pg_strict.override` reason, honoured only when set with SET LOCAL inside an
+/// explicit transaction block, so that it lapses at COMMIT/ROLLBACK.
+pub fn active_override() -> Option<String> {
+    let reason = override_reason()?;
+    if !unsafe { pg_sys::IsTransactionBlock() } || !override_set_locally() {
+        let statement_start = unsafe { pg_sys::GetCurrentStatementStartTimestamp() };
+        if unsafe { OVERRIDE_WARNED_AT } != statement_start {
+            unsafe { OVERRIDE_WARNED_AT = statement_start };
+            pgrx::warning!(
+                "pg_strict: pg_strict.override is ignored unless set with SET LOCAL inside a transaction block. Use SET LOCAL inside BEGIN ... COMMIT."
+            );
+        }
+        return None;
+    }
+    Some(reason)
+}
+
+pub fn report_bypass(
+    mechanism: Mechanism,
+    reason: &str,
+    operation: Operation,
+    statement: &str,
+    executor_depth: u32,
+) {
+    stats::increment(Counter::Bypasses);
+    let statement = logging::loggable_statement(statement);
+    audit::log_bypass(mechanism.as_str(), reason, &statement, executor_depth);
+
+    let reason = if reason.is_empty() {
+        "no reason given"
+    } else {
+        reason
+    };
+    pgrx::notice!(
+        "pg_strict: {} statement without WHERE clause allowed by {} ({}).",
+        operation.as_str(),
+        mechanism.as_str(),
+        reason
+    );
+    pgrx::log!(
+        "pg_strict: bypass {} used for {} statement without WHERE clause: {} (statement: {})",
+        mechanism.as_str(),
+        operation.as_str(),
+        reason,
+        statement
+    );
+}
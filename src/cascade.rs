@@ -0,0 +1,20 @@
+use crate::schema::extension_installed;
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+
+/// One line summary of the tables a DELETE on `relid` reaches through foreign
+/// keys, for violation details. `None` when there are none.
+pub fn summary(relid: pg_sys::Oid) -> Option<String> {
+    if !extension_installed() {
+        return None;
+    }
+
+    Spi::get_one_with_args::<String>(
+        "SELECT string_agg(format('%s (%s, ~%s rows)', target_table, lower(on_delete), \
+                                  coalesce(round(estimated_rows)::bigint::text, '?')), ', ') \
+         FROM strict.cascade_targets($1)",
+        &[relid.into()],
+    )
+    .ok()
+    .flatten()
+}
@@ -0,0 +1,181 @@
+use crate::analyzer::Operation;
+use pgrx::PgList;
+use pgrx::Spi;
+use pgrx::pg_sys;
+use pgrx::spi::quote_identifier;
+use std::ffi::CStr;
+
+/// Returns the OID of the relation an analyzed INSERT/UPDATE/DELETE writes to.
+pub unsafe fn target_relation(query: *mut pg_sys::Query) -> Option<pg_sys::Oid> {
+    if query.is_null() {
+        return None;
+    }
+
+    let result_relation = unsafe { (*query).resultRelation };
+    if result_relation <= 0 {
+        return None;
+    }
+
+    let rtable = unsafe { PgList::<pg_sys::RangeTblEntry>::from_pg((*query).rtable) };
+    let rte = rtable.get_ptr((result_relation - 1) as usize)?;
+    Some(unsafe { (*rte).relid })
+}
+
+pub fn relation_namespace(relid: pg_sys::Oid) -> Option<String> {
+    unsafe {
+        let namespace = pg_sys::get_namespace_name(pg_sys::get_rel_namespace(relid));
+        if namespace.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(namespace).to_string_lossy().into_owned())
+    }
+}
+
+pub fn relation_name(relid: pg_sys::Oid) -> Option<String> {
+    let name = unsafe { pg_sys::get_rel_name(relid) };
+    if name.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+pub fn qualified_relation_name(relid: pg_sys::Oid) -> String {
+    let Some(name) = relation_name(relid) else {
+        return relid.as_u32().to_string();
+    };
+    match relation_namespace(relid) {
+        Some(namespace) => format!("{namespace}.{name}"),
+        None => name,
+    }
+}
+
+/// The schema-qualified name of the relation with each part quoted as an
+/// identifier where needed, for SQL suggested to the user.
+pub fn quoted_relation_name(relid: pg_sys::Oid) -> String {
+    let Some(name) = relation_name(relid) else {
+        return relid.as_u32().to_string();
+    };
+    match relation_namespace(relid) {
+        Some(namespace) => format!(
+            "{}.{}",
+            quote_identifier(&namespace),
+            quote_identifier(&name)
+        ),
+        None => quote_identifier(&name),
+    }
+}
+
+pub fn is_system_catalog(relid: pg_sys::Oid) -> bool {
+    relation_namespace(relid).as_deref() == Some("pg_catalog")
+}
+
+/// The primary key columns of the relation in key order, quoted as
+/// identifiers. Empty when it has no primary key.
+pub fn primary_key_columns(relid: pg_sys::Oid) -> Vec<String> {
+    Spi::get_one_with_args::<Vec<String>>(
+        "SELECT array_agg(quote_ident(a.attname) ORDER BY k.ord) \
+         FROM pg_catalog.pg_index i \
+         CROSS JOIN LATERAL unnest(i.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord) \
+         JOIN pg_catalog.pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum \
+         WHERE i.indrelid = $1 AND i.indisprimary",
+        &[relid.into()],
+    )
+    .ok()
+    .flatten()
+    .unwrap_or_default()
+}
+
+/// True when a foreign key of another table references the relation, so a
+/// plain TRUNCATE of it would fail.
+pub fn is_referenced(relid: pg_sys::Oid) -> bool {
+    Spi::get_one_with_args::<bool>(
+        "SELECT EXISTS (SELECT 1 FROM pg_catalog.pg_constraint \
+                        WHERE confrelid = $1 AND contype = 'f' AND conrelid <> $1)",
+        &[relid.into()],
+    )
+    .ok()
+    .flatten()
+    .unwrap_or(false)
+}
+
+/// True when an enabled, unconditional INSTEAD rule replaces `command` on the
+/// relation with actions that neither update nor delete, so the statement as
+/// written never modifies anything (`DO INSTEAD NOTHING`, or an INSERT into a
+/// log table).
+pub fn replaced_by_harmless_rule(relid: pg_sys::Oid, command: pg_sys::CmdType::Type) -> bool {
+    unsafe {
+        let relation = pg_sys::RelationIdGetRelation(relid);
+        if relation.is_null() {
+            return false;
+        }
+        let locks = (*relation).rd_rules;
+        let count = if locks.is_null() {
+            0
+        } else {
+            (*locks).numLocks.max(0) as usize
+        };
+        let replaced = (0..count).any(|i| {
+            let rule = *(*locks).rules.add(i);
+            // RULE_DISABLED; session_replication_role is not taken into account.
+            (*rule).event == command
+                && (*rule).isInstead
+                && (*rule).qual.is_null()
+                && (*rule).enabled as u8 != b'D'
+                && !PgList::<pg_sys::Query>::from_pg((*rule).actions)
+                    .iter_ptr()
+                    .any(|action| {
+                        matches!(
+                            (*action).commandType,
+                            pg_sys::CmdType::CMD_UPDATE | pg_sys::CmdType::CMD_DELETE
+                        )
+                    })
+        });
+        pg_sys::RelationClose(relation);
+        replaced
+    }
+}
+
+/// The UPDATE and DELETE actions of the rule `rule_id` on the relation that
+/// have neither a WHERE clause of their own nor a rule condition, with the
+/// relation each one writes to.
+pub fn unconditional_rule_actions(
+    relid: pg_sys::Oid,
+    rule_id: pg_sys::Oid,
+) -> Vec<(Operation, Option<pg_sys::Oid>)> {
+    let mut found = Vec::new();
+    unsafe {
+        let relation = pg_sys::RelationIdGetRelation(relid);
+        if relation.is_null() {
+            return found;
+        }
+        let locks = (*relation).rd_rules;
+        let count = if locks.is_null() {
+            0
+        } else {
+            (*locks).numLocks.max(0) as usize
+        };
+        for i in 0..count {
+            let rule = *(*locks).rules.add(i);
+            if (*rule).ruleId != rule_id || !(*rule).qual.is_null() {
+                continue;
+            }
+            for action in PgList::<pg_sys::Query>::from_pg((*rule).actions).iter_ptr() {
+                let operation = match (*action).commandType {
+                    pg_sys::CmdType::CMD_UPDATE => Operation::Update,
+                    pg_sys::CmdType::CMD_DELETE => Operation::Delete,
+                    _ => continue,
+                };
+                let jointree = (*action).jointree;
+                if jointree.is_null() || (*jointree).quals.is_null() {
+                    found.push((operation, target_relation(action)));
+                }
+            }
+        }
+        pg_sys::RelationClose(relation);
+    }
+    found
+}
@@ -0,0 +1,146 @@
+use crate::analyzer::{normalize_statement, redact_literals};
+use crate::guc::StrictMode;
+use crate::report::Violation;
+use crate::rules;
+use crate::schema;
+use crate::shmem;
+use pgrx::prelude::*;
+use regex::{Regex, RegexBuilder};
+use std::sync::atomic::Ordering;
+
+/// An enabled row of `strict.custom_rules`, with its pattern compiled.
+#[derive(Clone)]
+pub struct CustomRule {
+    pub id: i64,
+    pub pattern: Regex,
+    pub mode: StrictMode,
+    pub message: String,
+}
+
+// Custom rules as last loaded by this backend. They share the generation
+// counter of strict.rules, whose trigger function both tables call.
+static mut CACHE: Option<(u64, Vec<CustomRule>)> = None;
+// Set while the rules are being read, so the SPI query is not checked itself.
+static mut LOADING: bool = false;
+
+const LOAD_SQL: &str = "SELECT id, pattern, action, message FROM strict.custom_rules \
+     WHERE enabled ORDER BY id";
+
+/// Compiles a pattern the way it is matched: case-insensitive, since SQL
+/// keywords and unquoted identifiers are. `(?-i)` turns that off.
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// The rules among `rules` whose pattern matches `statement`, with the name
+/// they are recorded under, in id order. Patterns see the statement the way
+/// pg_stat_statements shows it: literals and comments replaced by `$n` and
+/// whitespace collapsed, so the text of a rule never matches itself when it
+/// is inserted or removed.
+pub fn check(rules: Vec<CustomRule>, statement: &str) -> Vec<(String, StrictMode, Violation)> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let statement = normalize_statement(&redact_literals(statement));
+    rules
+        .into_iter()
+        .filter(|rule| rule.pattern.is_match(&statement))
+        .map(|rule| {
+            let violation = Violation::custom_rule(rule.id, &rule.message, rule.pattern.as_str());
+            (format!("custom rule {}", rule.id), rule.mode, violation)
+        })
+        .collect()
+}
+
+/// Drops the cached rules; `rules` calls this whenever it drops its own.
+pub fn invalidate() {
+    unsafe { CACHE = None };
+}
+
+/// The enabled rules, loaded once per change of strict.rules or
+/// strict.custom_rules.
+#[allow(static_mut_refs)]
+pub fn current_rules() -> Vec<CustomRule> {
+    if unsafe { LOADING } {
+        return Vec::new();
+    }
+    if rules::changed_in_transaction() {
+        let _loading = LoadingGuard::enter();
+        return load();
+    }
+    let generation = if shmem::available() {
+        shmem::RULES_GENERATION.get().load(Ordering::Acquire)
+    } else {
+        0
+    };
+    unsafe {
+        if let Some((loaded_at, rules)) = CACHE.as_ref() {
+            if *loaded_at == generation {
+                return rules.clone();
+            }
+        }
+    }
+    let rules = {
+        let _loading = LoadingGuard::enter();
+        load()
+    };
+    rules::track_relation();
+    unsafe { CACHE = Some((generation, rules.clone())) };
+    rules
+}
+
+struct LoadingGuard;
+
+impl LoadingGuard {
+    fn enter() -> Self {
+        unsafe { LOADING = true };
+        LoadingGuard
+    }
+}
+
+impl Drop for LoadingGuard {
+    fn drop(&mut self) {
+        unsafe { LOADING = false };
+    }
+}
+
+fn load() -> Vec<CustomRule> {
+    if !schema::extension_installed() {
+        return Vec::new();
+    }
+    let exists = Spi::get_one::<bool>("SELECT to_regclass('strict.custom_rules') IS NOT NULL")
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+    if !exists {
+        return Vec::new();
+    }
+
+    Spi::connect(|client| {
+        let mut rules = Vec::new();
+        for row in client.select(LOAD_SQL, None, &[])? {
+            let id = row.get::<i64>(1)?.unwrap_or_default();
+            let pattern = row.get::<String>(2)?.unwrap_or_default();
+            // The table's check constraint rejects invalid patterns, so this
+            // only skips rows written while the constraint was disabled.
+            let Ok(pattern) = compile(&pattern) else {
+                continue;
+            };
+            let mode = match row.get::<String>(3)?.as_deref() {
+                Some("warn") => StrictMode::Warn,
+                _ => StrictMode::On,
+            };
+            rules.push(CustomRule {
+                id,
+                pattern,
+                mode,
+                message: row.get::<String>(4)?.unwrap_or_default(),
+            });
+        }
+        Ok::<_, pgrx::spi::Error>(rules)
+    })
+    .unwrap_or_else(|error| {
+        pgrx::warning!("pg_strict: could not load strict.custom_rules: {}", error);
+        Vec::new()
+    })
+}
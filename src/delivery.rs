@@ -0,0 +1,350 @@
+use crate::audit;
+use crate::notify;
+use crate::schema::extension_installed;
+use crate::shmem;
+use crate::subxact;
+use pgrx::bgworkers::{BackgroundWorker, BackgroundWorkerBuilder, SignalWakeFlags};
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use pgrx::shmem::PGRXSharedMemory;
+
+pub const DELIVERY_QUEUE_LEN: usize = 64;
+const MAX_WORKERS: usize = 8;
+const QUERY_LEN: usize = 1024;
+// A worker that was requested but never started is given up on after this
+// long, so the next violation in its database requests another one.
+const START_TIMEOUT_USECS: i64 = 60 * 1_000_000;
+
+/// Text of at most `N` bytes, cut at a character boundary.
+#[derive(Copy, Clone)]
+struct Text<const N: usize> {
+    len: usize,
+    bytes: [u8; N],
+}
+
+impl<const N: usize> Text<N> {
+    fn new(value: &str) -> Self {
+        let mut len = value.len().min(N);
+        while !value.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut bytes = [0; N];
+        bytes[..len].copy_from_slice(&value.as_bytes()[..len]);
+        Self { len, bytes }
+    }
+
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Default for Text<N> {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+/// The violation of a blocked statement, waiting to be written to
+/// strict.violations of its database.
+#[derive(Copy, Clone, Default)]
+pub struct BlockedViolation {
+    pub occurred_at: pg_sys::TimestampTz,
+    pub query_id: i64,
+    username: Text<64>,
+    application_name: Text<64>,
+    operation: Text<32>,
+    query: Text<QUERY_LEN>,
+}
+
+impl BlockedViolation {
+    /// Statements longer than 1 kB are stored truncated.
+    pub fn new(
+        occurred_at: pg_sys::TimestampTz,
+        username: &str,
+        application_name: &str,
+        operation: &str,
+        query_id: i64,
+        query: &str,
+    ) -> Self {
+        Self {
+            occurred_at,
+            query_id,
+            username: Text::new(username),
+            application_name: Text::new(application_name),
+            operation: Text::new(operation),
+            query: Text::new(query),
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        self.username.as_str()
+    }
+
+    pub fn application_name(&self) -> &str {
+        self.application_name.as_str()
+    }
+
+    pub fn operation(&self) -> &str {
+        self.operation.as_str()
+    }
+
+    pub fn query(&self) -> &str {
+        self.query.as_str()
+    }
+}
+
+/// The notification of a blocked statement, waiting to be sent from its
+/// database.
+#[derive(Copy, Clone, Default)]
+pub struct BlockedNotification {
+    channel: Text<64>,
+    payload: Text<QUERY_LEN>,
+}
+
+impl BlockedNotification {
+    pub fn new(channel: &str, payload: &str) -> Self {
+        Self {
+            channel: Text::new(channel),
+            payload: Text::new(payload),
+        }
+    }
+
+    pub fn channel(&self) -> &str {
+        self.channel.as_str()
+    }
+
+    pub fn payload(&self) -> &str {
+        self.payload.as_str()
+    }
+}
+
+/// What a blocked statement leaves to be done after its transaction has
+/// aborted.
+#[derive(Copy, Clone)]
+pub enum Delivery {
+    Violation(BlockedViolation),
+    Notification(BlockedNotification),
+}
+
+impl Default for Delivery {
+    fn default() -> Self {
+        Self::Violation(BlockedViolation::default())
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct Entry {
+    database: u32,
+    delivery: Delivery,
+}
+
+/// A database with a delivery worker requested or running; `pid` is 0 until
+/// the worker has started.
+#[derive(Copy, Clone, Default)]
+struct WorkerSlot {
+    database: u32,
+    pid: i32,
+    requested_at: pg_sys::TimestampTz,
+}
+
+/// What blocked statements leave to be done after their transaction has
+/// aborted. Each database's entries are handled by a short-lived worker
+/// connected to it, since a worker can only connect to one database and
+/// notifications only reach listeners of their own database.
+#[derive(Copy, Clone)]
+pub struct DeliveryQueue {
+    head: usize,
+    len: usize,
+    dropped: u64,
+    entries: [Entry; DELIVERY_QUEUE_LEN],
+    workers: [WorkerSlot; MAX_WORKERS],
+}
+
+impl Default for DeliveryQueue {
+    fn default() -> Self {
+        Self {
+            head: 0,
+            len: 0,
+            dropped: 0,
+            entries: [Entry::default(); DELIVERY_QUEUE_LEN],
+            workers: [WorkerSlot::default(); MAX_WORKERS],
+        }
+    }
+}
+
+unsafe impl PGRXSharedMemory for DeliveryQueue {}
+
+impl DeliveryQueue {
+    /// Queues an entry and returns whether a worker must be started for its
+    /// database.
+    fn push(&mut self, database: u32, delivery: Delivery, now: i64) -> bool {
+        if self.len == DELIVERY_QUEUE_LEN {
+            self.dropped += 1;
+        } else {
+            self.entries[(self.head + self.len) % DELIVERY_QUEUE_LEN] =
+                Entry { database, delivery };
+            self.len += 1;
+        }
+
+        if let Some(slot) = self
+            .workers
+            .iter_mut()
+            .find(|slot| slot.database == database)
+        {
+            if slot.pid != 0 || now - slot.requested_at < START_TIMEOUT_USECS {
+                return false;
+            }
+            slot.requested_at = now;
+            return true;
+        }
+        let Some(slot) = self.workers.iter_mut().find(|slot| slot.database == 0) else {
+            // Every slot is busy; a later violation in this database starts
+            // the worker once one is free.
+            return false;
+        };
+        *slot = WorkerSlot {
+            database,
+            pid: 0,
+            requested_at: now,
+        };
+        true
+    }
+
+    /// Takes the entries of `database` for the worker `pid`. When there are
+    /// none, the worker's slot is freed in the same step, so an entry queued
+    /// afterwards requests a new worker.
+    fn take(&mut self, database: u32, pid: i32) -> (Vec<Delivery>, u64) {
+        let mut taken = Vec::new();
+        let mut kept = 0;
+        for i in 0..self.len {
+            let entry = self.entries[(self.head + i) % DELIVERY_QUEUE_LEN];
+            if entry.database == database {
+                taken.push(entry.delivery);
+            } else {
+                self.entries[(self.head + kept) % DELIVERY_QUEUE_LEN] = entry;
+                kept += 1;
+            }
+        }
+        self.len = kept;
+
+        if let Some(slot) = self
+            .workers
+            .iter_mut()
+            .find(|slot| slot.database == database)
+        {
+            if taken.is_empty() {
+                *slot = WorkerSlot::default();
+            } else {
+                slot.pid = pid;
+            }
+        }
+        (taken, std::mem::take(&mut self.dropped))
+    }
+
+    fn release(&mut self, database: u32, pid: i32) {
+        if let Some(slot) = self
+            .workers
+            .iter_mut()
+            .find(|slot| slot.database == database && slot.pid == pid)
+        {
+            *slot = WorkerSlot::default();
+        }
+    }
+}
+
+/// Hands what a blocked statement leaves behind to a worker connected to the
+/// current database, which delivers it whatever becomes of the session.
+/// Returns false when shared memory is not available.
+pub fn enqueue(delivery: Delivery) -> bool {
+    if !shmem::available() {
+        return false;
+    }
+    let database = unsafe { pg_sys::MyDatabaseId };
+    let now = unsafe { pg_sys::GetCurrentTimestamp() };
+    let start = shmem::DELIVERY_QUEUE
+        .exclusive()
+        .push(database.as_u32(), delivery, now);
+    if start {
+        let started = BackgroundWorkerBuilder::new("pg_strict delivery")
+            .set_type("pg_strict delivery")
+            .set_function("pg_strict_delivery_main")
+            .set_library("pg_strict")
+            .set_argument(Some(pg_sys::Datum::from(database.as_u32())))
+            .enable_spi_access()
+            .set_restart_time(None)
+            .load_dynamic();
+        if started.is_err() {
+            shmem::DELIVERY_QUEUE
+                .exclusive()
+                .release(database.as_u32(), 0);
+            pgrx::log!(
+                "pg_strict: could not start a delivery worker; blocked violations of this database wait for the next one."
+            );
+        }
+    }
+    true
+}
+
+#[pg_guard]
+#[unsafe(no_mangle)]
+pub extern "C-unwind" fn pg_strict_delivery_main(arg: pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGTERM);
+    let database = arg.value() as u32;
+    let pid = unsafe { pg_sys::MyProcPid };
+    unsafe {
+        pg_sys::before_shmem_exit(Some(release_on_exit), arg);
+        pg_sys::BackgroundWorkerInitializeConnectionByOid(
+            pg_sys::Oid::from(database),
+            pg_sys::InvalidOid,
+            0,
+        );
+    }
+
+    loop {
+        let (pending, dropped) = shmem::DELIVERY_QUEUE.exclusive().take(database, pid);
+        if dropped > 0 {
+            pgrx::log!(
+                "pg_strict: delivery queue overflowed, {} blocked violations were not recorded or announced.",
+                dropped
+            );
+        }
+        if pending.is_empty() {
+            break;
+        }
+        BackgroundWorker::transaction(|| {
+            let installed = extension_installed();
+            for delivery in &pending {
+                match delivery {
+                    Delivery::Violation(violation) if installed => {
+                        // One failed write must not lose the others.
+                        subxact::try_in_subtransaction(|| {
+                            audit::write_blocked_violation(violation);
+                            Some(())
+                        });
+                    }
+                    Delivery::Violation(_) => {}
+                    // Sent when the worker's transaction commits; a channel
+                    // the server rejects must not lose the other entries.
+                    Delivery::Notification(notification) => {
+                        subxact::try_in_subtransaction(|| {
+                            notify::send(notification.channel(), notification.payload());
+                            Some(())
+                        });
+                    }
+                }
+            }
+        });
+        if BackgroundWorker::sigterm_received() {
+            break;
+        }
+    }
+}
+
+// Frees the worker's slot if it exits before finding its queue empty.
+#[pg_guard]
+unsafe extern "C-unwind" fn release_on_exit(_code: i32, arg: pg_sys::Datum) {
+    let pid = unsafe { pg_sys::MyProcPid };
+    shmem::DELIVERY_QUEUE
+        .exclusive()
+        .release(arg.value() as u32, pid);
+}
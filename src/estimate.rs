@@ -0,0 +1,154 @@
+use pgrx::PgList;
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+
+/// Rows the planner expects an UPDATE or DELETE plan to modify.
+pub unsafe fn planned_rows(stmt: *mut pg_sys::PlannedStmt) -> Option<f64> {
+    if stmt.is_null() {
+        return None;
+    }
+    let plan = unsafe { (*stmt).planTree };
+    if plan.is_null() || unsafe { (*plan).type_ } != pg_sys::NodeTag::T_ModifyTable {
+        return None;
+    }
+    // ModifyTable carries the row estimate of the subplan(s) feeding it.
+    Some(unsafe { (*plan).plan_rows })
+}
+
+/// The relation's `pg_class.reltuples`, or `None` when it has never been
+/// vacuumed or analyzed and the planner has nothing to compare against.
+pub fn reltuples(relid: pg_sys::Oid) -> Option<f64> {
+    unsafe {
+        let relation = pg_sys::RelationIdGetRelation(relid);
+        if relation.is_null() {
+            return None;
+        }
+        let reltuples = (*(*relation).rd_rel).reltuples as f64;
+        pg_sys::RelationClose(relation);
+        (reltuples > 0.0).then_some(reltuples)
+    }
+}
+
+/// Share of the table's rows the estimate covers, in percent.
+pub fn affected_percent(rows: f64, reltuples: f64) -> f64 {
+    (rows / reltuples * 100.0).min(100.0)
+}
+
+/// The planner's view of an UPDATE or DELETE, as reported by
+/// `pg_strict_count_affected()`.
+pub struct Estimate {
+    pub operation: String,
+    pub target_table: String,
+    pub estimated_rows: f64,
+    pub table_rows: Option<f64>,
+}
+
+/// Plans `query` with EXPLAIN, without executing it, and reads the row
+/// estimate and target table off the ModifyTable node.
+pub fn explain_dml(query: &str) -> Result<Estimate, pgrx::spi::Error> {
+    Spi::connect(|client| {
+        let plan = client
+            .select(
+                &format!("EXPLAIN (VERBOSE, FORMAT JSON) {query}"),
+                None,
+                &[],
+            )?
+            .first()
+            .get_one::<pgrx::Json>()?;
+        let Some(plan) = plan else {
+            pgrx::error!("pg_strict: EXPLAIN returned no plan.");
+        };
+
+        let row = client
+            .select(
+                "SELECT p ->> 'Operation', \
+                        format('%s.%s', p ->> 'Schema', p ->> 'Relation Name'), \
+                        (p ->> 'Plan Rows')::float8, \
+                        nullif(c.reltuples, -1)::float8 \
+                 FROM (SELECT $1::json -> 0 -> 'Plan' AS p) AS plan \
+                 LEFT JOIN pg_catalog.pg_class c \
+                   ON c.oid = pg_catalog.to_regclass(format('%I.%I', p ->> 'Schema', p ->> 'Relation Name')) \
+                 WHERE p ->> 'Node Type' = 'ModifyTable'",
+                Some(1),
+                &[plan.into()],
+            )?
+            .first();
+        let Some(operation) = row.get::<String>(1)? else {
+            pgrx::error!("pg_strict: query is not a plain UPDATE or DELETE.");
+        };
+
+        Ok(Estimate {
+            operation: operation.to_uppercase(),
+            target_table: row.get::<String>(2)?.unwrap_or_default(),
+            estimated_rows: row.get::<f64>(3)?.unwrap_or(0.0),
+            table_rows: row.get::<f64>(4)?.filter(|rows| *rows > 0.0),
+        })
+    })
+}
+
+/// Relations read with a sequential scan anywhere in the plan, including
+/// subqueries and InitPlans.
+pub unsafe fn sequential_scans(stmt: *mut pg_sys::PlannedStmt) -> Vec<pg_sys::Oid> {
+    if stmt.is_null() {
+        return Vec::new();
+    }
+    let rtable = unsafe { PgList::<pg_sys::RangeTblEntry>::from_pg((*stmt).rtable) };
+    let mut plans = vec![unsafe { (*stmt).planTree }];
+    plans.extend(unsafe { PgList::<pg_sys::Plan>::from_pg((*stmt).subplans) }.iter_ptr());
+
+    let mut relids = Vec::new();
+    while let Some(plan) = plans.pop() {
+        if plan.is_null() {
+            continue;
+        }
+        unsafe {
+            plans.push((*plan).lefttree);
+            plans.push((*plan).righttree);
+            plans.extend(child_plans(plan));
+        }
+
+        if unsafe { (*plan).type_ } == pg_sys::NodeTag::T_SeqScan {
+            // SeqScan starts with a Scan on every supported version.
+            let scanrelid = unsafe { (*(plan as *mut pg_sys::Scan)).scanrelid };
+            if let Some(rte) = rtable.get_ptr((scanrelid as usize).wrapping_sub(1)) {
+                let relid = unsafe { (*rte).relid };
+                if !relids.contains(&relid) {
+                    relids.push(relid);
+                }
+            }
+        }
+    }
+    relids
+}
+
+/// Children of plan nodes that do not keep them in lefttree/righttree.
+unsafe fn child_plans(plan: *mut pg_sys::Plan) -> Vec<*mut pg_sys::Plan> {
+    let list = match unsafe { (*plan).type_ } {
+        pg_sys::NodeTag::T_Append => unsafe { (*(plan as *mut pg_sys::Append)).appendplans },
+        pg_sys::NodeTag::T_MergeAppend => unsafe {
+            (*(plan as *mut pg_sys::MergeAppend)).mergeplans
+        },
+        #[cfg(feature = "pg13")]
+        pg_sys::NodeTag::T_ModifyTable => unsafe { (*(plan as *mut pg_sys::ModifyTable)).plans },
+        pg_sys::NodeTag::T_SubqueryScan => {
+            return vec![unsafe { (*(plan as *mut pg_sys::SubqueryScan)).subplan }];
+        }
+        _ => return Vec::new(),
+    };
+    unsafe { PgList::<pg_sys::Plan>::from_pg(list) }
+        .iter_ptr()
+        .collect()
+}
+
+/// On-disk size of the relation in kilobytes, from `pg_class.relpages`.
+pub fn relation_size_kb(relid: pg_sys::Oid) -> i64 {
+    unsafe {
+        let relation = pg_sys::RelationIdGetRelation(relid);
+        if relation.is_null() {
+            return 0;
+        }
+        let pages = (*(*relation).rd_rel).relpages as i64;
+        pg_sys::RelationClose(relation);
+        pages * pg_sys::BLCKSZ as i64 / 1024
+    }
+}
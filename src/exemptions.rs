@@ -0,0 +1,167 @@
+use crate::guc::{
+    configured_allowed_queryids, exempt_application_names, exempt_backend_types, trusted_functions,
+};
+use crate::shmem;
+use pgrx::pg_sys;
+use std::ffi::CStr;
+
+// FmgrInfo addresses of trusted function calls currently on the stack.
+static mut TRUSTED_CALLS: Vec<usize> = Vec::new();
+
+pub fn statement_is_exempt() -> bool {
+    inside_trusted_function() || session_is_exempt()
+}
+
+pub fn session_is_exempt() -> bool {
+    let application_patterns = exempt_application_names();
+    if !application_patterns.is_empty() {
+        let application_name = current_application_name().unwrap_or_default();
+        if matches_any(&application_patterns, &application_name) {
+            return true;
+        }
+    }
+
+    let backend_patterns = exempt_backend_types();
+    if !backend_patterns.is_empty() {
+        let backend_type = current_backend_type().unwrap_or_default();
+        if matches_any(&backend_patterns, &backend_type) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// True when the statement's query identifier is on the configured or runtime allowlist.
+pub fn query_id_is_allowed(query_id: i64) -> bool {
+    if query_id == 0 {
+        return false;
+    }
+    if configured_allowed_queryids().contains(&query_id) {
+        return true;
+    }
+    shmem::available() && shmem::QUERYID_ALLOWLIST.share().contains(query_id)
+}
+
+#[allow(static_mut_refs)]
+pub fn inside_trusted_function() -> bool {
+    unsafe { !TRUSTED_CALLS.is_empty() }
+}
+
+#[allow(static_mut_refs)]
+pub fn enter_trusted_call(flinfo: *mut pg_sys::FmgrInfo) {
+    unsafe { TRUSTED_CALLS.push(flinfo as usize) };
+}
+
+#[allow(static_mut_refs)]
+pub fn exit_trusted_call(flinfo: *mut pg_sys::FmgrInfo) {
+    unsafe {
+        if TRUSTED_CALLS.last() == Some(&(flinfo as usize)) {
+            TRUSTED_CALLS.pop();
+        }
+    }
+}
+
+pub fn is_trusted_function(fn_oid: pg_sys::Oid) -> bool {
+    let patterns = trusted_functions();
+    if patterns.is_empty() {
+        return false;
+    }
+
+    let Some(name) = qualified_function_name(fn_oid) else {
+        return false;
+    };
+    patterns
+        .iter()
+        .any(|pattern| glob_match(&normalize_function_pattern(pattern), &name))
+}
+
+/// Accepts `schema.name`, `schema.name()` or a bare `name` (resolved against `public`).
+pub fn normalize_function_pattern(pattern: &str) -> String {
+    let pattern = pattern.trim().trim_end_matches("()");
+    if pattern.contains('.') {
+        pattern.to_string()
+    } else {
+        format!("public.{pattern}")
+    }
+}
+
+fn qualified_function_name(fn_oid: pg_sys::Oid) -> Option<String> {
+    unsafe {
+        let name = pg_sys::get_func_name(fn_oid);
+        if name.is_null() {
+            return None;
+        }
+        let namespace = pg_sys::get_namespace_name(pg_sys::get_func_namespace(fn_oid));
+        if namespace.is_null() {
+            return None;
+        }
+        Some(format!(
+            "{}.{}",
+            CStr::from_ptr(namespace).to_string_lossy(),
+            CStr::from_ptr(name).to_string_lossy()
+        ))
+    }
+}
+
+fn matches_any(patterns: &[String], value: &str) -> bool {
+    patterns.iter().any(|pattern| glob_match(pattern, value))
+}
+
+/// Case-insensitive match supporting `*` (any run of characters) and `?` (one character).
+pub fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let value: Vec<char> = value.to_lowercase().chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = backtrack {
+            p = star_p + 1;
+            v = star_v + 1;
+            backtrack = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+pub fn current_application_name() -> Option<String> {
+    let name = unsafe { pg_sys::application_name };
+    if name.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+pub fn current_backend_type() -> Option<String> {
+    unsafe {
+        // Background workers report their registered bgw_type in pg_stat_activity,
+        // which is what job runners such as pg_cron are identified by.
+        if pg_sys::MyBackendType == pg_sys::BackendType::B_BG_WORKER
+            && !pg_sys::MyBgworkerEntry.is_null()
+        {
+            let bgw_type = CStr::from_ptr((*pg_sys::MyBgworkerEntry).bgw_type.as_ptr());
+            return Some(bgw_type.to_string_lossy().into_owned());
+        }
+
+        let desc = pg_sys::GetBackendTypeDesc(pg_sys::MyBackendType);
+        if desc.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(desc).to_string_lossy().into_owned())
+    }
+}
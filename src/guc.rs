@@ -1,70 +1,1891 @@
+use crate::policy::{
+    DELETE_MODE_GUC, GUARD_TRUNCATE_GUC, LINT_FUNCTIONS_GUC, MAX_AFFECTED_PERCENT_GUC,
+    MAX_AFFECTED_ROWS_GUC, PROTECT_CATALOGS_GUC, UPDATE_MODE_GUC,
+};
+use crate::schedule;
+use pgrx::datum::TimestampWithTimeZone;
 use pgrx::guc::{GucContext, GucFlags, GucRegistry, GucSetting};
-use std::ffi::CStr;
+use pgrx::{pg_guard, pg_sys};
+use std::ffi::{CStr, CString, c_char, c_void};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
 pub enum StrictMode {
     Off,
     Warn,
     On,
+    Learn,
+    Notice,
+    Log,
+}
+
+impl StrictMode {
+    /// Whether violations are reported but the statement still runs.
+    pub fn is_warning(self) -> bool {
+        matches!(
+            self,
+            StrictMode::Warn | StrictMode::Notice | StrictMode::Log
+        )
+    }
+}
+
+/// Languages of pg_strict's violation messages, in the order of the
+/// translations in `messages::CATALOG`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum MessageLanguage {
+    En,
+    De,
+    Fr,
+    Ja,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum StrictPreset {
+    None,
+    Production,
+    Staging,
+    Development,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum StrictEnforce {
+    Normal,
+    Force,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum EnforcementPoint {
+    Analyze,
+    Planner,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum NestedStatements {
+    #[name = c"top_level"]
+    TopLevel,
+    All,
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum ExplainAnalyzeMode {
+    Check,
+    Exempt,
+    Warn,
+    Block,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum WatchdogAction {
+    Cancel,
+    Terminate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum PlanFormat {
+    Off,
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, pgrx::PostgresGucEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
 }
 
 #[allow(non_upper_case_globals)]
 static mut REQUIRE_WHERE_ON_UPDATE_MODE: Option<GucSetting<StrictMode>> = None;
 #[allow(non_upper_case_globals)]
 static mut REQUIRE_WHERE_ON_DELETE_MODE: Option<GucSetting<StrictMode>> = None;
+#[allow(non_upper_case_globals)]
+static mut EXEMPT_APPLICATION_NAMES: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut EXEMPT_BACKEND_TYPES: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut TRUSTED_FUNCTIONS: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut PROTECT_CATALOGS_MODE: Option<GucSetting<StrictMode>> = None;
+#[allow(non_upper_case_globals)]
+static mut PRESET: Option<GucSetting<StrictPreset>> = None;
+#[allow(non_upper_case_globals)]
+static mut REQUIRE_BYPASS_REASON: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut OVERRIDE_REASON: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_SNOOZE_SECONDS: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut APPROVAL_TTL_SECONDS: Option<GucSetting<i32>> = None;
+// Settings with a check hook are defined directly, so PostgreSQL stores their
+// values here rather than in a GucSetting.
+static mut ALLOWED_QUERYIDS: *mut c_char = std::ptr::null_mut();
+// The parsed value of pg_strict.allowed_queryids, kept by its assign hook.
+static mut ALLOWED_QUERYID_LIST: Vec<i64> = Vec::new();
+#[allow(non_upper_case_globals)]
+static mut ENFORCE: Option<GucSetting<StrictEnforce>> = None;
+static mut MAINTENANCE_WINDOWS: *mut c_char = std::ptr::null_mut();
+// The parsed value of pg_strict.maintenance_windows, kept by its assign hook.
+static mut MAINTENANCE_WINDOW_LIST: Vec<schedule::Window> = Vec::new();
+static mut ESCALATE_AT: *mut c_char = std::ptr::null_mut();
+static mut ERROR_CODE: *mut c_char = std::ptr::null_mut();
+#[allow(non_upper_case_globals)]
+static mut AUDIT: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut NOTIFY_CHANNEL: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut WEBHOOK_URL: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut LOG_FORMAT: Option<GucSetting<LogFormat>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_WARNINGS_PER_MINUTE: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut REDACT_LITERALS: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_AFFECTED_ROWS: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_AFFECTED_PERCENT: Option<GucSetting<f64>> = None;
+#[allow(non_upper_case_globals)]
+static mut SAMPLE_RATE: Option<GucSetting<f64>> = None;
+#[allow(non_upper_case_globals)]
+static mut REPORT_CASCADES: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut REQUIRE_TRANSACTION: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut ENFORCEMENT_POINT: Option<GucSetting<EnforcementPoint>> = None;
+#[allow(non_upper_case_globals)]
+static mut CHECK_NESTED: Option<GucSetting<NestedStatements>> = None;
+#[allow(non_upper_case_globals)]
+static mut ALLOW_EXPLAIN_ANALYZE: Option<GucSetting<ExplainAnalyzeMode>> = None;
+#[allow(non_upper_case_globals)]
+static mut REQUIRE_TIMEOUTS: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_STATEMENT_TIMEOUT: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_LOCK_TIMEOUT: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut REQUIRE_RECENT_BACKUP: Option<GucSetting<i32>> = None;
+static mut LAST_BACKUP_TIME: *mut c_char = std::ptr::null_mut();
+#[allow(non_upper_case_globals)]
+static mut BACKUP_PROBE: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut BACKUP_PROTECTED_TABLES: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut RECYCLE_BIN: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut RECYCLE_BIN_MAX_ROWS: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut RECYCLE_BIN_RETENTION: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut RECYCLE_BIN_DATABASE: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut SEQSCAN_WARN_SIZE: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut REPORT_ROWS_AFFECTED: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut SUMMARY_AT_COMMIT: Option<GucSetting<bool>> = None;
+#[allow(non_upper_case_globals)]
+static mut ROWS_AFFECTED_THRESHOLD: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut MAX_STATEMENT_COST: Option<GucSetting<f64>> = None;
+#[allow(non_upper_case_globals)]
+static mut STATEMENT_COST_MODE: Option<GucSetting<StrictMode>> = None;
+#[allow(non_upper_case_globals)]
+static mut GUARD_TRUNCATE_MODE: Option<GucSetting<StrictMode>> = None;
+#[allow(non_upper_case_globals)]
+static mut LINT_FUNCTIONS_MODE: Option<GucSetting<StrictMode>> = None;
+#[allow(non_upper_case_globals)]
+static mut LOG_PLANS: Option<GucSetting<PlanFormat>> = None;
+#[allow(non_upper_case_globals)]
+static mut LOG_DIRECTORY: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut LOG_FILENAME: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut MESSAGE_TEMPLATE: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut MESSAGE_LANGUAGE: Option<GucSetting<MessageLanguage>> = None;
+#[allow(non_upper_case_globals)]
+static mut WATCHDOG_MAX_DURATION: Option<GucSetting<i32>> = None;
+#[allow(non_upper_case_globals)]
+static mut WATCHDOG_ACTION: Option<GucSetting<WatchdogAction>> = None;
+#[allow(non_upper_case_globals)]
+static mut WATCHDOG_DATABASE: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut METRICS_ADDRESS: Option<GucSetting<Option<CString>>> = None;
+#[allow(non_upper_case_globals)]
+static mut METRICS_PORT: Option<GucSetting<i32>> = None;
 
 pub fn init_gucs() {
     unsafe {
         REQUIRE_WHERE_ON_UPDATE_MODE = Some(GucSetting::<StrictMode>::new(StrictMode::Off));
         REQUIRE_WHERE_ON_DELETE_MODE = Some(GucSetting::<StrictMode>::new(StrictMode::Off));
+        EXEMPT_APPLICATION_NAMES = Some(GucSetting::<Option<CString>>::new(None));
+        EXEMPT_BACKEND_TYPES = Some(GucSetting::<Option<CString>>::new(None));
+        TRUSTED_FUNCTIONS = Some(GucSetting::<Option<CString>>::new(None));
+        PROTECT_CATALOGS_MODE = Some(GucSetting::<StrictMode>::new(StrictMode::Off));
+        PRESET = Some(GucSetting::<StrictPreset>::new(StrictPreset::None));
+        REQUIRE_BYPASS_REASON = Some(GucSetting::<bool>::new(false));
+        OVERRIDE_REASON = Some(GucSetting::<Option<CString>>::new(None));
+        MAX_SNOOZE_SECONDS = Some(GucSetting::<i32>::new(3600));
+        APPROVAL_TTL_SECONDS = Some(GucSetting::<i32>::new(3600));
+        ENFORCE = Some(GucSetting::<StrictEnforce>::new(StrictEnforce::Normal));
+        AUDIT = Some(GucSetting::<bool>::new(false));
+        NOTIFY_CHANNEL = Some(GucSetting::<Option<CString>>::new(Some(
+            c"pg_strict_violations",
+        )));
+        WEBHOOK_URL = Some(GucSetting::<Option<CString>>::new(None));
+        METRICS_ADDRESS = Some(GucSetting::<Option<CString>>::new(Some(c"127.0.0.1")));
+        METRICS_PORT = Some(GucSetting::<i32>::new(0));
+        WATCHDOG_MAX_DURATION = Some(GucSetting::<i32>::new(0));
+        WATCHDOG_ACTION = Some(GucSetting::<WatchdogAction>::new(WatchdogAction::Cancel));
+        WATCHDOG_DATABASE = Some(GucSetting::<Option<CString>>::new(Some(c"postgres")));
+        LOG_FORMAT = Some(GucSetting::<LogFormat>::new(LogFormat::Text));
+        REDACT_LITERALS = Some(GucSetting::<bool>::new(false));
+        MAX_WARNINGS_PER_MINUTE = Some(GucSetting::<i32>::new(0));
+        MAX_AFFECTED_ROWS = Some(GucSetting::<i32>::new(0));
+        MAX_AFFECTED_PERCENT = Some(GucSetting::<f64>::new(0.0));
+        SAMPLE_RATE = Some(GucSetting::<f64>::new(1.0));
+        REPORT_CASCADES = Some(GucSetting::<bool>::new(false));
+        REQUIRE_TRANSACTION = Some(GucSetting::<bool>::new(false));
+        ENFORCEMENT_POINT = Some(GucSetting::<EnforcementPoint>::new(
+            EnforcementPoint::Analyze,
+        ));
+        CHECK_NESTED = Some(GucSetting::<NestedStatements>::new(
+            NestedStatements::TopLevel,
+        ));
+        ALLOW_EXPLAIN_ANALYZE = Some(GucSetting::<ExplainAnalyzeMode>::new(
+            ExplainAnalyzeMode::Check,
+        ));
+        REQUIRE_TIMEOUTS = Some(GucSetting::<bool>::new(false));
+        MAX_STATEMENT_TIMEOUT = Some(GucSetting::<i32>::new(0));
+        MAX_LOCK_TIMEOUT = Some(GucSetting::<i32>::new(0));
+        REQUIRE_RECENT_BACKUP = Some(GucSetting::<i32>::new(0));
+        BACKUP_PROBE = Some(GucSetting::<Option<CString>>::new(None));
+        BACKUP_PROTECTED_TABLES = Some(GucSetting::<Option<CString>>::new(None));
+        RECYCLE_BIN = Some(GucSetting::<bool>::new(false));
+        RECYCLE_BIN_MAX_ROWS = Some(GucSetting::<i32>::new(100000));
+        RECYCLE_BIN_RETENTION = Some(GucSetting::<i32>::new(0));
+        RECYCLE_BIN_DATABASE = Some(GucSetting::<Option<CString>>::new(Some(c"postgres")));
+        SEQSCAN_WARN_SIZE = Some(GucSetting::<i32>::new(0));
+        REPORT_ROWS_AFFECTED = Some(GucSetting::<bool>::new(false));
+        SUMMARY_AT_COMMIT = Some(GucSetting::<bool>::new(false));
+        ROWS_AFFECTED_THRESHOLD = Some(GucSetting::<i32>::new(10000));
+        MAX_STATEMENT_COST = Some(GucSetting::<f64>::new(0.0));
+        STATEMENT_COST_MODE = Some(GucSetting::<StrictMode>::new(StrictMode::Warn));
+        GUARD_TRUNCATE_MODE = Some(GucSetting::<StrictMode>::new(StrictMode::Off));
+        LINT_FUNCTIONS_MODE = Some(GucSetting::<StrictMode>::new(StrictMode::Off));
+        LOG_PLANS = Some(GucSetting::<PlanFormat>::new(PlanFormat::Off));
+        LOG_DIRECTORY = Some(GucSetting::<Option<CString>>::new(None));
+        LOG_FILENAME = Some(GucSetting::<Option<CString>>::new(Some(
+            c"pg_strict-%Y-%m-%d.log",
+        )));
+        MESSAGE_TEMPLATE = Some(GucSetting::<Option<CString>>::new(None));
+        MESSAGE_LANGUAGE = Some(GucSetting::<MessageLanguage>::new(MessageLanguage::En));
 
         if let Some(ref mut setting) = REQUIRE_WHERE_ON_UPDATE_MODE {
-            GucRegistry::define_enum_guc(
+            GucRegistry::define_enum_guc_with_hooks(
                 cstr(b"pg_strict.require_where_on_update\0"),
                 cstr(b"Mode for requiring WHERE clause on UPDATE statements.\0"),
                 cstr(b"Controls how pg_strict handles UPDATE statements without WHERE clauses.\0"),
                 setting,
                 GucContext::Userset,
                 GucFlags::default(),
+                Some(check_source::<i32>),
+                Some(assign_source::<i32, UPDATE_MODE_SLOT>),
+                None,
             );
         }
 
         if let Some(ref mut setting) = REQUIRE_WHERE_ON_DELETE_MODE {
-            GucRegistry::define_enum_guc(
+            GucRegistry::define_enum_guc_with_hooks(
                 cstr(b"pg_strict.require_where_on_delete\0"),
                 cstr(b"Mode for requiring WHERE clause on DELETE statements.\0"),
                 cstr(b"Controls how pg_strict handles DELETE statements without WHERE clauses.\0"),
                 setting,
                 GucContext::Userset,
                 GucFlags::default(),
+                Some(check_source::<i32>),
+                Some(assign_source::<i32, DELETE_MODE_SLOT>),
+                None,
             );
         }
-    }
-}
 
-#[allow(static_mut_refs)]
-pub fn current_modes() -> (StrictMode, StrictMode) {
-    let update_mode = unsafe {
-        REQUIRE_WHERE_ON_UPDATE_MODE
-            .as_mut()
-            .map(|setting| setting.get())
-            .unwrap_or(StrictMode::Off)
-    };
-    let delete_mode = unsafe {
-        REQUIRE_WHERE_ON_DELETE_MODE
-            .as_mut()
-            .map(|setting| setting.get())
-            .unwrap_or(StrictMode::Off)
-    };
-    (update_mode, delete_mode)
-}
+        if let Some(ref mut setting) = EXEMPT_APPLICATION_NAMES {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.exempt_application_names\0"),
+                cstr(b"Comma-separated application_name patterns exempt from pg_strict.\0"),
+                cstr(b"Sessions whose application_name matches one of these patterns (* and ? wildcards) are not checked.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
 
-pub fn mode_to_str(mode: StrictMode) -> &'static str {
-    match mode {
-        StrictMode::Off => "off",
-        StrictMode::Warn => "warn",
-        StrictMode::On => "on",
-    }
+        if let Some(ref mut setting) = EXEMPT_BACKEND_TYPES {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.exempt_backend_types\0"),
+                cstr(b"Comma-separated backend type patterns exempt from pg_strict.\0"),
+                cstr(b"Backends whose type (as shown in pg_stat_activity.backend_type) matches one of these patterns are not checked.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = TRUSTED_FUNCTIONS {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.trusted_functions\0"),
+                cstr(b"Comma-separated schema-qualified functions whose internal DML is trusted.\0"),
+                cstr(b"Statements executed while one of these functions is running are not checked. Patterns may use * and ? wildcards.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = PROTECT_CATALOGS_MODE {
+            GucRegistry::define_enum_guc_with_hooks(
+                cstr(b"pg_strict.protect_catalogs\0"),
+                cstr(b"Mode for INSERT/UPDATE/DELETE statements targeting pg_catalog relations.\0"),
+                cstr(b"Controls how pg_strict handles DML on system catalogs, regardless of WHERE clauses. Only superusers can change this setting.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+                Some(check_source::<i32>),
+                Some(assign_source::<i32, PROTECT_CATALOGS_SLOT>),
+                None,
+            );
+        }
+
+        if let Some(ref mut setting) = PRESET {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.preset\0"),
+                cstr(b"Environment preset supplying defaults for pg_strict settings.\0"),
+                cstr(b"One of none, production, staging or development. Settings left at their built-in default take their value from the preset.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = REQUIRE_BYPASS_REASON {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.require_bypass_reason\0"),
+                cstr(b"Require a non-empty reason when arming a pg_strict bypass.\0"),
+                cstr(b"When on, bypass functions such as pg_strict_allow_next() reject calls without a reason.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = OVERRIDE_REASON {
+            GucRegistry::define_string_guc_with_hooks(
+                cstr(b"pg_strict.override\0"),
+                cstr(b"Reason for relaxing pg_strict enforcement in the current transaction.\0"),
+                cstr(b"Set with SET LOCAL inside a transaction block. A non-empty reason relaxes enforcement and is logged with every statement it allows.\0"),
+                setting,
+                GucContext::Userset,
+                GucFlags::default(),
+                Some(check_override),
+                Some(assign_override),
+                None,
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_SNOOZE_SECONDS {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.max_snooze_duration\0"),
+                cstr(b"Longest duration pg_strict_snooze() may disable enforcement for.\0"),
+                cstr(b"Snooze requests longer than this are rejected.\0"),
+                setting,
+                1,
+                7 * 24 * 3600,
+                GucContext::Suset,
+                GucFlags::UNIT_S,
+            );
+        }
+
+        if let Some(ref mut setting) = APPROVAL_TTL_SECONDS {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.approval_ttl\0"),
+                cstr(b"How long an approved statement remains usable.\0"),
+                cstr(b"Approvals granted by pg_strict_approve() expire after this duration if unused.\0"),
+                setting,
+                1,
+                7 * 24 * 3600,
+                GucContext::Suset,
+                GucFlags::UNIT_S,
+            );
+        }
+
+        define_checked_string_guc(
+            cstr(b"pg_strict.allowed_queryids\0"),
+            cstr(b"Comma-separated query identifiers that are always exempt from pg_strict.\0"),
+            cstr(b"Complements pg_strict_allow_queryid(), which only lasts until the next server restart.\0"),
+            &raw mut ALLOWED_QUERYIDS,
+            pg_sys::GucContext::PGC_SIGHUP,
+            check_allowed_queryids,
+            Some(assign_allowed_queryids),
+        );
+
+        if let Some(ref mut setting) = ENFORCE {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.enforce\0"),
+                cstr(b"Whether non-superusers may relax pg_strict enforcement.\0"),
+                cstr(b"When force, WHERE clause rules are always on and session bypasses are honoured only for superusers.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        define_checked_string_guc(
+            cstr(b"pg_strict.maintenance_windows\0"),
+            cstr(b"Weekly windows during which on mode is downgraded to warn.\0"),
+            cstr(b"Comma-separated entries of the form '<days> HH:MM-HH:MM', e.g. 'sat 02:00-04:00, mon-fri 23:00-01:00', in the session time zone.\0"),
+            &raw mut MAINTENANCE_WINDOWS,
+            pg_sys::GucContext::PGC_SUSET,
+            check_maintenance_windows,
+            Some(assign_maintenance_windows),
+        );
+
+        define_checked_string_guc(
+            cstr(b"pg_strict.escalate_at\0"),
+            cstr(b"Timestamp from which rules in warn mode are enforced as on.\0"),
+            cstr(b"Lets a rollout run in warn mode until an announced date, after which violations are blocked.\0"),
+            &raw mut ESCALATE_AT,
+            pg_sys::GucContext::PGC_SUSET,
+            check_timestamp,
+            None,
+        );
+
+        if let Some(ref mut setting) = AUDIT {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.audit\0"),
+                cstr(b"Record pg_strict violations in strict.violations.\0"),
+                cstr(b"Warned violations are written immediately; blocked ones are written by the session's next statement, after the failed transaction is gone.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = NOTIFY_CHANNEL {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.notify_channel\0"),
+                cstr(b"Channel pg_strict sends a NOTIFY on for every violation.\0"),
+                cstr(b"The payload is a JSON object describing the violation. Set to an empty string to disable notifications.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = WEBHOOK_URL {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.webhook_url\0"),
+                cstr(b"Endpoint the pg_strict webhook worker posts violation events to.\0"),
+                cstr(b"The worker is only started when this is set at server start; afterwards the URL can be changed with a reload.\0"),
+                setting,
+                GucContext::Sighup,
+                GucFlags::SUPERUSER_ONLY,
+            );
+        }
+
+        if let Some(ref mut setting) = LOG_FORMAT {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.log_format\0"),
+                cstr(b"Format of the server log entries pg_strict writes for violations.\0"),
+                cstr(b"With json, each violation is also logged as a single-line JSON object.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_WARNINGS_PER_MINUTE {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.max_warnings_per_minute\0"),
+                cstr(b"Most violation warnings a session emits per minute.\0"),
+                cstr(b"Further warnings are suppressed and counted in the next one emitted. Statistics and audit records still include every violation. 0 means no limit.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_AFFECTED_ROWS {
+            GucRegistry::define_int_guc_with_hooks(
+                cstr(b"pg_strict.max_affected_rows\0"),
+                cstr(b"Most rows a single UPDATE or DELETE may modify.\0"),
+                cstr(b"Checked while the statement executes: it is aborted as soon as it reaches one row past the limit, whether or not it has a WHERE clause. 0 means no limit.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Suset,
+                GucFlags::default(),
+                Some(check_source::<i32>),
+                Some(assign_source::<i32, MAX_AFFECTED_ROWS_SLOT>),
+                None,
+            );
+        }
+
+        if let Some(ref mut setting) = SAMPLE_RATE {
+            GucRegistry::define_float_guc(
+                cstr(b"pg_strict.sample_rate\0"),
+                cstr(b"Fraction of violations reported in warn and learn modes.\0"),
+                cstr(b"Violations left out of the sample run without being counted, logged or warned about. Blocking in on mode is never sampled.\0"),
+                setting,
+                0.0,
+                1.0,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_AFFECTED_PERCENT {
+            GucRegistry::define_float_guc_with_hooks(
+                cstr(b"pg_strict.max_affected_percent\0"),
+                cstr(b"Largest share of a table, in percent, an UPDATE or DELETE may be estimated to modify.\0"),
+                cstr(b"Compares the planner's row estimate with the table's reltuples and reports statements above it in the mode configured for the operation. 0 disables the check.\0"),
+                setting,
+                0.0,
+                100.0,
+                GucContext::Suset,
+                GucFlags::default(),
+                Some(check_source::<f64>),
+                Some(assign_source::<f64, MAX_AFFECTED_PERCENT_SLOT>),
+                None,
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_STATEMENT_COST {
+            GucRegistry::define_float_guc(
+                cstr(b"pg_strict.max_statement_cost\0"),
+                cstr(b"Highest total planner cost allowed for any statement.\0"),
+                cstr(b"Statements whose plan costs more are reported in pg_strict.statement_cost_mode. 0 disables the check.\0"),
+                setting,
+                0.0,
+                f64::MAX,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = STATEMENT_COST_MODE {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.statement_cost_mode\0"),
+                cstr(b"Mode for statements above pg_strict.max_statement_cost.\0"),
+                cstr(b"warn reports them, on blocks them before they run; off and learn disable the check.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = GUARD_TRUNCATE_MODE {
+            GucRegistry::define_enum_guc_with_hooks(
+                cstr(b"pg_strict.guard_truncate\0"),
+                cstr(b"Mode for TRUNCATE statements.\0"),
+                cstr(b"TRUNCATE removes every row like a DELETE without WHERE clause.\0"),
+                setting,
+                GucContext::Userset,
+                GucFlags::default(),
+                Some(check_source::<i32>),
+                Some(assign_source::<i32, GUARD_TRUNCATE_SLOT>),
+                None,
+            );
+        }
+
+        if let Some(ref mut setting) = LINT_FUNCTIONS_MODE {
+            GucRegistry::define_enum_guc_with_hooks(
+                cstr(b"pg_strict.lint_functions\0"),
+                cstr(b"Mode for UPDATE and DELETE without WHERE clause in new function bodies.\0"),
+                cstr(b"Checked by an event trigger when a SQL or PL/pgSQL function or procedure is created or replaced.\0"),
+                setting,
+                GucContext::Userset,
+                GucFlags::default(),
+                Some(check_source::<i32>),
+                Some(assign_source::<i32, LINT_FUNCTIONS_SLOT>),
+                None,
+            );
+        }
+
+        if let Some(ref mut setting) = SEQSCAN_WARN_SIZE {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.seqscan_warn_size\0"),
+                cstr(b"Table size above which a sequentially scanned UPDATE or DELETE is warned about.\0"),
+                cstr(b"Applies to statements that have a WHERE clause but whose plan still reads the whole table. 0 disables the warning.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Suset,
+                GucFlags::UNIT_KB,
+            );
+        }
+
+        if let Some(ref mut setting) = REPORT_ROWS_AFFECTED {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.report_rows_affected\0"),
+                cstr(b"Send a NOTICE after an UPDATE or DELETE that modified many rows.\0"),
+                cstr(b"Reports statements that modified more than pg_strict.rows_affected_threshold rows, whatever the enforcement mode.\0"),
+                setting,
+                GucContext::Userset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = ROWS_AFFECTED_THRESHOLD {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.rows_affected_threshold\0"),
+                cstr(b"Row count above which pg_strict.report_rows_affected sends a NOTICE.\0"),
+                cstr(b"Only used when pg_strict.report_rows_affected is on.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Userset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = SUMMARY_AT_COMMIT {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.summary_at_commit\0"),
+                cstr(b"Summarize flagged statements when a transaction block commits.\0"),
+                cstr(b"Sends one NOTICE at COMMIT listing the warned or bypassed UPDATE and DELETE statements of the transaction and the rows they modified.\0"),
+                setting,
+                GucContext::Userset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = RECYCLE_BIN {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.recycle_bin\0"),
+                cstr(b"Save the rows a risky UPDATE or DELETE is about to modify.\0"),
+                cstr(b"Copies the rows of warned, bypassed or above-threshold statements into strict.recycle_bin before they run, so they can be restored later.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = RECYCLE_BIN_MAX_ROWS {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.recycle_bin_max_rows\0"),
+                cstr(b"Most rows saved to the recycle bin for one statement.\0"),
+                cstr(b"Statements modifying more rows are not saved, and a warning says so.\0"),
+                setting,
+                1,
+                i32::MAX - 1,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = RECYCLE_BIN_RETENTION {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.recycle_bin_retention\0"),
+                cstr(b"How long rows saved in the recycle bin are kept.\0"),
+                cstr(b"A background worker purges older bins. It is only started when this is non-zero at server start. 0 keeps bins forever.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Sighup,
+                GucFlags::UNIT_S,
+            );
+        }
+
+        if let Some(ref mut setting) = ENFORCEMENT_POINT {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.enforcement_point\0"),
+                cstr(b"Where the WHERE clause and catalog rules are checked.\0"),
+                cstr(b"analyze checks statements as soon as they are parsed; planner checks the rewritten query each time it is planned.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = ALLOW_EXPLAIN_ANALYZE {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.allow_explain_analyze\0"),
+                cstr(b"How statements run by EXPLAIN ANALYZE are treated.\0"),
+                cstr(b"check applies the statement's own mode; exempt skips the checks; warn and block override the mode.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = CHECK_NESTED {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.check_nested\0"),
+                cstr(b"Which statements run by functions, triggers, DO blocks and procedures are checked.\0"),
+                cstr(b"top_level checks statements at executor nesting level 0, including those of a top-level DO block or CALL; all checks every statement; none checks only the statements clients send.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = REQUIRE_TRANSACTION {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.require_transaction\0"),
+                cstr(b"Require flagged UPDATE and DELETE statements to run inside a transaction block.\0"),
+                cstr(b"Statements that would otherwise run with a warning or through a bypass are rejected in autocommit mode, so ROLLBACK is always available.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = REQUIRE_TIMEOUTS {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.require_timeouts\0"),
+                cstr(b"Require statement_timeout and lock_timeout for flagged UPDATE and DELETE statements.\0"),
+                cstr(b"Statements that would otherwise run with a warning or through a bypass are rejected when either timeout is disabled or above its pg_strict maximum.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_STATEMENT_TIMEOUT {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.max_statement_timeout\0"),
+                cstr(b"Largest statement_timeout accepted by pg_strict.require_timeouts.\0"),
+                cstr(b"0 accepts any non-zero statement_timeout.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Suset,
+                GucFlags::UNIT_MS,
+            );
+        }
+
+        if let Some(ref mut setting) = MAX_LOCK_TIMEOUT {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.max_lock_timeout\0"),
+                cstr(b"Largest lock_timeout accepted by pg_strict.require_timeouts.\0"),
+                cstr(b"0 accepts any non-zero lock_timeout.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Suset,
+                GucFlags::UNIT_MS,
+            );
+        }
+
+        if let Some(ref mut setting) = REQUIRE_RECENT_BACKUP {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.require_recent_backup\0"),
+                cstr(b"Maximum age of the last backup for flagged UPDATE and DELETE statements.\0"),
+                cstr(b"Statements that would otherwise run with a warning or through a bypass are rejected on protected tables when the last backup is older. 0 disables the check.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Suset,
+                GucFlags::UNIT_S,
+            );
+        }
+
+        define_checked_string_guc(
+            cstr(b"pg_strict.last_backup_time\0"),
+            cstr(b"When the last backup finished, as a timestamp.\0"),
+            cstr(b"Meant to be updated by backup tooling with ALTER SYSTEM and a reload. Ignored when pg_strict.backup_probe is set.\0"),
+            &raw mut LAST_BACKUP_TIME,
+            pg_sys::GucContext::PGC_SIGHUP,
+            check_timestamp,
+            None,
+        );
+
+        if let Some(ref mut setting) = BACKUP_PROBE {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.backup_probe\0"),
+                cstr(b"Query returning the time the last backup finished.\0"),
+                cstr(b"Must return a single timestamptz value. Runs with the privileges of the session running the checked statement.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = BACKUP_PROTECTED_TABLES {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.backup_protected_tables\0"),
+                cstr(b"Comma-separated schema.table patterns checked by pg_strict.require_recent_backup.\0"),
+                cstr(b"Supports * and ? wildcards. Empty protects every table.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = REPORT_CASCADES {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.report_cascades\0"),
+                cstr(b"List the tables a flagged DELETE reaches through foreign keys.\0"),
+                cstr(b"Adds the ON DELETE CASCADE, SET NULL and SET DEFAULT targets of the table, with approximate row counts, to the detail of DELETE violations.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = REDACT_LITERALS {
+            GucRegistry::define_bool_guc(
+                cstr(b"pg_strict.redact_literals\0"),
+                cstr(b"Replace constants with $n placeholders in statement text pg_strict records.\0"),
+                cstr(b"Applies to the server log, strict.violations, strict.bypass_log and pg_strict_top_offenders(), so literal values such as PII are not retained.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = LOG_PLANS {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.log_plans\0"),
+                cstr(b"Log the execution plan of statements pg_strict warns about or blocks.\0"),
+                cstr(b"text or json logs the EXPLAIN output in that format. The statement is planned but not executed.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = LOG_DIRECTORY {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.log_directory\0"),
+                cstr(b"Directory pg_strict writes its own violation log to.\0"),
+                cstr(b"Relative paths are resolved against the data directory. Empty disables the dedicated log.\0"),
+                setting,
+                GucContext::Sighup,
+                GucFlags::SUPERUSER_ONLY,
+            );
+        }
+
+        if let Some(ref mut setting) = LOG_FILENAME {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.log_filename\0"),
+                cstr(b"File name pattern of the dedicated pg_strict log.\0"),
+                cstr(b"Supports %Y, %m, %d, %H and %M in UTC, so a new file is started as the name changes.\0"),
+                setting,
+                GucContext::Sighup,
+                GucFlags::SUPERUSER_ONLY,
+            );
+        }
+
+        if let Some(ref mut setting) = WATCHDOG_MAX_DURATION {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.watchdog_max_duration\0"),
+                cstr(b"How long an UPDATE or DELETE without WHERE clause may run before the watchdog stops it.\0"),
+                cstr(b"The watchdog worker is only started when this is non-zero at server start. 0 disables it.\0"),
+                setting,
+                0,
+                i32::MAX,
+                GucContext::Sighup,
+                GucFlags::UNIT_S,
+            );
+        }
+
+        if let Some(ref mut setting) = WATCHDOG_ACTION {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.watchdog_action\0"),
+                cstr(b"Whether the watchdog cancels runaway statements or terminates their sessions.\0"),
+                cstr(b"cancel uses pg_cancel_backend(), terminate uses pg_terminate_backend().\0"),
+                setting,
+                GucContext::Sighup,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = MESSAGE_TEMPLATE {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.message_template\0"),
+                cstr(b"Wording of pg_strict's violation messages.\0"),
+                cstr(b"Replaces %operation%, %table%, %rows_estimated%, %hint% and %message% with the violation's details. Empty keeps the built-in message.\0"),
+                setting,
+                GucContext::Suset,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = MESSAGE_LANGUAGE {
+            GucRegistry::define_enum_guc(
+                cstr(b"pg_strict.message_language\0"),
+                cstr(b"Language of pg_strict's violation messages, details and hints.\0"),
+                cstr(b"One of en, de, fr or ja.\0"),
+                setting,
+                GucContext::Userset,
+                GucFlags::default(),
+            );
+        }
+
+        define_checked_string_guc(
+            cstr(b"pg_strict.error_code\0"),
+            cstr(b"SQLSTATE of the errors raised for blocked statements.\0"),
+            cstr(b"Five digits or upper-case letters, e.g. PGS01. Empty uses 2F003 (prohibited_sql_statement_attempted).\0"),
+            &raw mut ERROR_CODE,
+            pg_sys::GucContext::PGC_SUSET,
+            check_error_code,
+            None,
+        );
+
+        // PostgreSQL only accepts postmaster settings from preloaded
+        // libraries. They configure background workers, which need
+        // preloading anyway.
+        if pg_sys::process_shared_preload_libraries_in_progress {
+            define_worker_gucs();
+        }
+    }
+}
+
+fn define_worker_gucs() {
+    unsafe {
+        if let Some(ref mut setting) = RECYCLE_BIN_DATABASE {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.recycle_bin_database\0"),
+                cstr(b"Database whose recycle bin the retention worker purges.\0"),
+                cstr(b"The recycle bin lives in the database pg_strict is installed in.\0"),
+                setting,
+                GucContext::Postmaster,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = WATCHDOG_DATABASE {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.watchdog_database\0"),
+                cstr(b"Database the watchdog worker connects to.\0"),
+                cstr(
+                    b"The watchdog sees statements in every database regardless of this setting.\0",
+                ),
+                setting,
+                GucContext::Postmaster,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = METRICS_ADDRESS {
+            GucRegistry::define_string_guc(
+                cstr(b"pg_strict.metrics_listen_address\0"),
+                cstr(b"Address the pg_strict metrics worker listens on.\0"),
+                cstr(b"Use 0.0.0.0 to accept scrapes from other hosts.\0"),
+                setting,
+                GucContext::Postmaster,
+                GucFlags::default(),
+            );
+        }
+
+        if let Some(ref mut setting) = METRICS_PORT {
+            GucRegistry::define_int_guc(
+                cstr(b"pg_strict.metrics_port\0"),
+                cstr(b"Port serving pg_strict counters in Prometheus text format.\0"),
+                cstr(b"0 disables the metrics worker.\0"),
+                setting,
+                0,
+                65535,
+                GucContext::Postmaster,
+                GucFlags::default(),
+            );
+        }
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn current_modes() -> (StrictMode, StrictMode) {
+    let update_mode = unsafe {
+        REQUIRE_WHERE_ON_UPDATE_MODE
+            .as_mut()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictMode::Off)
+    };
+    let delete_mode = unsafe {
+        REQUIRE_WHERE_ON_DELETE_MODE
+            .as_mut()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictMode::Off)
+    };
+    (update_mode, delete_mode)
+}
+
+#[allow(static_mut_refs)]
+pub fn protect_catalogs_mode() -> StrictMode {
+    unsafe {
+        PROTECT_CATALOGS_MODE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictMode::Off)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn guard_truncate_mode() -> StrictMode {
+    unsafe {
+        GUARD_TRUNCATE_MODE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictMode::Off)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn lint_functions_mode() -> StrictMode {
+    unsafe {
+        LINT_FUNCTIONS_MODE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictMode::Off)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn current_preset() -> StrictPreset {
+    unsafe {
+        PRESET
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictPreset::None)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn require_bypass_reason() -> bool {
+    unsafe {
+        REQUIRE_BYPASS_REASON
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn audit_enabled() -> bool {
+    unsafe { AUDIT.as_ref().map(|setting| setting.get()).unwrap_or(false) }
+}
+
+#[allow(static_mut_refs)]
+pub fn notify_channel() -> Option<String> {
+    unsafe {
+        NOTIFY_CHANNEL
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn webhook_url() -> Option<String> {
+    unsafe {
+        WEBHOOK_URL
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn current_log_format() -> LogFormat {
+    unsafe {
+        LOG_FORMAT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(LogFormat::Text)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_warnings_per_minute() -> i32 {
+    unsafe {
+        MAX_WARNINGS_PER_MINUTE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_affected_rows() -> i32 {
+    unsafe {
+        MAX_AFFECTED_ROWS
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn sample_rate() -> f64 {
+    unsafe {
+        SAMPLE_RATE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(1.0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_affected_percent() -> f64 {
+    unsafe {
+        MAX_AFFECTED_PERCENT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0.0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_statement_cost() -> f64 {
+    unsafe {
+        MAX_STATEMENT_COST
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0.0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn statement_cost_mode() -> StrictMode {
+    unsafe {
+        STATEMENT_COST_MODE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictMode::Warn)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn seqscan_warn_size_kb() -> i32 {
+    unsafe {
+        SEQSCAN_WARN_SIZE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn report_rows_affected_enabled() -> bool {
+    unsafe {
+        REPORT_ROWS_AFFECTED
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn summary_at_commit_enabled() -> bool {
+    unsafe {
+        SUMMARY_AT_COMMIT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn rows_affected_threshold() -> i32 {
+    unsafe {
+        ROWS_AFFECTED_THRESHOLD
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(10000)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn recycle_bin_enabled() -> bool {
+    unsafe {
+        RECYCLE_BIN
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn recycle_bin_max_rows() -> i32 {
+    unsafe {
+        RECYCLE_BIN_MAX_ROWS
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(100000)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn recycle_bin_retention() -> i32 {
+    unsafe {
+        RECYCLE_BIN_RETENTION
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn recycle_bin_database() -> String {
+    unsafe {
+        RECYCLE_BIN_DATABASE
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "postgres".to_string())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn enforcement_point() -> EnforcementPoint {
+    unsafe {
+        ENFORCEMENT_POINT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(EnforcementPoint::Analyze)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn allow_explain_analyze() -> ExplainAnalyzeMode {
+    unsafe {
+        ALLOW_EXPLAIN_ANALYZE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(ExplainAnalyzeMode::Check)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn check_nested() -> NestedStatements {
+    unsafe {
+        CHECK_NESTED
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(NestedStatements::TopLevel)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn require_transaction_enabled() -> bool {
+    unsafe {
+        REQUIRE_TRANSACTION
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn require_timeouts_enabled() -> bool {
+    unsafe {
+        REQUIRE_TIMEOUTS
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_statement_timeout() -> i32 {
+    unsafe {
+        MAX_STATEMENT_TIMEOUT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_lock_timeout() -> i32 {
+    unsafe {
+        MAX_LOCK_TIMEOUT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn require_recent_backup() -> i32 {
+    unsafe {
+        REQUIRE_RECENT_BACKUP
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+pub fn last_backup_time() -> Option<String> {
+    unsafe { raw_string(LAST_BACKUP_TIME) }
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[allow(static_mut_refs)]
+pub fn backup_probe() -> Option<String> {
+    unsafe {
+        BACKUP_PROBE
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn backup_protected_tables() -> Vec<String> {
+    unsafe { string_list(BACKUP_PROTECTED_TABLES.as_ref()) }
+}
+
+#[allow(static_mut_refs)]
+pub fn report_cascades_enabled() -> bool {
+    unsafe {
+        REPORT_CASCADES
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn redact_literals_enabled() -> bool {
+    unsafe {
+        REDACT_LITERALS
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(false)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn log_plans() -> PlanFormat {
+    unsafe {
+        LOG_PLANS
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(PlanFormat::Off)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn log_directory() -> Option<String> {
+    unsafe {
+        LOG_DIRECTORY
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn log_filename() -> String {
+    unsafe {
+        LOG_FILENAME
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "pg_strict-%Y-%m-%d.log".to_string())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn message_template() -> Option<String> {
+    unsafe {
+        MESSAGE_TEMPLATE
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn message_language() -> MessageLanguage {
+    unsafe {
+        MESSAGE_LANGUAGE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(MessageLanguage::En)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn watchdog_max_duration() -> i32 {
+    unsafe {
+        WATCHDOG_MAX_DURATION
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn watchdog_action() -> WatchdogAction {
+    unsafe {
+        WATCHDOG_ACTION
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(WatchdogAction::Cancel)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn watchdog_database() -> String {
+    unsafe {
+        WATCHDOG_DATABASE
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "postgres".to_string())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn metrics_address() -> String {
+    unsafe {
+        METRICS_ADDRESS
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "127.0.0.1".to_string())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn metrics_port() -> i32 {
+    unsafe {
+        METRICS_PORT
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(0)
+    }
+}
+
+// Settings a preset stands in for while nobody has set them, in the order of
+// their slots in SET_EXPLICITLY.
+const PRESET_SETTINGS: [&str; 7] = [
+    UPDATE_MODE_GUC,
+    DELETE_MODE_GUC,
+    PROTECT_CATALOGS_GUC,
+    GUARD_TRUNCATE_GUC,
+    LINT_FUNCTIONS_GUC,
+    MAX_AFFECTED_ROWS_GUC,
+    MAX_AFFECTED_PERCENT_GUC,
+];
+const UPDATE_MODE_SLOT: usize = 0;
+const DELETE_MODE_SLOT: usize = 1;
+const PROTECT_CATALOGS_SLOT: usize = 2;
+const GUARD_TRUNCATE_SLOT: usize = 3;
+const LINT_FUNCTIONS_SLOT: usize = 4;
+const MAX_AFFECTED_ROWS_SLOT: usize = 5;
+const MAX_AFFECTED_PERCENT_SLOT: usize = 6;
+
+// Whether the current value of each preset setting was set by someone rather
+// than left at its built-in default. The check hook stores that in the value's
+// `extra`, which PostgreSQL hands back to the assign hook whenever the value
+// becomes current again: at RESET, at the end of a SET LOCAL, on rollback.
+static mut SET_EXPLICITLY: [bool; PRESET_SETTINGS.len()] = [false; PRESET_SETTINGS.len()];
+
+// Set while ProcessUtility runs a SET LOCAL statement, which is how the check
+// hook of pg_strict.override tells it from SET, set_config() or ALTER ROLE.
+static mut IN_SET_LOCAL: bool = false;
+// Whether the current pg_strict.override value came from SET LOCAL, kept the
+// same way as SET_EXPLICITLY.
+static mut OVERRIDE_SET_LOCALLY: bool = false;
+
+/// Whether nobody has set `name`, one of the settings a preset stands in for,
+/// anywhere (config file, ALTER ROLE/DATABASE, SET).
+#[allow(static_mut_refs)]
+pub fn setting_is_default(name: &str) -> bool {
+    PRESET_SETTINGS
+        .iter()
+        .position(|setting| *setting == name)
+        .is_none_or(|slot| unsafe { !SET_EXPLICITLY[slot] })
+}
+
+/// Notes whether a SET LOCAL statement is running, returning the previous state.
+pub fn mark_set_local(running: bool) -> bool {
+    unsafe {
+        let previous = IN_SET_LOCAL;
+        IN_SET_LOCAL = running;
+        previous
+    }
+}
+
+/// Whether the current value of pg_strict.override was set with SET LOCAL,
+/// rather than SET, set_config(), ALTER ROLE or the configuration file.
+pub fn override_set_locally() -> bool {
+    unsafe { OVERRIDE_SET_LOCALLY }
+}
+
+// PostgreSQL frees `extra` itself: with guc_free() from PostgreSQL 16, which
+// exports the matching guc_malloc(), and with free() before that.
+#[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+unsafe fn new_extra(flag: bool) -> *mut c_void {
+    unsafe extern "C" {
+        fn malloc(size: usize) -> *mut c_void;
+    }
+    let extra = unsafe { malloc(size_of::<bool>()) }.cast::<bool>();
+    if !extra.is_null() {
+        unsafe { *extra = flag };
+    }
+    extra.cast()
+}
+
+#[cfg(not(any(feature = "pg13", feature = "pg14", feature = "pg15")))]
+unsafe fn new_extra(flag: bool) -> *mut c_void {
+    let extra = unsafe { pg_sys::guc_malloc(pg_sys::LOG as i32, size_of::<bool>()) }.cast::<bool>();
+    if !extra.is_null() {
+        unsafe { *extra = flag };
+    }
+    extra.cast()
+}
+
+unsafe fn extra_flag(extra: *mut c_void) -> bool {
+    !extra.is_null() && unsafe { *extra.cast::<bool>() }
+}
+
+fn explicit_source(source: pg_sys::GucSource::Type) -> bool {
+    !matches!(
+        source,
+        pg_sys::GucSource::PGC_S_DEFAULT | pg_sys::GucSource::PGC_S_DYNAMIC_DEFAULT
+    )
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn check_source<T>(
+    _newval: *mut T,
+    extra: *mut *mut c_void,
+    source: pg_sys::GucSource::Type,
+) -> bool {
+    unsafe { *extra = new_extra(explicit_source(source)) };
+    unsafe { !(*extra).is_null() }
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn assign_source<T, const SLOT: usize>(_newval: T, extra: *mut c_void) {
+    unsafe { SET_EXPLICITLY[SLOT] = extra_flag(extra) };
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn check_override(
+    _newval: *mut *mut c_char,
+    extra: *mut *mut c_void,
+    source: pg_sys::GucSource::Type,
+) -> bool {
+    let local = unsafe { IN_SET_LOCAL } && source == pg_sys::GucSource::PGC_S_SESSION;
+    unsafe { *extra = new_extra(local) };
+    unsafe { !(*extra).is_null() }
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn assign_override(_newval: *const c_char, extra: *mut c_void) {
+    unsafe { OVERRIDE_SET_LOCALLY = extra_flag(extra) };
+}
+
+#[allow(static_mut_refs)]
+pub fn override_reason() -> Option<String> {
+    unsafe {
+        OVERRIDE_REASON
+            .as_ref()
+            .and_then(|setting| setting.get())
+            .map(|value| value.to_string_lossy().trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn max_snooze_seconds() -> i32 {
+    unsafe {
+        MAX_SNOOZE_SECONDS
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(3600)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn configured_allowed_queryids() -> Vec<i64> {
+    unsafe { ALLOWED_QUERYID_LIST.clone() }
+}
+
+#[allow(static_mut_refs)]
+pub fn current_enforce() -> StrictEnforce {
+    unsafe {
+        ENFORCE
+            .as_ref()
+            .map(|setting| setting.get())
+            .unwrap_or(StrictEnforce::Normal)
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn maintenance_windows() -> Vec<schedule::Window> {
+    unsafe { MAINTENANCE_WINDOW_LIST.clone() }
+}
+
+pub fn error_code() -> Option<String> {
+    unsafe { raw_string(ERROR_CODE) }
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn escalate_at() -> Option<String> {
+    unsafe { raw_string(ESCALATE_AT) }
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[allow(static_mut_refs)]
+pub fn exempt_application_names() -> Vec<String> {
+    unsafe { string_list(EXEMPT_APPLICATION_NAMES.as_ref()) }
+}
+
+#[allow(static_mut_refs)]
+pub fn exempt_backend_types() -> Vec<String> {
+    unsafe { string_list(EXEMPT_BACKEND_TYPES.as_ref()) }
+}
+
+#[allow(static_mut_refs)]
+pub fn trusted_functions() -> Vec<String> {
+    unsafe { string_list(TRUSTED_FUNCTIONS.as_ref()) }
+}
+
+pub fn mode_to_str(mode: StrictMode) -> &'static str {
+    match mode {
+        StrictMode::Off => "off",
+        StrictMode::Warn => "warn",
+        StrictMode::On => "on",
+        StrictMode::Learn => "learn",
+        StrictMode::Notice => "notice",
+        StrictMode::Log => "log",
+    }
+}
+
+pub fn enforce_to_str(enforce: StrictEnforce) -> &'static str {
+    match enforce {
+        StrictEnforce::Normal => "normal",
+        StrictEnforce::Force => "force",
+    }
+}
+
+pub fn preset_to_str(preset: StrictPreset) -> &'static str {
+    match preset {
+        StrictPreset::None => "none",
+        StrictPreset::Production => "production",
+        StrictPreset::Staging => "staging",
+        StrictPreset::Development => "development",
+    }
+}
+
+fn string_list(setting: Option<&GucSetting<Option<CString>>>) -> Vec<String> {
+    let Some(value) = setting.and_then(|setting| setting.get()) else {
+        return Vec::new();
+    };
+    parse_list(&value.to_string_lossy())
+}
+
+pub fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+type StringCheckHook = unsafe extern "C-unwind" fn(
+    *mut *mut c_char,
+    *mut *mut c_void,
+    pg_sys::GucSource::Type,
+) -> bool;
+type StringAssignHook = unsafe extern "C-unwind" fn(*const c_char, *mut c_void);
+
+/// Defines a string setting whose values are validated when they are set, so
+/// a bad value fails the SET (or is skipped with a LOG line when it comes from
+/// the configuration file) instead of being ignored at every statement.
+unsafe fn define_checked_string_guc(
+    name: &'static CStr,
+    short_description: &'static CStr,
+    long_description: &'static CStr,
+    value: *mut *mut c_char,
+    context: pg_sys::GucContext::Type,
+    check: StringCheckHook,
+    assign: Option<StringAssignHook>,
+) {
+    unsafe {
+        pg_sys::DefineCustomStringVariable(
+            name.as_ptr(),
+            short_description.as_ptr(),
+            long_description.as_ptr(),
+            value,
+            std::ptr::null(),
+            context,
+            0,
+            Some(check),
+            assign,
+            None,
+        )
+    };
+}
+
+unsafe fn raw_string(value: *const c_char) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(value) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Fails a check hook with `detail` shown under PostgreSQL's own
+/// "invalid value for parameter" message.
+fn reject(detail: &str) -> bool {
+    let detail = CString::new(detail).unwrap_or_default();
+    unsafe { pg_sys::GUC_check_errdetail_string = pg_sys::pstrdup(detail.as_ptr()) };
+    false
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn check_maintenance_windows(
+    newval: *mut *mut c_char,
+    _extra: *mut *mut c_void,
+    _source: pg_sys::GucSource::Type,
+) -> bool {
+    let spec = unsafe { raw_string(*newval) }.unwrap_or_default();
+    for entry in parse_list(&spec) {
+        if let Err(error) = schedule::parse_window(&entry) {
+            return reject(&format!("Window \"{entry}\": {error}"));
+        }
+    }
+    true
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn assign_maintenance_windows(newval: *const c_char, _extra: *mut c_void) {
+    let spec = unsafe { raw_string(newval) }.unwrap_or_default();
+    unsafe { MAINTENANCE_WINDOW_LIST = schedule::parse_windows(&spec) };
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn check_timestamp(
+    newval: *mut *mut c_char,
+    _extra: *mut *mut c_void,
+    _source: pg_sys::GucSource::Type,
+) -> bool {
+    let value = unsafe { raw_string(*newval) }.unwrap_or_default();
+    let value = value.trim();
+    if value.is_empty() || value.parse::<TimestampWithTimeZone>().is_ok() {
+        return true;
+    }
+    reject(&format!("\"{value}\" is not a timestamp with time zone."))
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn check_error_code(
+    newval: *mut *mut c_char,
+    _extra: *mut *mut c_void,
+    _source: pg_sys::GucSource::Type,
+) -> bool {
+    let value = unsafe { raw_string(*newval) }.unwrap_or_default();
+    let value = value.trim();
+    if value.is_empty() {
+        return true;
+    }
+    if value.len() != 5
+        || !value
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || ch.is_ascii_uppercase())
+    {
+        return reject(&format!(
+            "\"{value}\" is not a SQLSTATE: use five digits or upper-case letters."
+        ));
+    }
+    // Classes 00, 01 and 02 mean success, warning and no data.
+    if ["00", "01", "02"].contains(&&value[..2]) {
+        return reject(&format!("SQLSTATE class {} is not an error.", &value[..2]));
+    }
+    true
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn check_allowed_queryids(
+    newval: *mut *mut c_char,
+    _extra: *mut *mut c_void,
+    _source: pg_sys::GucSource::Type,
+) -> bool {
+    let value = unsafe { raw_string(*newval) }.unwrap_or_default();
+    let ids = parse_list(&value);
+    if let Some(id) = ids.iter().find(|id| id.parse::<i64>().is_err()) {
+        return reject(&format!("\"{id}\" is not a query identifier."));
+    }
+    // While the preloaded libraries load, one listed after pg_strict may still
+    // enable query identifiers, so only `off` is certain then.
+    let disabled = if unsafe { pg_sys::process_shared_preload_libraries_in_progress } {
+        query_id_setting_off()
+    } else {
+        query_ids_disabled()
+    };
+    if !ids.is_empty() && disabled {
+        return reject(QUERY_IDS_DISABLED);
+    }
+    true
+}
+
+pub const QUERY_IDS_DISABLED: &str = "statements have no query identifier to match (compute_query_id is off, or auto without a module such as pg_stat_statements that enables query identifiers).";
+
+/// Whether statements carry no query identifier for the allowlist to match,
+/// as `IsQueryIdEnabled()` decides; that function is inline, so it is
+/// repeated here. PostgreSQL 13 has no `compute_query_id`, and
+/// pg_stat_statements computes identifiers itself.
+#[cfg(not(feature = "pg13"))]
+pub fn query_ids_disabled() -> bool {
+    use pg_sys::ComputeQueryIdType::{COMPUTE_QUERY_ID_OFF, COMPUTE_QUERY_ID_ON, Type};
+    match unsafe { pg_sys::compute_query_id } as Type {
+        COMPUTE_QUERY_ID_OFF => true,
+        COMPUTE_QUERY_ID_ON => false,
+        _ => !unsafe { pg_sys::query_id_enabled },
+    }
+}
+
+#[cfg(feature = "pg13")]
+pub fn query_ids_disabled() -> bool {
+    false
+}
+
+#[cfg(not(feature = "pg13"))]
+fn query_id_setting_off() -> bool {
+    let setting = unsafe { pg_sys::compute_query_id } as pg_sys::ComputeQueryIdType::Type;
+    setting == pg_sys::ComputeQueryIdType::COMPUTE_QUERY_ID_OFF
+}
+
+#[cfg(feature = "pg13")]
+fn query_id_setting_off() -> bool {
+    false
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn assign_allowed_queryids(newval: *const c_char, _extra: *mut c_void) {
+    let value = unsafe { raw_string(newval) }.unwrap_or_default();
+    let ids = parse_list(&value)
+        .iter()
+        .filter_map(|id| id.parse::<i64>().ok())
+        .collect();
+    unsafe { ALLOWED_QUERYID_LIST = ids };
 }
 
 fn cstr(bytes: &'static [u8]) -> &'static CStr {
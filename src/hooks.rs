@@ -1,7 +1,36 @@
 use crate::analyzer::Operation;
-use crate::guc::{StrictMode, current_modes};
+use crate::approval;
+use crate::audit;
+use crate::backup;
+use crate::bypass::{self, Mechanism};
+use crate::cascade;
+use crate::catalog;
+use crate::custom_rules;
+use crate::estimate;
+use crate::exemptions;
+use crate::guc::{self, EnforcementPoint, ExplainAnalyzeMode, NestedStatements, StrictMode};
+use crate::learn;
+use crate::logging::{self, ViolationLogEntry};
+use crate::messages;
+use crate::notify;
+use crate::plan;
+use crate::plpgsql;
+use crate::plugins;
+use crate::policy;
+use crate::recycle;
+use crate::report::{self, Violation};
+use crate::rowlimit;
+use crate::rules;
+use crate::sqlstate;
+use crate::stats;
+use crate::summary;
+use crate::triggers;
+use crate::utility;
+use crate::webhook;
+use pgrx::PgList;
 use pgrx::pg_guard;
 use pgrx::pg_sys;
+use std::ffi::CStr;
 
 // PostgreSQL 14+ has JumbleState parameter, PG13 does not
 #[cfg(feature = "pg13")]
@@ -16,13 +45,26 @@ type PostParseAnalyzeHook = unsafe extern "C-unwind" fn(
 );
 
 static mut PREV_POST_PARSE_ANALYZE_HOOK: Option<PostParseAnalyzeHook> = None;
+static mut PREV_NEEDS_FMGR_HOOK: pg_sys::needs_fmgr_hook_type = None;
+static mut PREV_FMGR_HOOK: pg_sys::fmgr_hook_type = None;
+static mut PREV_PLANNER_HOOK: pg_sys::planner_hook_type = None;
+static mut PREV_EXECUTOR_START_HOOK: pg_sys::ExecutorStart_hook_type = None;
+static mut PREV_EXECUTOR_RUN_HOOK: pg_sys::ExecutorRun_hook_type = None;
+static mut PREV_EXECUTOR_FINISH_HOOK: pg_sys::ExecutorFinish_hook_type = None;
+static mut PREV_EXECUTOR_END_HOOK: pg_sys::ExecutorEnd_hook_type = None;
+static mut PREV_PROCESS_UTILITY_HOOK: pg_sys::ProcessUtility_hook_type = None;
+static mut PREV_EMIT_LOG_HOOK: pg_sys::emit_log_hook_type = None;
 
-fn generate_violation_message(operation: Operation) -> String {
-    format!(
-        "pg_strict: {} statement without WHERE clause detected. This operation would affect all rows in the table.",
-        operation.as_str()
-    )
-}
+// Query descriptors that passed through pg_strict's ExecutorRun hook and have
+// not ended yet.
+static mut RUN_QUERY_DESCS: Vec<usize> = Vec::new();
+// Times the ExecutorRun hook was put back at the head of the chain.
+static mut HOOK_REINSTALLS: u64 = 0;
+static mut HOOKS_INSTALLED: bool = false;
+
+// Executor nesting level, as tracked by pg_stat_statements: statements run by
+// functions and triggers execute while the outer statement is in ExecutorRun/Finish.
+static mut EXECUTOR_DEPTH: u32 = 0;
 
 unsafe fn analyzed_query_operation(query: *mut pg_sys::Query) -> Option<(Operation, bool)> {
     if query.is_null() {
@@ -46,32 +88,696 @@ unsafe fn analyzed_query_operation(query: *mut pg_sys::Query) -> Option<(Operati
     Some((operation, has_where))
 }
 
-unsafe fn check_query_strictness_from_query(query: *mut pg_sys::Query) {
-    let (update_mode, delete_mode) = current_modes();
+/// The part of `source_text` that `query` was parsed from.
+unsafe fn source_statement_text(
+    source_text: *const std::ffi::c_char,
+    query: *mut pg_sys::Query,
+) -> String {
+    unsafe { statement_text_at(source_text, (*query).stmt_location, (*query).stmt_len) }
+}
+
+/// The `length` bytes of `source_text` starting at `location`; a length of
+/// zero means the rest of the string.
+pub unsafe fn statement_text_at(
+    source_text: *const std::ffi::c_char,
+    location: i32,
+    length: i32,
+) -> String {
+    if source_text.is_null() {
+        return String::new();
+    }
+
+    let source = unsafe { CStr::from_ptr(source_text) }.to_bytes();
+
+    let start = if location > 0 {
+        (location as usize).min(source.len())
+    } else {
+        0
+    };
+    let end = if length > 0 {
+        (start + length as usize).min(source.len())
+    } else {
+        source.len()
+    };
+    String::from_utf8_lossy(&source[start..end])
+        .trim()
+        .to_string()
+}
+
+/// Counts, persists and announces a violation before it is reported to the client.
+fn record_violation(
+    query_id: i64,
+    mode: StrictMode,
+    operation: &str,
+    table: Option<&str>,
+    statement: &str,
+) {
+    let blocked = mode == StrictMode::On;
+    plan::log_plan(operation, blocked, statement);
+    let statement = logging::loggable_statement(statement);
+    stats::count_violation(mode);
+    stats::count_offender(query_id, &statement);
+    audit::record_violation(mode, operation, query_id, &statement);
+    let fingerprint = stats::fingerprint(query_id, &statement);
+    notify::notify_violation(operation, fingerprint, blocked);
+    webhook::enqueue(&notify::violation_payload(operation, fingerprint, blocked));
+    logging::log_violation(&ViolationLogEntry {
+        operation,
+        table,
+        blocked,
+        query_id,
+    });
+}
+
+/// Rejects a flagged statement that is about to run anyway when it lacks the
+/// safeguards required by `pg_strict.require_transaction`,
+/// `pg_strict.require_timeouts` and `pg_strict.require_recent_backup`.
+fn require_safeguards(operation: Operation, relid: Option<pg_sys::Oid>) {
+    if guc::require_transaction_enabled() && !unsafe { pg_sys::IsTransactionBlock() } {
+        stats::increment(stats::Counter::ViolationsBlocked);
+        Violation::outside_transaction(operation).raise(StrictMode::On);
+    }
+
+    if guc::require_timeouts_enabled() {
+        let problems: Vec<String> = [
+            report::timeout_problem(
+                "statement_timeout",
+                unsafe { pg_sys::StatementTimeout },
+                guc::max_statement_timeout(),
+            ),
+            report::timeout_problem(
+                "lock_timeout",
+                unsafe { pg_sys::LockTimeout },
+                guc::max_lock_timeout(),
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !problems.is_empty() {
+            stats::increment(stats::Counter::ViolationsBlocked);
+            Violation::unbounded_timeouts(operation, &problems).raise(StrictMode::On);
+        }
+    }
+
+    backup::require_recent(operation, relid);
+}
+
+/// Saves the rows of a flagged statement that is about to run anyway and
+/// remembers it for the summary at COMMIT.
+unsafe fn capture_pre_images(query: *mut pg_sys::Query, operation: Operation, statement: &str) {
+    let relid = unsafe { catalog::target_relation(query) };
+    let table = relid.map(catalog::qualified_relation_name);
+    summary::note(operation, table.as_deref(), executor_depth());
+    if let Some(relid) = relid {
+        recycle::capture(operation, relid, statement);
+    }
+}
+
+unsafe fn check_catalog_protection(
+    source_text: *const std::ffi::c_char,
+    query: *mut pg_sys::Query,
+) {
+    if query.is_null() || !policy::catalog_protection_configured() {
+        return;
+    }
+
+    let command = match unsafe { (*query).commandType } {
+        pg_sys::CmdType::CMD_INSERT => "INSERT",
+        pg_sys::CmdType::CMD_UPDATE => "UPDATE",
+        pg_sys::CmdType::CMD_DELETE => "DELETE",
+        _ => return,
+    };
+
+    let Some(relid) = (unsafe { catalog::target_relation(query) }) else {
+        return;
+    };
+    if !catalog::is_system_catalog(relid) {
+        return;
+    }
+
+    // Learning only covers the WHERE clause rules.
+    let mode = policy::effective_protect_catalogs_mode();
+    if matches!(mode, StrictMode::Off | StrictMode::Learn) {
+        return;
+    }
+
+    let table = catalog::qualified_relation_name(relid);
+    let statement = unsafe { source_statement_text(source_text, query) };
+    record_violation(
+        unsafe { (*query).queryId } as i64,
+        mode,
+        command,
+        Some(&table),
+        &statement,
+    );
+    Violation::catalog_modification(command, &table)
+        .with_query_id(unsafe { (*query).queryId } as i64)
+        .raise(mode);
+}
+
+/// True for UPDATE and DELETE statements the rewriter added for a rule. Parse
+/// analysis only sees the statement that fired the rule, so these are
+/// checked when they are planned.
+unsafe fn added_by_rule(query: *mut pg_sys::Query) -> bool {
+    matches!(
+        unsafe { (*query).querySource },
+        pg_sys::QuerySource::QSRC_INSTEAD_RULE | pg_sys::QuerySource::QSRC_NON_INSTEAD_RULE
+    )
+}
+
+/// True when a rule replaces the statement with one that does not modify
+/// rows, so only the rewritten statements are worth checking.
+unsafe fn rewritten_away(query: *mut pg_sys::Query) -> bool {
+    unsafe { catalog::target_relation(query) }.is_some_and(|relid| {
+        catalog::replaced_by_harmless_rule(relid, unsafe { (*query).commandType })
+    })
+}
 
-    if update_mode == StrictMode::Off && delete_mode == StrictMode::Off {
+/// Checks an analyzed query against the WHERE clause and catalog rules.
+/// `source_text` is the string it was parsed from.
+unsafe fn check_query_strictness_from_query(
+    source_text: *const std::ffi::c_char,
+    query: *mut pg_sys::Query,
+) {
+    // Reads and utility statements, most of the traffic, need nothing else.
+    if query.is_null()
+        || !matches!(
+            unsafe { (*query).commandType },
+            pg_sys::CmdType::CMD_INSERT | pg_sys::CmdType::CMD_UPDATE | pg_sys::CmdType::CMD_DELETE
+        )
+    {
         return;
     }
+    unsafe { check_catalog_protection(source_text, query) };
 
     let (operation, has_where) = match unsafe { analyzed_query_operation(query) } {
         Some(info) => info,
         None => return,
     };
+    if !has_where && unsafe { rewritten_away(query) } {
+        return;
+    }
+    let _timer = stats::CheckTimer::start();
 
     if has_where {
         return;
     }
 
-    let mode = match operation {
-        Operation::Update => update_mode,
-        Operation::Delete => delete_mode,
+    let relid = unsafe { catalog::target_relation(query) };
+    let mode = explained_mode(policy::effective_mode(operation, relid));
+    if mode == StrictMode::Off {
+        return;
+    }
+
+    // Learn mode observes every session, exempt or not, so recommendations can
+    // include the exemptions themselves.
+    if mode == StrictMode::Learn {
+        if !policy::sampled(mode) {
+            return;
+        }
+        let statement = unsafe { source_statement_text(source_text, query) };
+        let table = relid.map(catalog::qualified_relation_name);
+        learn::observe(operation.as_str(), table.as_deref(), &statement);
+        return;
+    }
+
+    if exemptions::statement_is_exempt() {
+        return;
+    }
+
+    if exemptions::query_id_is_allowed(unsafe { (*query).queryId } as i64) {
+        return;
+    }
+
+    if policy::session_bypass_allowed() {
+        // An armed pg_strict_allow_next() is used up only by the statement it
+        // lets through.
+        if let Some(reason) = bypass::take_allow_next() {
+            let statement = unsafe { source_statement_text(source_text, query) };
+            require_safeguards(operation, unsafe { catalog::target_relation(query) });
+            bypass::report_bypass(
+                Mechanism::AllowNext,
+                &reason,
+                operation,
+                &statement,
+                executor_depth(),
+            );
+            unsafe { capture_pre_images(query, operation, &statement) };
+            return;
+        }
+
+        if let Some(reason) = bypass::active_override() {
+            let statement = unsafe { source_statement_text(source_text, query) };
+            require_safeguards(operation, unsafe { catalog::target_relation(query) });
+            bypass::report_bypass(
+                Mechanism::Override,
+                &reason,
+                operation,
+                &statement,
+                executor_depth(),
+            );
+            unsafe { capture_pre_images(query, operation, &statement) };
+            return;
+        }
+
+        if let Some(reason) = bypass::active_snooze() {
+            let statement = unsafe { source_statement_text(source_text, query) };
+            require_safeguards(operation, unsafe { catalog::target_relation(query) });
+            bypass::report_bypass(
+                Mechanism::Snooze,
+                &reason,
+                operation,
+                &statement,
+                executor_depth(),
+            );
+            unsafe { capture_pre_images(query, operation, &statement) };
+            return;
+        }
+    }
+
+    let statement = unsafe { source_statement_text(source_text, query) };
+    if approval::consume(&statement) {
+        require_safeguards(operation, unsafe { catalog::target_relation(query) });
+        bypass::report_bypass(
+            Mechanism::Approval,
+            "approved statement",
+            operation,
+            &statement,
+            executor_depth(),
+        );
+        unsafe { capture_pre_images(query, operation, &statement) };
+        return;
+    }
+
+    if mode.is_warning() {
+        require_safeguards(operation, relid);
+    }
+    if !policy::sampled(mode) {
+        // Left out of the sample: runs as warned, but unreported.
+        unsafe { capture_pre_images(query, operation, &statement) };
+        return;
+    }
+    if let Some(relid) = relid {
+        stats::count_table_violation(relid, operation, mode);
+    }
+    let table = relid.map(catalog::qualified_relation_name);
+    record_violation(
+        unsafe { (*query).queryId } as i64,
+        mode,
+        operation.as_str(),
+        table.as_deref(),
+        &statement,
+    );
+
+    let mut violation = Violation::missing_where(operation, table.as_deref())
+        .with_rows_estimated(relid.and_then(estimate::reltuples))
+        .with_origin(plpgsql::dynamic_origin())
+        .with_query_id(unsafe { (*query).queryId } as i64);
+    if let Some(relid) = relid {
+        violation = violation.with_table_hint(operation, relid);
+    }
+    if unsafe { added_by_rule(query) } {
+        violation.detail = format!(
+            "{} {}",
+            violation.detail,
+            messages::lookup("report.rewrite_rule")
+        );
+    }
+    if let Some(triggers) = relid.and_then(|relid| triggers::summary(relid, operation)) {
+        violation = violation.with_triggers(&triggers);
+    }
+    if operation == Operation::Delete && guc::report_cascades_enabled() {
+        if let Some(cascades) = relid.and_then(cascade::summary) {
+            violation = violation.with_cascades(&cascades);
+        }
+    }
+    if mode.is_warning() {
+        summary::note(operation, table.as_deref(), executor_depth());
+        if let Some(relid) = relid {
+            recycle::capture(operation, relid, &statement);
+        }
+        if let Some(policy::Escalation::Pending(deadline)) = policy::escalation() {
+            violation.message = format!(
+                "{} {}",
+                violation.message,
+                messages::text("report.escalation", &[("deadline", &deadline)])
+            );
+        }
+    }
+    violation.raise(mode);
+}
+
+/// Reports an UPDATE or DELETE whose estimated rows exceed
+/// `pg_strict.max_affected_percent` of the target table. Statements without a
+/// WHERE clause are left to the WHERE check.
+unsafe fn check_selectivity(
+    parse: *mut pg_sys::Query,
+    query_string: *const std::ffi::c_char,
+    stmt: *mut pg_sys::PlannedStmt,
+    target: (Operation, pg_sys::Oid),
+) {
+    let (operation, relid) = target;
+    let threshold = rules::max_affected_percent(operation, Some(relid));
+    if threshold <= 0.0 {
+        return;
+    }
+
+    let mode = explained_mode(policy::effective_mode(operation, Some(relid)));
+    if matches!(mode, StrictMode::Off | StrictMode::Learn) {
+        return;
+    }
+    if exemptions::statement_is_exempt()
+        || exemptions::query_id_is_allowed(unsafe { (*parse).queryId } as i64)
+    {
+        return;
+    }
+    if policy::session_bypass_allowed()
+        && (bypass::active_override().is_some() || bypass::active_snooze().is_some())
+    {
+        return;
+    }
+
+    let (Some(rows), Some(reltuples)) = (
+        unsafe { estimate::planned_rows(stmt) },
+        estimate::reltuples(relid),
+    ) else {
+        return;
+    };
+    let percent = estimate::affected_percent(rows, reltuples);
+    if percent <= threshold {
+        return;
+    }
+
+    if mode.is_warning() {
+        require_safeguards(operation, Some(relid));
+    }
+    let table = catalog::qualified_relation_name(relid);
+    let statement = unsafe { source_statement_text(query_string, parse) };
+    if !policy::sampled(mode) {
+        summary::note(operation, Some(&table), executor_depth());
+        recycle::capture(operation, relid, &statement);
+        return;
+    }
+    stats::count_table_violation(relid, operation, mode);
+    record_violation(
+        unsafe { (*parse).queryId } as i64,
+        mode,
+        operation.as_str(),
+        Some(&table),
+        &statement,
+    );
+    let mut violation =
+        Violation::too_selective(operation, &table, rows, reltuples, percent, threshold)
+            .with_origin(plpgsql::dynamic_origin())
+            .with_query_id(unsafe { (*parse).queryId } as i64);
+    if let Some(triggers) = triggers::summary(relid, operation) {
+        violation = violation.with_triggers(&triggers);
+    }
+    if mode.is_warning() {
+        summary::note(operation, Some(&table), executor_depth());
+        recycle::capture(operation, relid, &statement);
+    }
+    violation.raise(mode);
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_planner_hook(
+    parse: *mut pg_sys::Query,
+    query_string: *const std::ffi::c_char,
+    cursor_options: i32,
+    bound_params: pg_sys::ParamListInfo,
+) -> *mut pg_sys::PlannedStmt {
+    // The planner rewrites its input, so note the target before planning.
+    let target = match unsafe { analyzed_query_operation(parse) } {
+        Some((operation, true)) => {
+            unsafe { catalog::target_relation(parse) }.map(|relid| (operation, relid))
+        }
+        _ => None,
+    };
+
+    // Plain EXPLAIN only shows the plan, so it is never reported.
+    let mut explain_only = false;
+    let skipped = skip_nested() || explain_analyze_exempt();
+    if let Some(_guard) = CheckGuard::enter() {
+        explain_only = take_explain_only();
+        // Parse analysis only sees the EXPLAIN wrapper and the statement that
+        // fired a rule, so statements run by EXPLAIN ANALYZE and rule actions
+        // are checked here at either enforcement point.
+        if !explain_only
+            && !skipped
+            && (guc::enforcement_point() == EnforcementPoint::Planner
+                || in_explain_analyze()
+                || unsafe { added_by_rule(parse) })
+        {
+            unsafe { check_query_strictness_from_query(query_string, parse) };
+        }
+    }
+
+    let stmt = match unsafe { PREV_PLANNER_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(parse, query_string, cursor_options, bound_params) },
+        None => unsafe {
+            pg_sys::standard_planner(parse, query_string, cursor_options, bound_params)
+        },
+    };
+    if explain_only || skipped {
+        return stmt;
+    }
+
+    if let Some(_guard) = CheckGuard::enter() {
+        if let Some(target) = target {
+            unsafe { check_selectivity(parse, query_string, stmt, target) };
+            unsafe { check_sequential_scan(stmt, target.0) };
+        }
+        unsafe { check_statement_cost(parse, query_string, stmt) };
+    }
+    stmt
+}
+
+/// Warns when an UPDATE or DELETE with a WHERE clause is still planned with a
+/// sequential scan of a table larger than `pg_strict.seqscan_warn_size`.
+unsafe fn check_sequential_scan(stmt: *mut pg_sys::PlannedStmt, operation: Operation) {
+    let threshold_kb = guc::seqscan_warn_size_kb() as i64;
+    if threshold_kb <= 0 || exemptions::statement_is_exempt() {
+        return;
+    }
+
+    for relid in unsafe { estimate::sequential_scans(stmt) } {
+        let size_kb = estimate::relation_size_kb(relid);
+        if size_kb > threshold_kb {
+            let table = catalog::qualified_relation_name(relid);
+            Violation::sequential_scan(operation, &table, size_kb).raise(StrictMode::Warn);
+        }
+    }
+}
+
+// Set by the parse analysis of an EXPLAIN without ANALYZE and consumed when the
+// explained statement is planned.
+static mut EXPLAIN_ONLY: bool = false;
+
+unsafe fn note_explain_only(query: *mut pg_sys::Query) {
+    let utility = if query.is_null() {
+        std::ptr::null_mut()
+    } else {
+        unsafe { (*query).utilityStmt }
+    };
+    let explain_only = !utility.is_null()
+        && unsafe { (*utility).type_ } == pg_sys::NodeTag::T_ExplainStmt
+        && !unsafe { explain_analyzes(utility as *mut pg_sys::ExplainStmt) };
+    unsafe { EXPLAIN_ONLY = explain_only };
+}
+
+fn take_explain_only() -> bool {
+    unsafe {
+        let explain_only = EXPLAIN_ONLY;
+        EXPLAIN_ONLY = false;
+        explain_only
+    }
+}
+
+// Executor depth of the EXPLAIN ANALYZE being processed, if any. Statements
+// planned at that depth are the one being explained.
+static mut EXPLAIN_ANALYZE_DEPTH: Option<u32> = None;
+
+struct ExplainAnalyzeGuard(Option<u32>);
+
+impl ExplainAnalyzeGuard {
+    unsafe fn enter(pstmt: *mut pg_sys::PlannedStmt) -> Self {
+        let previous = unsafe { EXPLAIN_ANALYZE_DEPTH };
+        let utility = if pstmt.is_null() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { (*pstmt).utilityStmt }
+        };
+        if !utility.is_null()
+            && unsafe { (*utility).type_ } == pg_sys::NodeTag::T_ExplainStmt
+            && unsafe { explain_analyzes(utility as *mut pg_sys::ExplainStmt) }
+        {
+            unsafe { EXPLAIN_ANALYZE_DEPTH = Some(executor_depth()) };
+        }
+        ExplainAnalyzeGuard(previous)
+    }
+}
+
+impl Drop for ExplainAnalyzeGuard {
+    fn drop(&mut self) {
+        unsafe { EXPLAIN_ANALYZE_DEPTH = self.0 };
+    }
+}
+
+fn in_explain_analyze() -> bool {
+    (unsafe { EXPLAIN_ANALYZE_DEPTH }) == Some(executor_depth())
+}
+
+fn explain_analyze_exempt() -> bool {
+    in_explain_analyze() && guc::allow_explain_analyze() == ExplainAnalyzeMode::Exempt
+}
+
+/// The mode for a statement run by EXPLAIN ANALYZE, following
+/// `pg_strict.allow_explain_analyze`; other statements keep `mode`.
+fn explained_mode(mode: StrictMode) -> StrictMode {
+    if !in_explain_analyze() {
+        return mode;
+    }
+    match guc::allow_explain_analyze() {
+        ExplainAnalyzeMode::Check => mode,
+        ExplainAnalyzeMode::Exempt => StrictMode::Off,
+        ExplainAnalyzeMode::Warn => StrictMode::Warn,
+        ExplainAnalyzeMode::Block => StrictMode::On,
+    }
+}
+
+unsafe fn explain_analyzes(explain: *mut pg_sys::ExplainStmt) -> bool {
+    let options = unsafe { PgList::<pg_sys::DefElem>::from_pg((*explain).options) };
+    options.iter_ptr().any(|option| {
+        let name = unsafe { CStr::from_ptr((*option).defname) };
+        name.to_bytes() == b"analyze" && unsafe { pg_sys::defGetBoolean(option) }
+    })
+}
+
+/// Reports any statement whose total plan cost exceeds `pg_strict.max_statement_cost`.
+unsafe fn check_statement_cost(
+    parse: *mut pg_sys::Query,
+    query_string: *const std::ffi::c_char,
+    stmt: *mut pg_sys::PlannedStmt,
+) {
+    let threshold = guc::max_statement_cost();
+    if threshold <= 0.0 || stmt.is_null() || unsafe { (*stmt).planTree.is_null() } {
+        return;
+    }
+    let mode = guc::statement_cost_mode();
+    if matches!(mode, StrictMode::Off | StrictMode::Learn) {
+        return;
+    }
+
+    let cost = unsafe { (*(*stmt).planTree).total_cost };
+    if cost <= threshold {
+        return;
+    }
+    if exemptions::statement_is_exempt()
+        || exemptions::query_id_is_allowed(unsafe { (*parse).queryId } as i64)
+    {
+        return;
+    }
+
+    let command = match unsafe { (*stmt).commandType } {
+        pg_sys::CmdType::CMD_SELECT => "SELECT",
+        pg_sys::CmdType::CMD_INSERT => "INSERT",
+        pg_sys::CmdType::CMD_UPDATE => "UPDATE",
+        pg_sys::CmdType::CMD_DELETE => "DELETE",
+        #[cfg(not(any(feature = "pg13", feature = "pg14")))]
+        pg_sys::CmdType::CMD_MERGE => "MERGE",
+        // Utility and empty commands are not planned into a costed tree.
+        _ => return,
+    };
+    let statement = unsafe { source_statement_text(query_string, parse) };
+    record_violation(
+        unsafe { (*parse).queryId } as i64,
+        mode,
+        command,
+        None,
+        &statement,
+    );
+    Violation::costly_statement(command, cost, threshold)
+        .with_query_id(unsafe { (*parse).queryId } as i64)
+        .raise(mode);
+}
+
+// Set while pg_strict runs its own SPI queries so they are not checked recursively.
+static mut IN_CHECK: bool = false;
+
+struct CheckGuard;
+
+impl CheckGuard {
+    fn enter() -> Option<Self> {
+        if unsafe { IN_CHECK } {
+            return None;
+        }
+        unsafe { IN_CHECK = true };
+        Some(CheckGuard)
+    }
+}
+
+impl Drop for CheckGuard {
+    fn drop(&mut self) {
+        unsafe { IN_CHECK = false };
+    }
+}
+
+/// Runs `f` with pg_strict's checks suspended, for API functions that plan
+/// user statements on the caller's behalf without running them.
+pub fn without_checks<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = CheckGuard::enter();
+    f()
+}
+
+unsafe fn run_checks(pstate: *mut pg_sys::ParseState, query: *mut pg_sys::Query) {
+    let Some(_guard) = CheckGuard::enter() else {
+        return;
     };
+    audit::flush_pending_violations();
+    notify::flush_pending_notifications();
+    if !skip_nested() {
+        let source_text = if pstate.is_null() {
+            std::ptr::null()
+        } else {
+            unsafe { (*pstate).p_sourcetext }
+        };
+        if guc::enforcement_point() == EnforcementPoint::Analyze {
+            unsafe { check_query_strictness_from_query(source_text, query) };
+        }
+        unsafe { check_custom_rules(source_text, query) };
+    }
+    unsafe { note_explain_only(query) };
+}
 
-    let message = generate_violation_message(operation);
-    match mode {
-        StrictMode::On => pgrx::error!("{}", message),
-        StrictMode::Warn => pgrx::warning!("{}", message),
-        StrictMode::Off => {}
+/// Runs the rules of strict.custom_rules, then the checks other extensions
+/// registered with pg_strict. Loading the rules needs catalog access, so an
+/// aborted transaction skips them.
+unsafe fn check_custom_rules(source_text: *const std::ffi::c_char, query: *mut pg_sys::Query) {
+    if query.is_null() || unsafe { pg_sys::IsAbortedTransactionBlockState() } {
+        return;
+    }
+    let rules = custom_rules::current_rules();
+    if rules.is_empty() && !plugins::any() {
+        return;
+    }
+    let query_id = unsafe { (*query).queryId } as i64;
+    if exemptions::statement_is_exempt() || exemptions::query_id_is_allowed(query_id) {
+        return;
+    }
+    let statement = unsafe { source_statement_text(source_text, query) };
+    let mut found = custom_rules::check(rules, &statement);
+    found.extend(plugins::check(query, &statement));
+    for (name, mode, violation) in found {
+        record_violation(
+            query_id,
+            mode,
+            &name,
+            violation.table.as_deref(),
+            &statement,
+        );
+        violation.with_query_id(query_id).raise(mode);
     }
 }
 
@@ -85,7 +791,7 @@ unsafe extern "C-unwind" fn pg_strict_post_parse_analyze_hook(
         unsafe { prev_hook(pstate, query) };
     }
 
-    unsafe { check_query_strictness_from_query(query) };
+    unsafe { run_checks(pstate, query) };
 }
 
 #[cfg(not(feature = "pg13"))]
@@ -99,18 +805,561 @@ unsafe extern "C-unwind" fn pg_strict_post_parse_analyze_hook(
         unsafe { prev_hook(pstate, query, jstate) };
     }
 
-    unsafe { check_query_strictness_from_query(query) };
+    unsafe { run_checks(pstate, query) };
+}
+
+// Trusted functions are routed through fmgr_hook so we can see when their
+// execution starts and ends, including on error.
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_needs_fmgr_hook(fn_oid: pg_sys::Oid) -> bool {
+    if let Some(prev_hook) = unsafe { PREV_NEEDS_FMGR_HOOK } {
+        if unsafe { prev_hook(fn_oid) } {
+            return true;
+        }
+    }
+
+    exemptions::is_trusted_function(fn_oid)
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_fmgr_hook(
+    event: pg_sys::FmgrHookEventType::Type,
+    flinfo: *mut pg_sys::FmgrInfo,
+    private: *mut pg_sys::Datum,
+) {
+    if let Some(prev_hook) = unsafe { PREV_FMGR_HOOK } {
+        unsafe { prev_hook(event, flinfo, private) };
+    }
+
+    if flinfo.is_null() {
+        return;
+    }
+
+    match event {
+        pg_sys::FmgrHookEventType::FHET_START => {
+            if exemptions::is_trusted_function(unsafe { (*flinfo).fn_oid }) {
+                exemptions::enter_trusted_call(flinfo);
+            }
+        }
+        pg_sys::FmgrHookEventType::FHET_END | pg_sys::FmgrHookEventType::FHET_ABORT => {
+            exemptions::exit_trusted_call(flinfo);
+        }
+        _ => {}
+    }
+}
+
+pub fn executor_depth() -> u32 {
+    unsafe { EXECUTOR_DEPTH }
+}
+
+/// True when the current statement runs inside another one and
+/// `pg_strict.check_nested` leaves such statements unchecked.
+fn skip_nested() -> bool {
+    match guc::check_nested() {
+        NestedStatements::All => false,
+        NestedStatements::TopLevel => executor_depth() > 0,
+        NestedStatements::None => executor_depth() > 0 || unsafe { PROCEDURE_DEPTH } > 0,
+    }
+}
+
+// Number of DO blocks and CALLs executing. Their statements run at executor
+// nesting level 0 but were not sent by the client.
+static mut PROCEDURE_DEPTH: u32 = 0;
+
+struct ProcedureDepthGuard(bool);
+
+impl ProcedureDepthGuard {
+    unsafe fn enter(pstmt: *mut pg_sys::PlannedStmt) -> Self {
+        let utility = if pstmt.is_null() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { (*pstmt).utilityStmt }
+        };
+        let procedural = !utility.is_null()
+            && matches!(
+                unsafe { (*utility).type_ },
+                pg_sys::NodeTag::T_DoStmt | pg_sys::NodeTag::T_CallStmt
+            );
+        if procedural {
+            unsafe { PROCEDURE_DEPTH += 1 };
+        }
+        ProcedureDepthGuard(procedural)
+    }
+}
+
+impl Drop for ProcedureDepthGuard {
+    fn drop(&mut self) {
+        if self.0 {
+            unsafe { PROCEDURE_DEPTH -= 1 };
+        }
+    }
+}
+
+// Marks a SET LOCAL statement while it runs, so that the pg_strict.override
+// check hook can tell it from SET and set_config().
+struct SetLocalGuard(bool);
+
+impl SetLocalGuard {
+    unsafe fn enter(pstmt: *mut pg_sys::PlannedStmt) -> Self {
+        let utility = if pstmt.is_null() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { (*pstmt).utilityStmt }
+        };
+        let set_local = !utility.is_null()
+            && unsafe { (*utility).type_ } == pg_sys::NodeTag::T_VariableSetStmt
+            && unsafe { (*(utility as *mut pg_sys::VariableSetStmt)).is_local };
+        SetLocalGuard(guc::mark_set_local(set_local))
+    }
+}
+
+impl Drop for SetLocalGuard {
+    fn drop(&mut self) {
+        guc::mark_set_local(self.0);
+    }
+}
+
+struct ExecutorDepthGuard;
+
+impl ExecutorDepthGuard {
+    fn enter() -> Self {
+        unsafe { EXECUTOR_DEPTH += 1 };
+        ExecutorDepthGuard
+    }
+}
+
+impl Drop for ExecutorDepthGuard {
+    fn drop(&mut self) {
+        unsafe { EXECUTOR_DEPTH -= 1 };
+    }
+}
+
+#[cfg(not(feature = "pg18"))]
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_executor_start_hook(
+    query_desc: *mut pg_sys::QueryDesc,
+    eflags: i32,
+) {
+    match unsafe { PREV_EXECUTOR_START_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(query_desc, eflags) },
+        None => unsafe { pg_sys::standard_ExecutorStart(query_desc, eflags) },
+    }
+    if !skip_nested() && !explain_analyze_exempt() {
+        unsafe { rowlimit::install(query_desc, eflags) };
+    }
+}
+
+#[cfg(feature = "pg18")]
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_executor_start_hook(
+    query_desc: *mut pg_sys::QueryDesc,
+    eflags: i32,
+) -> bool {
+    let plan_valid = match unsafe { PREV_EXECUTOR_START_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(query_desc, eflags) },
+        None => unsafe { pg_sys::standard_ExecutorStart(query_desc, eflags) },
+    };
+    if plan_valid && !skip_nested() && !explain_analyze_exempt() {
+        unsafe { rowlimit::install(query_desc, eflags) };
+    }
+    plan_valid
+}
+
+#[cfg(not(feature = "pg18"))]
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_executor_run_hook(
+    query_desc: *mut pg_sys::QueryDesc,
+    direction: pg_sys::ScanDirection::Type,
+    count: u64,
+    execute_once: bool,
+) {
+    let _depth = ExecutorDepthGuard::enter();
+    note_run(query_desc);
+    match unsafe { PREV_EXECUTOR_RUN_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(query_desc, direction, count, execute_once) },
+        None => unsafe { pg_sys::standard_ExecutorRun(query_desc, direction, count, execute_once) },
+    }
+}
+
+#[cfg(feature = "pg18")]
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_executor_run_hook(
+    query_desc: *mut pg_sys::QueryDesc,
+    direction: pg_sys::ScanDirection::Type,
+    count: u64,
+) {
+    let _depth = ExecutorDepthGuard::enter();
+    note_run(query_desc);
+    match unsafe { PREV_EXECUTOR_RUN_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(query_desc, direction, count) },
+        None => unsafe { pg_sys::standard_ExecutorRun(query_desc, direction, count) },
+    }
+}
+
+#[allow(static_mut_refs)]
+fn note_run(query_desc: *mut pg_sys::QueryDesc) {
+    unsafe {
+        if !RUN_QUERY_DESCS.contains(&(query_desc as usize)) {
+            RUN_QUERY_DESCS.push(query_desc as usize);
+        }
+    }
+}
+
+#[allow(static_mut_refs)]
+fn take_run(query_desc: *mut pg_sys::QueryDesc) -> bool {
+    unsafe {
+        let before = RUN_QUERY_DESCS.len();
+        RUN_QUERY_DESCS.retain(|desc| *desc != query_desc as usize);
+        RUN_QUERY_DESCS.len() != before
+    }
+}
+
+/// Puts pg_strict's ExecutorRun hook back at the head of the chain. Only
+/// called once the current hook is known not to call it, so wrapping that
+/// hook cannot run pg_strict's twice.
+unsafe fn reinstall_executor_run_hook() {
+    let current = unsafe { pg_sys::ExecutorRun_hook };
+    if current.map(|hook| hook as usize) == Some(pg_strict_executor_run_hook as usize) {
+        return;
+    }
+    unsafe {
+        PREV_EXECUTOR_RUN_HOOK = current;
+        pg_sys::ExecutorRun_hook = Some(pg_strict_executor_run_hook);
+        HOOK_REINSTALLS += 1;
+    }
+    pgrx::warning!(
+        "pg_strict: ExecutorRun_hook was replaced by another extension without calling pg_strict's hook; reinstalled it"
+    );
+}
+
+/// Whether each of pg_strict's hooks is still the first one PostgreSQL calls.
+/// A hook that is not first still runs if the extension in front of it chains
+/// to it.
+pub fn hook_chain() -> Vec<(&'static str, bool)> {
+    unsafe {
+        vec![
+            (
+                "post_parse_analyze_hook",
+                pg_sys::post_parse_analyze_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_post_parse_analyze_hook as usize),
+            ),
+            (
+                "planner_hook",
+                pg_sys::planner_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_planner_hook as usize),
+            ),
+            (
+                "ExecutorStart_hook",
+                pg_sys::ExecutorStart_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_executor_start_hook as usize),
+            ),
+            (
+                "ExecutorRun_hook",
+                pg_sys::ExecutorRun_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_executor_run_hook as usize),
+            ),
+            (
+                "ExecutorFinish_hook",
+                pg_sys::ExecutorFinish_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_executor_finish_hook as usize),
+            ),
+            (
+                "ExecutorEnd_hook",
+                pg_sys::ExecutorEnd_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_executor_end_hook as usize),
+            ),
+            (
+                "ProcessUtility_hook",
+                pg_sys::ProcessUtility_hook.map(|hook| hook as usize)
+                    == Some(pg_strict_process_utility_hook as usize),
+            ),
+        ]
+    }
+}
+
+pub fn hooks_installed() -> bool {
+    unsafe { HOOKS_INSTALLED }
+}
+
+pub fn hook_reinstalls() -> u64 {
+    unsafe { HOOK_REINSTALLS }
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_executor_finish_hook(query_desc: *mut pg_sys::QueryDesc) {
+    let _depth = ExecutorDepthGuard::enter();
+    match unsafe { PREV_EXECUTOR_FINISH_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(query_desc) },
+        None => unsafe { pg_sys::standard_ExecutorFinish(query_desc) },
+    }
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_executor_end_hook(query_desc: *mut pg_sys::QueryDesc) {
+    let operation = unsafe { (*query_desc).operation };
+    let estate = unsafe { (*query_desc).estate };
+    let rows = if estate.is_null() {
+        0
+    } else {
+        unsafe { (*estate).es_processed }
+    };
+
+    // Rows were produced without passing through pg_strict's ExecutorRun hook:
+    // an extension loaded later replaced it without chaining to it.
+    if !take_run(query_desc) && rows > 0 {
+        unsafe { reinstall_executor_run_hook() };
+    }
+
+    rowlimit::release(query_desc);
+    match unsafe { PREV_EXECUTOR_END_HOOK } {
+        Some(prev_hook) => unsafe { prev_hook(query_desc) },
+        None => unsafe { pg_sys::standard_ExecutorEnd(query_desc) },
+    }
+
+    let command = match operation {
+        pg_sys::CmdType::CMD_UPDATE => "UPDATE",
+        pg_sys::CmdType::CMD_DELETE => "DELETE",
+        _ => return,
+    };
+    audit::record_rows_affected(executor_depth(), rows);
+    summary::record_rows_affected(executor_depth(), rows);
+
+    if guc::report_rows_affected_enabled() && rows > guc::rows_affected_threshold() as u64 {
+        pgrx::notice!(
+            "pg_strict: {} modified {} rows.",
+            command,
+            report::group_digits(rows)
+        );
+    }
+}
+
+/// Applies the utility-statement rules to `pstmt` before it runs.
+unsafe fn check_utility(pstmt: *mut pg_sys::PlannedStmt, query_string: *const std::ffi::c_char) {
+    if pstmt.is_null() || skip_nested() {
+        return;
+    }
+    let Some(_guard) = CheckGuard::enter() else {
+        return;
+    };
+    let Some(found) = (unsafe { utility::check(pstmt) }) else {
+        return;
+    };
+    let query_id = unsafe { (*pstmt).queryId } as i64;
+    if exemptions::statement_is_exempt() || exemptions::query_id_is_allowed(query_id) {
+        return;
+    }
+
+    let statement =
+        unsafe { statement_text_at(query_string, (*pstmt).stmt_location, (*pstmt).stmt_len) };
+    record_violation(
+        query_id,
+        found.mode,
+        found.command,
+        found.finding.table.as_deref(),
+        &statement,
+    );
+    found
+        .finding
+        .violation
+        .with_query_id(query_id)
+        .raise(found.mode);
+}
+
+/// Checks the statement behind EXECUTE again. Its verdict at PREPARE reflects
+/// the modes of that moment, and a statement prepared while a rule was off or
+/// in warn mode must not slip through once it is on.
+unsafe fn check_execute(pstmt: *mut pg_sys::PlannedStmt) {
+    if pstmt.is_null() || guc::enforcement_point() != EnforcementPoint::Analyze || skip_nested() {
+        return;
+    }
+    let utility = unsafe { (*pstmt).utilityStmt };
+    if utility.is_null() || unsafe { (*utility).type_ } != pg_sys::NodeTag::T_ExecuteStmt {
+        return;
+    }
+    let name = unsafe { (*(utility as *mut pg_sys::ExecuteStmt)).name };
+    let prepared = unsafe { pg_sys::FetchPreparedStatement(name, false) };
+    if prepared.is_null() {
+        return;
+    }
+    // An invalidated statement is analyzed again before it runs, and checked then.
+    let plansource = unsafe { (*prepared).plansource };
+    if plansource.is_null() || unsafe { !(*plansource).is_valid } {
+        return;
+    }
+    let Some(_guard) = CheckGuard::enter() else {
+        return;
+    };
+    let queries = unsafe { PgList::<pg_sys::Query>::from_pg((*plansource).query_list) };
+    for query in queries.iter_ptr() {
+        unsafe { check_query_strictness_from_query((*plansource).query_string, query) };
+    }
+}
+
+#[cfg(feature = "pg13")]
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_process_utility_hook(
+    pstmt: *mut pg_sys::PlannedStmt,
+    query_string: *const std::ffi::c_char,
+    context: pg_sys::ProcessUtilityContext::Type,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+    dest: *mut pg_sys::DestReceiver,
+    qc: *mut pg_sys::QueryCompletion,
+) {
+    unsafe { check_utility(pstmt, query_string) };
+    unsafe { check_execute(pstmt) };
+    let _explain = unsafe { ExplainAnalyzeGuard::enter(pstmt) };
+    let _procedure = unsafe { ProcedureDepthGuard::enter(pstmt) };
+    let _set_local = unsafe { SetLocalGuard::enter(pstmt) };
+    match unsafe { PREV_PROCESS_UTILITY_HOOK } {
+        Some(prev_hook) => unsafe {
+            prev_hook(pstmt, query_string, context, params, query_env, dest, qc)
+        },
+        None => unsafe {
+            pg_sys::standard_ProcessUtility(
+                pstmt,
+                query_string,
+                context,
+                params,
+                query_env,
+                dest,
+                qc,
+            )
+        },
+    }
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_guard]
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C-unwind" fn pg_strict_process_utility_hook(
+    pstmt: *mut pg_sys::PlannedStmt,
+    query_string: *const std::ffi::c_char,
+    read_only_tree: bool,
+    context: pg_sys::ProcessUtilityContext::Type,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+    dest: *mut pg_sys::DestReceiver,
+    qc: *mut pg_sys::QueryCompletion,
+) {
+    unsafe { check_utility(pstmt, query_string) };
+    unsafe { check_execute(pstmt) };
+    let _explain = unsafe { ExplainAnalyzeGuard::enter(pstmt) };
+    let _procedure = unsafe { ProcedureDepthGuard::enter(pstmt) };
+    let _set_local = unsafe { SetLocalGuard::enter(pstmt) };
+    match unsafe { PREV_PROCESS_UTILITY_HOOK } {
+        Some(prev_hook) => unsafe {
+            prev_hook(
+                pstmt,
+                query_string,
+                read_only_tree,
+                context,
+                params,
+                query_env,
+                dest,
+                qc,
+            )
+        },
+        None => unsafe {
+            pg_sys::standard_ProcessUtility(
+                pstmt,
+                query_string,
+                read_only_tree,
+                context,
+                params,
+                query_env,
+                dest,
+                qc,
+            )
+        },
+    }
+}
+
+/// Runs for every message before it goes to the client and the server log.
+#[pg_guard]
+unsafe extern "C-unwind" fn pg_strict_emit_log_hook(edata: *mut pg_sys::ErrorData) {
+    unsafe { sqlstate::rewrite(edata) };
+    if let Some(prev_hook) = unsafe { PREV_EMIT_LOG_HOOK } {
+        unsafe { prev_hook(edata) };
+    }
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn pg_strict_xact_callback(
+    event: pg_sys::XactEvent::Type,
+    _arg: *mut std::ffi::c_void,
+) {
+    if event == pg_sys::XactEvent::XACT_EVENT_PRE_COMMIT {
+        summary::report_at_commit();
+    }
+    if matches!(
+        event,
+        pg_sys::XactEvent::XACT_EVENT_COMMIT
+            | pg_sys::XactEvent::XACT_EVENT_ABORT
+            | pg_sys::XactEvent::XACT_EVENT_PARALLEL_COMMIT
+            | pg_sys::XactEvent::XACT_EVENT_PARALLEL_ABORT
+    ) {
+        audit::reset_pending();
+        rowlimit::release(std::ptr::null_mut());
+        summary::reset();
+        plpgsql::reset();
+        sqlstate::reset();
+        unsafe { RUN_QUERY_DESCS.clear() };
+    }
+    if event == pg_sys::XactEvent::XACT_EVENT_COMMIT {
+        rules::at_commit();
+    } else if event == pg_sys::XactEvent::XACT_EVENT_ABORT {
+        rules::at_abort();
+    }
 }
 
 pub fn install_hooks() {
     unsafe {
+        HOOKS_INSTALLED = true;
         PREV_POST_PARSE_ANALYZE_HOOK = pg_sys::post_parse_analyze_hook;
         pg_sys::post_parse_analyze_hook = Some(pg_strict_post_parse_analyze_hook);
+
+        PREV_NEEDS_FMGR_HOOK = pg_sys::needs_fmgr_hook;
+        pg_sys::needs_fmgr_hook = Some(pg_strict_needs_fmgr_hook);
+        PREV_FMGR_HOOK = pg_sys::fmgr_hook;
+        pg_sys::fmgr_hook = Some(pg_strict_fmgr_hook);
+
+        PREV_PLANNER_HOOK = pg_sys::planner_hook;
+        pg_sys::planner_hook = Some(pg_strict_planner_hook);
+        PREV_EXECUTOR_START_HOOK = pg_sys::ExecutorStart_hook;
+        pg_sys::ExecutorStart_hook = Some(pg_strict_executor_start_hook);
+        PREV_EXECUTOR_RUN_HOOK = pg_sys::ExecutorRun_hook;
+        pg_sys::ExecutorRun_hook = Some(pg_strict_executor_run_hook);
+        PREV_EXECUTOR_FINISH_HOOK = pg_sys::ExecutorFinish_hook;
+        pg_sys::ExecutorFinish_hook = Some(pg_strict_executor_finish_hook);
+        PREV_EXECUTOR_END_HOOK = pg_sys::ExecutorEnd_hook;
+        pg_sys::ExecutorEnd_hook = Some(pg_strict_executor_end_hook);
+        PREV_PROCESS_UTILITY_HOOK = pg_sys::ProcessUtility_hook;
+        pg_sys::ProcessUtility_hook = Some(pg_strict_process_utility_hook);
+        PREV_EMIT_LOG_HOOK = pg_sys::emit_log_hook;
+        pg_sys::emit_log_hook = Some(pg_strict_emit_log_hook);
+
+        pg_sys::RegisterXactCallback(Some(pg_strict_xact_callback), std::ptr::null_mut());
     }
+    plpgsql::install();
 }
 
 pub fn uninstall_hooks() {
     unsafe {
+        HOOKS_INSTALLED = false;
         pg_sys::post_parse_analyze_hook = PREV_POST_PARSE_ANALYZE_HOOK;
+        pg_sys::needs_fmgr_hook = PREV_NEEDS_FMGR_HOOK;
+        pg_sys::fmgr_hook = PREV_FMGR_HOOK;
+        pg_sys::planner_hook = PREV_PLANNER_HOOK;
+        pg_sys::ExecutorStart_hook = PREV_EXECUTOR_START_HOOK;
+        pg_sys::ExecutorRun_hook = PREV_EXECUTOR_RUN_HOOK;
+        pg_sys::ExecutorFinish_hook = PREV_EXECUTOR_FINISH_HOOK;
+        pg_sys::ExecutorEnd_hook = PREV_EXECUTOR_END_HOOK;
+        pg_sys::ProcessUtility_hook = PREV_PROCESS_UTILITY_HOOK;
+        pg_sys::emit_log_hook = PREV_EMIT_LOG_HOOK;
+
+        pg_sys::UnregisterXactCallback(Some(pg_strict_xact_callback), std::ptr::null_mut());
     }
+    plpgsql::uninstall();
 }
@@ -0,0 +1,108 @@
+use crate::exemptions::{current_application_name, current_backend_type};
+use crate::logging::loggable_statement;
+use crate::schema::extension_installed;
+use pgrx::prelude::*;
+
+/// Records a statement without WHERE clause seen in `learn` mode.
+pub fn observe(operation: &str, table: Option<&str>, statement: &str) {
+    if !extension_installed() {
+        return;
+    }
+
+    let result = Spi::run_with_args(
+        "SELECT strict.log_learned_statement($1, $2, $3, $4, $5)",
+        &[
+            current_application_name().unwrap_or_default().into(),
+            current_backend_type().unwrap_or_default().into(),
+            operation.into(),
+            table.into(),
+            loggable_statement(statement).into(),
+        ],
+    );
+    if result.is_err() {
+        pgrx::warning!("pg_strict: failed to write strict.learned_statements entry.");
+    }
+}
+
+/// Settings suggested by the statements recorded in `learn` mode:
+///
+/// - applications that ran full-table DML repeatedly on different days look like
+///   scheduled jobs and are suggested for `pg_strict.exempt_application_names`;
+/// - roles doing it repeatedly outside such jobs get a per-role `warn` default;
+/// - tables that only saw occasional, ad-hoc full-table DML are suggested for `on`;
+/// - each rule is suggested for `on` once nothing outside jobs and roles relies on it.
+pub fn recommendations() -> Vec<(String, String, String, String)> {
+    if !extension_installed() {
+        pgrx::error!("pg_strict: recommendations require CREATE EXTENSION pg_strict.");
+    }
+
+    Spi::connect(|client| {
+        let rows = client.select(RECOMMENDATIONS_SQL, None, &[])?;
+        let mut recommendations = Vec::new();
+        for row in rows {
+            recommendations.push((
+                row.get::<String>(1)?.unwrap_or_default(),
+                row.get::<String>(2)?.unwrap_or_default(),
+                row.get::<String>(3)?.unwrap_or_default(),
+                row.get::<String>(4)?.unwrap_or_default(),
+            ));
+        }
+        Ok::<_, pgrx::spi::Error>(recommendations)
+    })
+    .unwrap_or_else(|error| pgrx::error!("pg_strict: could not build recommendations: {}", error))
+}
+
+const RECOMMENDATIONS_SQL: &str = r#"
+WITH observed AS (
+    SELECT * FROM strict.learned_statements
+), jobs AS (
+    SELECT application_name,
+           count(*) AS statements,
+           count(DISTINCT observed_at::date) AS days,
+           string_agg(DISTINCT coalesce(table_name, '?'), ', ') AS tables
+    FROM observed
+    WHERE coalesce(application_name, '') <> ''
+    GROUP BY application_name
+    HAVING count(*) >= 3 AND count(DISTINCT observed_at::date) >= 2
+), outside_jobs AS (
+    SELECT * FROM observed o
+    WHERE NOT EXISTS (SELECT 1 FROM jobs j WHERE j.application_name = o.application_name)
+), roles AS (
+    SELECT username, operation, count(*) AS statements
+    FROM outside_jobs
+    GROUP BY username, operation
+    HAVING count(*) >= 3
+), adhoc AS (
+    SELECT * FROM outside_jobs o
+    WHERE NOT EXISTS (
+        SELECT 1 FROM roles r WHERE r.username = o.username AND r.operation = o.operation
+    )
+)
+SELECT 'exempt_application', application_name,
+       format('Add %s to pg_strict.exempt_application_names', application_name),
+       format('%s statements on %s days, tables: %s', statements, days, tables)
+FROM jobs
+UNION ALL
+SELECT 'role_mode', username::text,
+       format('ALTER ROLE %I SET pg_strict.require_where_on_%s = ''warn''', username, lower(operation)),
+       format('%s %s statements without WHERE clause outside scheduled jobs', statements, operation)
+FROM roles
+UNION ALL
+SELECT 'protect_table', table_name,
+       'Full-table DML on this table looks accidental; keep on mode for it',
+       format('%s ad-hoc statements by %s', count(*), string_agg(DISTINCT username::text, ', '))
+FROM adhoc
+WHERE table_name IS NOT NULL
+GROUP BY table_name
+UNION ALL
+SELECT 'mode', 'require_where_on_' || lower(op.operation),
+       CASE WHEN count(a.id) = 0
+            THEN format('SET pg_strict.require_where_on_%s = ''on''', lower(op.operation))
+            ELSE format('Keep pg_strict.require_where_on_%s in warn until ad-hoc statements are fixed', lower(op.operation))
+       END,
+       format('%s ad-hoc statements', count(a.id))
+FROM (VALUES ('UPDATE'), ('DELETE')) AS op(operation)
+LEFT JOIN adhoc a ON a.operation = op.operation
+GROUP BY op.operation
+ORDER BY 1, 2
+"#;
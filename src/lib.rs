@@ -1,18 +1,112 @@
 use pgrx::prelude::*;
+use std::ffi::CStr;
 
+mod activity;
 mod analyzer;
 mod api;
+mod approval;
+mod audit;
+mod backup;
+mod bypass;
+mod cascade;
+mod catalog;
+mod custom_rules;
+mod delivery;
+mod estimate;
+mod exemptions;
 mod guc;
 mod hooks;
+mod learn;
+mod lint;
+mod logging;
+mod messages;
+mod metrics;
+mod migration;
+mod notify;
+mod plan;
+mod plpgsql;
+mod plugins;
+mod policy;
+mod recycle;
+mod report;
+mod rowlimit;
+mod rules;
+mod schedule;
+mod schema;
+mod shmem;
+mod sqlstate;
+mod stats;
+mod subxact;
+mod summary;
+mod triggers;
+mod utility;
+mod watchdog;
+mod webhook;
 
 pub use analyzer::{Operation, QueryAnalyzer};
+pub use guc::StrictMode;
+pub use plugins::{PgStrictCheck, StrictRule, pg_strict_register_check, register_rule};
+pub use report::Violation;
 
 pgrx::pg_module_magic!(name, version);
 
+static mut INITIALIZED: bool = false;
+static mut LOADED_VIA: &str = "on demand";
+
+/// Whether `_PG_init` ran to completion in this backend.
+pub(crate) fn initialized() -> bool {
+    unsafe { INITIALIZED }
+}
+
+/// The setting that loaded the library, or "on demand" when a pg_strict
+/// function did. Only shared_preload_libraries provides shared memory and
+/// background workers; otherwise each backend enforces on its own.
+pub(crate) fn loaded_via() -> &'static str {
+    unsafe { LOADED_VIA }
+}
+
+fn listed_in(setting: &CStr) -> bool {
+    let value = unsafe { pg_sys::GetConfigOption(setting.as_ptr(), true, false) };
+    if value.is_null() {
+        return false;
+    }
+    let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
+    guc::parse_list(&value).iter().any(|library| {
+        let library = library.trim_matches('"');
+        // Windows paths may use either separator, and the library ends in
+        // .dll there (.dylib on macOS with PostgreSQL 16 and later).
+        let name = library.rsplit(['/', '\\']).next().unwrap_or(library);
+        [".so", ".dll", ".dylib"]
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix))
+            .unwrap_or(name)
+            == "pg_strict"
+    })
+}
+
 #[pg_guard]
 extern "C-unwind" fn _PG_init() {
+    unsafe {
+        LOADED_VIA = if pg_sys::process_shared_preload_libraries_in_progress {
+            "shared_preload_libraries"
+        } else if listed_in(c"session_preload_libraries") {
+            "session_preload_libraries"
+        } else if listed_in(c"local_preload_libraries") {
+            "local_preload_libraries"
+        } else {
+            "on demand"
+        };
+    }
     guc::init_gucs();
+    shmem::init();
+    webhook::register_worker();
+    metrics::register_worker();
+    watchdog::register_worker();
+    recycle::register_worker();
     hooks::install_hooks();
+    rules::register_invalidation_callback();
+    plugins::publish();
+    unsafe { INITIALIZED = true };
 }
 
 #[pg_guard]
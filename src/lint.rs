@@ -0,0 +1,756 @@
+use crate::analyzer::{self, Operation, QueryAnalyzer};
+use crate::catalog;
+use crate::estimate;
+use crate::exemptions;
+use crate::guc::{self, StrictMode};
+use crate::hooks;
+use crate::policy::{
+    self, DELETE_MODE_GUC, LINT_FUNCTIONS_GUC, PROTECT_CATALOGS_GUC, UPDATE_MODE_GUC,
+};
+use crate::report::Violation;
+use crate::rules;
+use crate::subxact;
+use crate::utility::{self, range_var_name, relation_oid};
+use pgrx::PgList;
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use serde_json::{Value, json};
+use std::ffi::{CStr, CString};
+
+/// A problem `pg_strict_lint()` found in one statement of a script.
+pub struct Finding {
+    /// 1-based position of the statement in the script.
+    pub statement: i32,
+    pub operation: &'static str,
+    pub table: Option<String>,
+    /// The setting whose rule the statement breaks.
+    pub rule: &'static str,
+    pub mode: StrictMode,
+    /// The message the statement would be reported with.
+    pub message: String,
+    pub fix: String,
+}
+
+/// Checks each statement of `script` against the rules pg_strict enforces,
+/// in the modes that apply to the current session, without running it.
+/// Statements inside functions and DO blocks are not looked into.
+pub fn lint(script: &str) -> Vec<Finding> {
+    let script = c_script(script);
+    let statements = parse(&script);
+
+    let mut findings = Vec::new();
+    for (index, raw) in statements.iter_ptr().enumerate() {
+        let node = unsafe { (*raw).stmt };
+        if !node.is_null() {
+            unsafe { lint_statement(index as i32 + 1, node, &mut findings) };
+        }
+    }
+    findings
+}
+
+/// The parts of an INSERT, UPDATE or DELETE parse tree the checks look at.
+struct Dml {
+    command: pg_sys::CmdType::Type,
+    relation: *mut pg_sys::RangeVar,
+    where_clause: *mut pg_sys::Node,
+    /// Whether an UPDATE has a FROM list or a DELETE a USING list.
+    joined: bool,
+}
+
+unsafe fn dml(node: *mut pg_sys::Node) -> Option<Dml> {
+    match unsafe { (*node).type_ } {
+        pg_sys::NodeTag::T_InsertStmt => {
+            let insert = node as *mut pg_sys::InsertStmt;
+            Some(Dml {
+                command: pg_sys::CmdType::CMD_INSERT,
+                relation: unsafe { (*insert).relation },
+                where_clause: std::ptr::null_mut(),
+                joined: false,
+            })
+        }
+        pg_sys::NodeTag::T_UpdateStmt => {
+            let update = node as *mut pg_sys::UpdateStmt;
+            Some(Dml {
+                command: pg_sys::CmdType::CMD_UPDATE,
+                relation: unsafe { (*update).relation },
+                where_clause: unsafe { (*update).whereClause },
+                joined: unsafe { !(*update).fromClause.is_null() },
+            })
+        }
+        pg_sys::NodeTag::T_DeleteStmt => {
+            let delete = node as *mut pg_sys::DeleteStmt;
+            Some(Dml {
+                command: pg_sys::CmdType::CMD_DELETE,
+                relation: unsafe { (*delete).relation },
+                where_clause: unsafe { (*delete).whereClause },
+                joined: unsafe { !(*delete).usingClause.is_null() },
+            })
+        }
+        _ => None,
+    }
+}
+
+unsafe fn lint_statement(statement: i32, node: *mut pg_sys::Node, findings: &mut Vec<Finding>) {
+    let Some(Dml {
+        command,
+        relation,
+        where_clause,
+        ..
+    }) = (unsafe { dml(node) })
+    else {
+        if let Some(found) = unsafe { utility::check_node(node) } {
+            findings.push(Finding {
+                statement,
+                operation: found.command,
+                table: found.finding.table,
+                rule: found.setting,
+                mode: found.mode,
+                message: found.finding.violation.message,
+                fix: found.finding.violation.hint,
+            });
+        }
+        return;
+    };
+    if relation.is_null() {
+        return;
+    }
+
+    let relid = unsafe { relation_oid(relation) };
+    if relid.is_some_and(|relid| catalog::replaced_by_harmless_rule(relid, command)) {
+        return;
+    }
+    let table = match relid {
+        Some(relid) => catalog::qualified_relation_name(relid),
+        None => unsafe { range_var_name(relation) },
+    };
+    let operation = match command {
+        pg_sys::CmdType::CMD_UPDATE => Some(Operation::Update),
+        pg_sys::CmdType::CMD_DELETE => Some(Operation::Delete),
+        _ => None,
+    };
+    let command = operation.map_or("INSERT", Operation::as_str);
+
+    if relid.is_some_and(catalog::is_system_catalog) {
+        let mode = policy::effective_protect_catalogs_mode();
+        if reported(mode) {
+            let violation = Violation::catalog_modification(command, &table);
+            findings.push(Finding {
+                statement,
+                operation: command,
+                table: Some(table.clone()),
+                rule: PROTECT_CATALOGS_GUC,
+                mode,
+                message: violation.message,
+                fix: violation.hint,
+            });
+        }
+    }
+
+    let Some(operation) = operation.filter(|_| where_clause.is_null()) else {
+        return;
+    };
+    let mode = policy::effective_mode(operation, relid);
+    if !reported(mode) {
+        return;
+    }
+    let mut violation = Violation::missing_where(operation, Some(&table))
+        .with_rows_estimated(relid.and_then(estimate::reltuples));
+    if let Some(relid) = relid {
+        violation = violation.with_table_hint(operation, relid);
+    }
+    findings.push(Finding {
+        statement,
+        operation: command,
+        table: Some(table),
+        rule: match operation {
+            Operation::Update => UPDATE_MODE_GUC,
+            Operation::Delete => DELETE_MODE_GUC,
+        },
+        mode,
+        message: violation.message,
+        fix: violation.hint,
+    });
+}
+
+/// Whether `query` would run under the current configuration without being
+/// blocked: no rule `lint()` checks is broken in `on` mode, and a single
+/// UPDATE or DELETE stays within its `max_affected_percent`. Never raises; a
+/// query that cannot be parsed or checked is not safe.
+pub fn is_safe(query: &str) -> bool {
+    if QueryAnalyzer::new(query).is_err() {
+        return false;
+    }
+    if exemptions::session_is_exempt() {
+        return true;
+    }
+    subxact::try_in_subtransaction(|| {
+        Some(verdict(&lint(query)) != "blocked" && within_affected_percent(query))
+    })
+    .unwrap_or(false)
+}
+
+/// The planner's estimate for a single UPDATE or DELETE with a WHERE clause,
+/// checked against `max_affected_percent` as the planner hook would.
+fn within_affected_percent(query: &str) -> bool {
+    let Ok(analyzer) = QueryAnalyzer::new(query) else {
+        return false;
+    };
+    let Some(operation) = analyzer
+        .single_operation()
+        .filter(|operation| analyzer.has_where_clause(*operation))
+    else {
+        return true;
+    };
+    let Ok(estimate) = hooks::without_checks(|| estimate::explain_dml(query)) else {
+        return true;
+    };
+    let relid = Spi::get_one_with_args::<pg_sys::Oid>(
+        "SELECT pg_catalog.to_regclass($1)::oid",
+        &[estimate.target_table.as_str().into()],
+    )
+    .ok()
+    .flatten();
+    let (Some(relid), Some(table_rows)) = (relid, estimate.table_rows) else {
+        return true;
+    };
+
+    let threshold = rules::max_affected_percent(operation, Some(relid));
+    threshold <= 0.0
+        || policy::effective_mode(operation, Some(relid)) != StrictMode::On
+        || estimate::affected_percent(estimate.estimated_rows, table_rows) <= threshold
+}
+
+/// What `pg_strict_explain()` reports about one statement.
+pub struct Explanation {
+    pub statement: i32,
+    pub operation: String,
+    pub schema: Option<String>,
+    pub table: Option<String>,
+    /// Unset for statements other than UPDATE and DELETE, like the two below.
+    pub has_where: Option<bool>,
+    pub where_text: Option<String>,
+    pub joined: Option<bool>,
+    pub verdict: &'static str,
+}
+
+/// Describes each statement of `query` as the checks see it, with the verdict
+/// of `lint()` under the current configuration, without running anything.
+pub fn explain(query: &str) -> Vec<Explanation> {
+    let query = c_script(query);
+    let statements = parse(&query);
+
+    let mut explanations = Vec::new();
+    for (index, raw) in statements.iter_ptr().enumerate() {
+        let node = unsafe { (*raw).stmt };
+        if node.is_null() {
+            continue;
+        }
+        let statement = index as i32 + 1;
+        let mut findings = Vec::new();
+        unsafe { lint_statement(statement, node, &mut findings) };
+        let operation = unsafe { CStr::from_ptr(pg_sys::CreateCommandName(node)) }
+            .to_string_lossy()
+            .into_owned();
+
+        let parts = unsafe { dml(node) };
+        let (schema, table) = match parts.as_ref() {
+            Some(dml) if !dml.relation.is_null() => unsafe { target_names(dml.relation) },
+            _ => (None, None),
+        };
+        let modifies = parts
+            .as_ref()
+            .filter(|dml| dml.command != pg_sys::CmdType::CMD_INSERT);
+        let has_where = modifies.map(|dml| !dml.where_clause.is_null());
+        let where_text = if has_where == Some(true) {
+            let text = unsafe {
+                hooks::statement_text_at(query.as_ptr(), (*raw).stmt_location, (*raw).stmt_len)
+            };
+            analyzer::where_condition(&text)
+        } else {
+            None
+        };
+
+        explanations.push(Explanation {
+            statement,
+            operation,
+            schema,
+            table,
+            has_where,
+            where_text,
+            joined: modifies.map(|dml| dml.joined),
+            verdict: verdict(&findings),
+        });
+    }
+    explanations
+}
+
+/// The full analysis of each statement of `query` as one document: what
+/// `pg_strict_explain()` reports, plus every table the statement reads or
+/// writes, its WITH queries and the findings of `lint()`. Nothing is run.
+pub fn analyze(query: &str) -> Value {
+    let query = c_script(query);
+    let statements = parse(&query);
+
+    let mut analyzed = Vec::new();
+    let mut all_findings = Vec::new();
+    for (index, raw) in statements.iter_ptr().enumerate() {
+        let node = unsafe { (*raw).stmt };
+        if node.is_null() {
+            continue;
+        }
+        let statement = index as i32 + 1;
+        let mut findings = Vec::new();
+        unsafe { lint_statement(statement, node, &mut findings) };
+        let text = unsafe {
+            hooks::statement_text_at(query.as_ptr(), (*raw).stmt_location, (*raw).stmt_len)
+        };
+
+        let mut entry = unsafe { node_analysis(node) };
+        if entry["predicates"]["has_where"] == Value::Bool(true) {
+            entry["predicates"]["where"] = json!(analyzer::where_condition(&text));
+        }
+        entry["index"] = json!(statement);
+        entry["text"] = json!(analyzer::normalize_statement(&text));
+        entry["findings"] = findings
+            .iter()
+            .map(|finding| {
+                json!({
+                    "rule": finding.rule,
+                    "mode": guc::mode_to_str(finding.mode),
+                    "severity": severity(finding.mode),
+                    "table": finding.table,
+                    "message": finding.message,
+                    "fix": finding.fix,
+                })
+            })
+            .collect();
+        entry["verdict"] = json!(verdict(&findings));
+        analyzed.push(entry);
+        all_findings.extend(findings);
+    }
+    json!({
+        "statements": analyzed,
+        "verdict": verdict(&all_findings),
+    })
+}
+
+/// The parts of one statement's analysis that come from its parse tree
+/// alone, also used for the statements of its WITH queries.
+unsafe fn node_analysis(node: *mut pg_sys::Node) -> Value {
+    let command = unsafe { CStr::from_ptr(pg_sys::CreateCommandName(node)) }.to_string_lossy();
+    let parts = unsafe { dml(node) };
+    let relid = parts
+        .as_ref()
+        .filter(|dml| !dml.relation.is_null())
+        .and_then(|dml| unsafe { relation_oid(dml.relation) });
+
+    let mut tables = Vec::new();
+    let mut ctes = Vec::new();
+    if let Some(dml) = parts.as_ref().filter(|dml| !dml.relation.is_null()) {
+        tables.push(unsafe { table_analysis(dml.relation, "target") });
+    }
+    let (from, with) = unsafe { from_and_with(node) };
+    for item in unsafe { PgList::<pg_sys::Node>::from_pg(from) }.iter_ptr() {
+        unsafe { collect_tables(item, &mut tables) };
+    }
+    if !with.is_null() {
+        for cte in unsafe { PgList::<pg_sys::CommonTableExpr>::from_pg((*with).ctes) }.iter_ptr() {
+            let name = unsafe { CStr::from_ptr((*cte).ctename) }.to_string_lossy();
+            let mut entry = unsafe { node_analysis((*cte).ctequery) };
+            entry["name"] = json!(name);
+            ctes.push(entry);
+        }
+    }
+
+    let modifies = parts
+        .as_ref()
+        .filter(|dml| dml.command != pg_sys::CmdType::CMD_INSERT);
+    json!({
+        "command": command,
+        "tables": tables,
+        "ctes": ctes,
+        "predicates": {
+            "has_where": modifies.map(|dml| !dml.where_clause.is_null()),
+            "where": null,
+            "uses_from_or_using": modifies.map(|dml| dml.joined),
+        },
+        "flags": {
+            "modifies_data": parts.is_some(),
+            "system_catalog": relid.is_some_and(catalog::is_system_catalog),
+            "replaced_by_rule": parts.as_ref().zip(relid).is_some_and(|(dml, relid)| {
+                catalog::replaced_by_harmless_rule(relid, dml.command)
+            }),
+        },
+    })
+}
+
+/// The FROM list of a SELECT or UPDATE, or the USING list of a DELETE, and
+/// the statement's WITH clause.
+unsafe fn from_and_with(node: *mut pg_sys::Node) -> (*mut pg_sys::List, *mut pg_sys::WithClause) {
+    unsafe {
+        match (*node).type_ {
+            pg_sys::NodeTag::T_SelectStmt => {
+                let select = node as *mut pg_sys::SelectStmt;
+                ((*select).fromClause, (*select).withClause)
+            }
+            pg_sys::NodeTag::T_InsertStmt => {
+                let insert = node as *mut pg_sys::InsertStmt;
+                let from = if (*insert).selectStmt.is_null() {
+                    std::ptr::null_mut()
+                } else {
+                    from_and_with((*insert).selectStmt).0
+                };
+                (from, (*insert).withClause)
+            }
+            pg_sys::NodeTag::T_UpdateStmt => {
+                let update = node as *mut pg_sys::UpdateStmt;
+                ((*update).fromClause, (*update).withClause)
+            }
+            pg_sys::NodeTag::T_DeleteStmt => {
+                let delete = node as *mut pg_sys::DeleteStmt;
+                ((*delete).usingClause, (*delete).withClause)
+            }
+            _ => (std::ptr::null_mut(), std::ptr::null_mut()),
+        }
+    }
+}
+
+/// The tables of a FROM or USING item, through joins. Subqueries and
+/// functions are not looked into.
+unsafe fn collect_tables(item: *mut pg_sys::Node, tables: &mut Vec<Value>) {
+    if item.is_null() {
+        return;
+    }
+    match unsafe { (*item).type_ } {
+        pg_sys::NodeTag::T_RangeVar => {
+            tables.push(unsafe { table_analysis(item as *mut pg_sys::RangeVar, "source") });
+        }
+        pg_sys::NodeTag::T_JoinExpr => {
+            let join = item as *mut pg_sys::JoinExpr;
+            unsafe {
+                collect_tables((*join).larg, tables);
+                collect_tables((*join).rarg, tables);
+            }
+        }
+        _ => {}
+    }
+}
+
+unsafe fn table_analysis(relation: *mut pg_sys::RangeVar, role: &str) -> Value {
+    let (schema, table) = unsafe { target_names(relation) };
+    json!({
+        "schema": schema,
+        "table": table,
+        "exists": unsafe { relation_oid(relation) }.is_some(),
+        "role": role,
+    })
+}
+
+/// The schema and name of a statement's target, from the catalog when the
+/// relation exists and as written otherwise.
+unsafe fn target_names(relation: *mut pg_sys::RangeVar) -> (Option<String>, Option<String>) {
+    if let Some(relid) = unsafe { relation_oid(relation) } {
+        return (
+            catalog::relation_namespace(relid),
+            catalog::relation_name(relid),
+        );
+    }
+    let text = |ptr: *mut std::ffi::c_char| {
+        (!ptr.is_null()).then(|| {
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+    unsafe { (text((*relation).schemaname), text((*relation).relname)) }
+}
+
+pub fn c_script(script: &str) -> CString {
+    let Ok(script) = CString::new(script) else {
+        pgrx::error!("pg_strict: the script must not contain NUL bytes.");
+    };
+    script
+}
+
+/// The statements of a script. A syntax error is raised as it is, with its
+/// position in the script.
+pub fn parse(script: &CString) -> PgList<pg_sys::RawStmt> {
+    unsafe { PgList::<pg_sys::RawStmt>::from_pg(pg_sys::pg_parse_query(script.as_ptr())) }
+}
+
+/// Reports the UPDATE and DELETE statements without WHERE clause in the body
+/// of a SQL or PL/pgSQL function, in `pg_strict.lint_functions` mode, and
+/// returns how many there are. Other languages are not checked.
+pub fn function_body(function: pg_sys::Oid) -> i32 {
+    let Some((name, language, body)) = function_source(function) else {
+        return 0;
+    };
+    if !matches!(language.as_str(), "sql" | "plpgsql") {
+        return 0;
+    }
+    let unsafe_statements: Vec<String> = unconditional_statements(&body)
+        .into_iter()
+        .map(|(_, _, statement)| statement)
+        .collect();
+    if unsafe_statements.is_empty() {
+        return 0;
+    }
+
+    let mode = policy::resolve(LINT_FUNCTIONS_GUC, guc::lint_functions_mode());
+    if reported(mode) {
+        Violation::function_body(&name, &unsafe_statements.join("; ")).raise(mode);
+    }
+    unsafe_statements.len() as i32
+}
+
+/// A statement found by `audit_functions()`.
+pub struct RoutineStatement {
+    pub routine: String,
+    pub language: String,
+    pub line: i32,
+    pub operation: Operation,
+    pub statement: String,
+}
+
+/// Every UPDATE and DELETE without WHERE clause in the SQL and PL/pgSQL
+/// routines of the database, leaving out system schemas and routines that
+/// belong to an extension.
+pub fn audit_functions() -> Vec<RoutineStatement> {
+    let routines = Spi::connect(|client| {
+        let mut routines = Vec::new();
+        for row in client.select(
+            "SELECT p.oid::regprocedure::text, l.lanname::text, p.prosrc \
+             FROM pg_catalog.pg_proc p \
+             JOIN pg_catalog.pg_language l ON l.oid = p.prolang \
+             JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace \
+             WHERE l.lanname IN ('sql', 'plpgsql') \
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+               AND NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d \
+                               WHERE d.classid = 'pg_catalog.pg_proc'::regclass \
+                                 AND d.objid = p.oid AND d.deptype = 'e') \
+             ORDER BY 1",
+            None,
+            &[],
+        )? {
+            routines.push((
+                row.get::<String>(1)?.unwrap_or_default(),
+                row.get::<String>(2)?.unwrap_or_default(),
+                row.get::<String>(3)?.unwrap_or_default(),
+            ));
+        }
+        Ok::<_, pgrx::spi::Error>(routines)
+    })
+    .unwrap_or_else(|e| pgrx::error!("pg_strict: could not read pg_proc: {}", e));
+
+    routines
+        .into_iter()
+        .flat_map(|(routine, language, body)| {
+            unconditional_statements(&body)
+                .into_iter()
+                .map(move |(line, operation, statement)| RoutineStatement {
+                    routine: routine.clone(),
+                    language: language.clone(),
+                    line,
+                    operation,
+                    statement,
+                })
+        })
+        .collect()
+}
+
+/// A statement found by `audit_triggers()`.
+pub struct TriggerStatement {
+    pub table: String,
+    pub trigger: String,
+    pub function: String,
+    pub line: i32,
+    pub operation: Operation,
+    pub target: Option<String>,
+    pub statement: String,
+}
+
+/// The UPDATE and DELETE statements without WHERE clause that the PL/pgSQL
+/// trigger functions of user tables run against other tables. A target that
+/// does not resolve with the current search_path is listed as well, with no
+/// table.
+pub fn audit_triggers() -> Vec<TriggerStatement> {
+    let triggers = Spi::connect(|client| {
+        let mut triggers = Vec::new();
+        for row in client.select(
+            "SELECT format('%s.%s', n.nspname, c.relname), t.tgname::text, \
+                    p.oid::regprocedure::text, p.prosrc \
+             FROM pg_catalog.pg_trigger t \
+             JOIN pg_catalog.pg_class c ON c.oid = t.tgrelid \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             JOIN pg_catalog.pg_proc p ON p.oid = t.tgfoid \
+             JOIN pg_catalog.pg_language l ON l.oid = p.prolang \
+             WHERE NOT t.tgisinternal \
+               AND l.lanname = 'plpgsql' \
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+             ORDER BY 1, 2",
+            None,
+            &[],
+        )? {
+            triggers.push((
+                row.get::<String>(1)?.unwrap_or_default(),
+                row.get::<String>(2)?.unwrap_or_default(),
+                row.get::<String>(3)?.unwrap_or_default(),
+                row.get::<String>(4)?.unwrap_or_default(),
+            ));
+        }
+        Ok::<_, pgrx::spi::Error>(triggers)
+    })
+    .unwrap_or_else(|e| pgrx::error!("pg_strict: could not read pg_trigger: {}", e));
+
+    let mut found = Vec::new();
+    for (table, trigger, function, body) in triggers {
+        for (line, operation, statement) in unconditional_statements(&body) {
+            let target = statement_target(&statement);
+            if target.as_deref() == Some(table.as_str()) {
+                continue;
+            }
+            found.push(TriggerStatement {
+                table: table.clone(),
+                trigger: trigger.clone(),
+                function: function.clone(),
+                line,
+                operation,
+                target,
+                statement,
+            });
+        }
+    }
+    found
+}
+
+/// A rule action found by `audit_rules()`.
+pub struct RuleAction {
+    pub table: String,
+    pub rule: String,
+    pub event: String,
+    pub instead: bool,
+    pub operation: Operation,
+    pub target: Option<String>,
+    pub definition: String,
+}
+
+/// The UPDATE and DELETE actions without WHERE clause in the rewrite rules of
+/// user tables and views, which otherwise only show up when a statement
+/// fires the rule.
+pub fn audit_rules() -> Vec<RuleAction> {
+    let rules = Spi::connect(|client| {
+        let mut rules = Vec::new();
+        for row in client.select(
+            "SELECT r.oid, r.ev_class, format('%s.%s', n.nspname, c.relname), r.rulename::text, \
+                    CASE r.ev_type WHEN '1' THEN 'SELECT' WHEN '2' THEN 'UPDATE' \
+                                   WHEN '3' THEN 'INSERT' WHEN '4' THEN 'DELETE' END, \
+                    r.is_instead, pg_catalog.pg_get_ruledef(r.oid) \
+             FROM pg_catalog.pg_rewrite r \
+             JOIN pg_catalog.pg_class c ON c.oid = r.ev_class \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname NOT IN ('pg_catalog', 'information_schema') \
+             ORDER BY 3, 4",
+            None,
+            &[],
+        )? {
+            rules.push((
+                row.get::<pg_sys::Oid>(1)?.unwrap_or(pg_sys::InvalidOid),
+                row.get::<pg_sys::Oid>(2)?.unwrap_or(pg_sys::InvalidOid),
+                row.get::<String>(3)?.unwrap_or_default(),
+                row.get::<String>(4)?.unwrap_or_default(),
+                row.get::<String>(5)?.unwrap_or_default(),
+                row.get::<bool>(6)?.unwrap_or(false),
+                row.get::<String>(7)?.unwrap_or_default(),
+            ));
+        }
+        Ok::<_, pgrx::spi::Error>(rules)
+    })
+    .unwrap_or_else(|e| pgrx::error!("pg_strict: could not read pg_rewrite: {}", e));
+
+    let mut found = Vec::new();
+    for (rule_id, relid, table, rule, event, instead, definition) in rules {
+        for (operation, target) in catalog::unconditional_rule_actions(relid, rule_id) {
+            found.push(RuleAction {
+                table: table.clone(),
+                rule: rule.clone(),
+                event: event.clone(),
+                instead,
+                operation,
+                target: target.map(catalog::qualified_relation_name),
+                definition: definition.clone(),
+            });
+        }
+    }
+    found
+}
+
+/// The schema-qualified table an UPDATE or DELETE writes to, if it exists.
+fn statement_target(statement: &str) -> Option<String> {
+    let statement = c_script(statement);
+    let statements = parse(&statement);
+    let raw = statements.get_ptr(0)?;
+    let parts = unsafe { dml((*raw).stmt) }?;
+    if parts.relation.is_null() {
+        return None;
+    }
+    unsafe { relation_oid(parts.relation) }.map(catalog::qualified_relation_name)
+}
+
+/// The UPDATE and DELETE statements without WHERE clause in a function body,
+/// with their line, normalized. PL/pgSQL-only syntax such as INTO does not
+/// parse as SQL; such statements are passed over.
+fn unconditional_statements(body: &str) -> Vec<(i32, Operation, String)> {
+    analyzer::body_statements(body)
+        .into_iter()
+        .filter_map(|(line, statement)| {
+            let operation = QueryAnalyzer::new(&statement)
+                .ok()?
+                .missing_where_operations()
+                .first()
+                .copied()?;
+            Some((line, operation, analyzer::normalize_statement(&statement)))
+        })
+        .collect()
+}
+
+fn function_source(function: pg_sys::Oid) -> Option<(String, String, String)> {
+    let (name, language, body) = Spi::get_three_with_args::<String, String, String>(
+        "SELECT p.oid::regprocedure::text, l.lanname::text, p.prosrc \
+         FROM pg_catalog.pg_proc p \
+         JOIN pg_catalog.pg_language l ON l.oid = p.prolang \
+         WHERE p.oid = $1",
+        &[function.into()],
+    )
+    .ok()?;
+    Some((name?, language?, body?))
+}
+
+fn reported(mode: StrictMode) -> bool {
+    !matches!(mode, StrictMode::Off | StrictMode::Learn)
+}
+
+/// How a finding in `mode` would surface when the statement runs.
+pub fn severity(mode: StrictMode) -> &'static str {
+    match mode {
+        StrictMode::On => "error",
+        StrictMode::Warn => "warning",
+        StrictMode::Notice => "notice",
+        StrictMode::Log => "log",
+        StrictMode::Off | StrictMode::Learn => "none",
+    }
+}
+
+/// What would happen to a statement with these findings: `blocked` when one
+/// of them is an error, `warned` when any are reported, otherwise `allowed`.
+pub fn verdict(findings: &[Finding]) -> &'static str {
+    if findings
+        .iter()
+        .any(|finding| finding.mode == StrictMode::On)
+    {
+        "blocked"
+    } else if findings.is_empty() {
+        "allowed"
+    } else {
+        "warned"
+    }
+}
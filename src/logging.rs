@@ -0,0 +1,179 @@
+use crate::analyzer::{normalize_statement, redact_literals};
+use crate::guc::{
+    LogFormat, current_log_format, log_directory, log_filename, redact_literals_enabled,
+};
+use crate::notify::{database_name, json_string, session_user_name};
+use pgrx::pg_sys;
+use std::ffi::CStr;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+const USECS_PER_SEC: i64 = 1_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+// Seconds between the Unix epoch and PostgreSQL's 2000-01-01 epoch.
+const POSTGRES_EPOCH_UNIX_SECS: i64 = 946_684_800;
+
+/// A violation as written to the server log in `pg_strict.log_format = json`.
+pub struct ViolationLogEntry<'a> {
+    pub operation: &'a str,
+    pub table: Option<&'a str>,
+    pub blocked: bool,
+    pub query_id: i64,
+}
+
+/// Statement text as it may appear in logs, audit tables and offender stats:
+/// normalized, and with literals replaced when `pg_strict.redact_literals` is on.
+pub fn loggable_statement(statement: &str) -> String {
+    if redact_literals_enabled() {
+        normalize_statement(&redact_literals(statement))
+    } else {
+        normalize_statement(statement)
+    }
+}
+
+pub fn log_violation(entry: &ViolationLogEntry<'_>) {
+    let json = current_log_format() == LogFormat::Json;
+    if json {
+        pgrx::log!("{}", violation_json(entry));
+    }
+
+    if let Some(directory) = log_directory() {
+        let line = if json {
+            violation_json(entry)
+        } else {
+            violation_text(entry)
+        };
+        write_log_file(&directory, &line);
+    }
+}
+
+fn violation_text(entry: &ViolationLogEntry<'_>) -> String {
+    let now = unsafe { pg_sys::GetCurrentTimestamp() };
+    format!(
+        "{} {} {}@{} {} {} queryid={}",
+        iso8601_utc(now),
+        if entry.blocked { "blocked" } else { "warned" },
+        session_user_name().unwrap_or_default(),
+        database_name().unwrap_or_default(),
+        entry.operation,
+        entry.table.unwrap_or("-"),
+        entry.query_id,
+    )
+}
+
+/// Appends `line` to the current file in `pg_strict.log_directory`. Each line is
+/// a single O_APPEND write, so concurrent backends do not interleave.
+fn write_log_file(directory: &str, line: &str) {
+    let now = unsafe { pg_sys::GetCurrentTimestamp() };
+    let mut path = PathBuf::from(directory);
+    if path.is_relative() {
+        let data_dir = unsafe { CStr::from_ptr(pg_sys::DataDir) };
+        path = PathBuf::from(data_dir.to_string_lossy().into_owned()).join(path);
+    }
+
+    let result = fs::create_dir_all(&path).and_then(|_| {
+        path.push(expand_filename(&log_filename(), now));
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        file.write_all(format!("{line}\n").as_bytes())
+    });
+    if let Err(error) = result {
+        pgrx::log!(
+            "pg_strict: could not write to log file {}: {}",
+            path.display(),
+            error
+        );
+    }
+}
+
+pub fn violation_json(entry: &ViolationLogEntry<'_>) -> String {
+    let now = unsafe { pg_sys::GetCurrentTimestamp() };
+    format!(
+        "{{\"timestamp\":{},\"user\":{},\"database\":{},\"operation\":{},\"table\":{},\"action\":{},\"queryid\":{}}}",
+        json_string(&iso8601_utc(now)),
+        json_string(&session_user_name().unwrap_or_default()),
+        json_string(&database_name().unwrap_or_default()),
+        json_string(entry.operation),
+        entry
+            .table
+            .map(json_string)
+            .unwrap_or_else(|| "null".to_string()),
+        json_string(if entry.blocked { "blocked" } else { "warned" }),
+        entry.query_id,
+    )
+}
+
+struct UtcTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    micros: i64,
+}
+
+/// Formats a timestamptz as `YYYY-MM-DDTHH:MM:SS.ffffffZ`, independent of DateStyle and TimeZone.
+pub fn iso8601_utc(timestamp: pg_sys::TimestampTz) -> String {
+    let t = utc_time(timestamp);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second, t.micros
+    )
+}
+
+fn utc_time(timestamp: pg_sys::TimestampTz) -> UtcTime {
+    let unix_usecs = timestamp + POSTGRES_EPOCH_UNIX_SECS * USECS_PER_SEC;
+    let secs = unix_usecs.div_euclid(USECS_PER_SEC);
+    let micros = unix_usecs.rem_euclid(USECS_PER_SEC);
+    let days = secs.div_euclid(SECS_PER_DAY);
+    let time = secs.rem_euclid(SECS_PER_DAY);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's days_from_civil, inverted).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    UtcTime {
+        year,
+        month,
+        day,
+        hour: time / 3600,
+        minute: time % 3600 / 60,
+        second: time % 60,
+        micros,
+    }
+}
+
+/// Expands `%Y`, `%m`, `%d`, `%H`, `%M` and `%%` in `pattern` using UTC time.
+pub fn expand_filename(pattern: &str, timestamp: pg_sys::TimestampTz) -> String {
+    let t = utc_time(timestamp);
+    let mut out = String::with_capacity(pattern.len() + 8);
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", t.year)),
+            Some('m') => out.push_str(&format!("{:02}", t.month)),
+            Some('d') => out.push_str(&format!("{:02}", t.day)),
+            Some('H') => out.push_str(&format!("{:02}", t.hour)),
+            Some('M') => out.push_str(&format!("{:02}", t.minute)),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
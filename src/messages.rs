@@ -0,0 +1,486 @@
+use crate::guc::{MessageLanguage, message_language};
+
+/// Violation texts by key, in English, German, French and Japanese (the order
+/// of `MessageLanguage`). `{name}` placeholders are filled in by `text()`;
+/// every translation must use the same ones as the English text.
+pub static CATALOG: &[(&str, [&str; 4])] = &[
+    (
+        "missing_where.message",
+        [
+            "pg_strict: {operation} statement without WHERE clause detected. This operation would affect all rows in the table.",
+            "pg_strict: {operation}-Anweisung ohne WHERE-Klausel erkannt. Diese Operation würde alle Zeilen der Tabelle betreffen.",
+            "pg_strict: instruction {operation} sans clause WHERE détectée. Cette opération affecterait toutes les lignes de la table.",
+            "pg_strict: WHERE 句のない {operation} 文を検出しました。この操作はテーブルのすべての行に影響します。",
+        ],
+    ),
+    (
+        "missing_where.detail",
+        [
+            "{operation} statement has no WHERE clause.",
+            "Die {operation}-Anweisung hat keine WHERE-Klausel.",
+            "L'instruction {operation} n'a pas de clause WHERE.",
+            "{operation} 文に WHERE 句がありません。",
+        ],
+    ),
+    (
+        "missing_where.detail_table",
+        [
+            "{operation} on table {table} has no WHERE clause.",
+            "{operation} auf Tabelle {table} hat keine WHERE-Klausel.",
+            "{operation} sur la table {table} n'a pas de clause WHERE.",
+            "テーブル {table} に対する {operation} に WHERE 句がありません。",
+        ],
+    ),
+    (
+        "missing_where.hint_update",
+        [
+            "Add a WHERE clause to limit the rows updated, or WHERE true to update every row intentionally.",
+            "Fügen Sie eine WHERE-Klausel hinzu, um die geänderten Zeilen einzuschränken, oder WHERE true, um bewusst alle Zeilen zu ändern.",
+            "Ajoutez une clause WHERE pour limiter les lignes modifiées, ou WHERE true pour modifier volontairement toutes les lignes.",
+            "更新する行を絞り込む WHERE 句を追加してください。意図的にすべての行を更新する場合は WHERE true を指定してください。",
+        ],
+    ),
+    (
+        "missing_where.hint_delete",
+        [
+            "Add a WHERE clause to limit the rows deleted, or use TRUNCATE to remove every row intentionally.",
+            "Fügen Sie eine WHERE-Klausel hinzu, um die gelöschten Zeilen einzuschränken, oder verwenden Sie TRUNCATE, um bewusst alle Zeilen zu entfernen.",
+            "Ajoutez une clause WHERE pour limiter les lignes supprimées, ou utilisez TRUNCATE pour supprimer volontairement toutes les lignes.",
+            "削除する行を絞り込む WHERE 句を追加してください。意図的にすべての行を削除する場合は TRUNCATE を使用してください。",
+        ],
+    ),
+    (
+        "missing_where.hint_update_key",
+        [
+            "Add a WHERE clause on the primary key of {table}, for example WHERE {condition}, or WHERE true to update every row intentionally.",
+            "Fügen Sie eine WHERE-Klausel auf den Primärschlüssel von {table} hinzu, zum Beispiel WHERE {condition}, oder WHERE true, um bewusst alle Zeilen zu ändern.",
+            "Ajoutez une clause WHERE sur la clé primaire de {table}, par exemple WHERE {condition}, ou WHERE true pour modifier volontairement toutes les lignes.",
+            "{table} の主キーに対する WHERE 句を追加してください（例: WHERE {condition}）。意図的にすべての行を更新する場合は WHERE true を指定してください。",
+        ],
+    ),
+    (
+        "missing_where.hint_delete_truncate",
+        [
+            "Add a WHERE clause to limit the rows deleted. To empty {table} intentionally, TRUNCATE {table} is faster; to remove many rows without long locks, use CALL pg_strict_delete_in_batches('{table_literal}', '<condition>').",
+            "Fügen Sie eine WHERE-Klausel hinzu, um die gelöschten Zeilen einzuschränken. Um {table} bewusst zu leeren, ist TRUNCATE {table} schneller; um viele Zeilen ohne lange Sperren zu entfernen, verwenden Sie CALL pg_strict_delete_in_batches('{table_literal}', '<condition>').",
+            "Ajoutez une clause WHERE pour limiter les lignes supprimées. Pour vider volontairement {table}, TRUNCATE {table} est plus rapide ; pour supprimer beaucoup de lignes sans verrous prolongés, utilisez CALL pg_strict_delete_in_batches('{table_literal}', '<condition>').",
+            "削除する行を絞り込む WHERE 句を追加してください。{table} を意図的に空にする場合は TRUNCATE {table} の方が高速です。長時間のロックを避けて多数の行を削除するには CALL pg_strict_delete_in_batches('{table_literal}', '<condition>') を使用してください。",
+        ],
+    ),
+    (
+        "missing_where.hint_delete_batches",
+        [
+            "Add a WHERE clause to limit the rows deleted. Foreign keys reference {table}, so TRUNCATE would fail; to remove many rows, use CALL pg_strict_delete_in_batches('{table_literal}', '<condition>').",
+            "Fügen Sie eine WHERE-Klausel hinzu, um die gelöschten Zeilen einzuschränken. Fremdschlüssel verweisen auf {table}, daher würde TRUNCATE fehlschlagen; um viele Zeilen zu entfernen, verwenden Sie CALL pg_strict_delete_in_batches('{table_literal}', '<condition>').",
+            "Ajoutez une clause WHERE pour limiter les lignes supprimées. Des clés étrangères référencent {table}, donc TRUNCATE échouerait ; pour supprimer beaucoup de lignes, utilisez CALL pg_strict_delete_in_batches('{table_literal}', '<condition>').",
+            "削除する行を絞り込む WHERE 句を追加してください。{table} は外部キーから参照されているため TRUNCATE は失敗します。多数の行を削除するには CALL pg_strict_delete_in_batches('{table_literal}', '<condition>') を使用してください。",
+        ],
+    ),
+    (
+        "missing_where.hint_rows",
+        [
+            "The statement would touch about {rows} rows.",
+            "Die Anweisung würde etwa {rows} Zeilen betreffen.",
+            "L'instruction toucherait environ {rows} lignes.",
+            "この文は約 {rows} 行に影響します。",
+        ],
+    ),
+    (
+        "catalog_modification.message",
+        [
+            "pg_strict: {command} on system catalog {table} detected. Direct catalog modifications can corrupt the database.",
+            "pg_strict: {command} auf Systemkatalog {table} erkannt. Direkte Änderungen am Katalog können die Datenbank beschädigen.",
+            "pg_strict: {command} sur le catalogue système {table} détecté. Modifier directement le catalogue peut corrompre la base de données.",
+            "pg_strict: システムカタログ {table} に対する {command} を検出しました。カタログを直接変更するとデータベースが破損する可能性があります。",
+        ],
+    ),
+    (
+        "catalog_modification.detail",
+        [
+            "{command} targets system catalog {table}.",
+            "{command} zielt auf den Systemkatalog {table}.",
+            "{command} cible le catalogue système {table}.",
+            "{command} の対象はシステムカタログ {table} です。",
+        ],
+    ),
+    (
+        "catalog_modification.hint",
+        [
+            "Use DDL commands or the catalog's functions instead of modifying it directly.",
+            "Verwenden Sie DDL-Befehle oder die Funktionen des Katalogs, statt ihn direkt zu ändern.",
+            "Utilisez des commandes DDL ou les fonctions du catalogue plutôt que de le modifier directement.",
+            "カタログを直接変更せず、DDL コマンドまたはカタログの関数を使用してください。",
+        ],
+    ),
+    (
+        "function_body.message",
+        [
+            "pg_strict: function {function} contains UPDATE or DELETE statements without WHERE clause.",
+            "pg_strict: Funktion {function} enthält UPDATE- oder DELETE-Anweisungen ohne WHERE-Klausel.",
+            "pg_strict: la fonction {function} contient des instructions UPDATE ou DELETE sans clause WHERE.",
+            "pg_strict: 関数 {function} に WHERE 句のない UPDATE または DELETE 文が含まれています。",
+        ],
+    ),
+    (
+        "function_body.detail",
+        [
+            "Statements without WHERE clause: {statements}",
+            "Anweisungen ohne WHERE-Klausel: {statements}",
+            "Instructions sans clause WHERE : {statements}",
+            "WHERE 句のない文: {statements}",
+        ],
+    ),
+    (
+        "function_body.hint",
+        [
+            "Add a WHERE clause to each statement, or WHERE true where every row is meant. pg_strict.lint_functions sets how this check is reported.",
+            "Fügen Sie jeder Anweisung eine WHERE-Klausel hinzu, oder WHERE true, wo alle Zeilen gemeint sind. pg_strict.lint_functions legt fest, wie diese Prüfung gemeldet wird.",
+            "Ajoutez une clause WHERE à chaque instruction, ou WHERE true lorsque toutes les lignes sont visées. pg_strict.lint_functions définit comment cette vérification est signalée.",
+            "各文に WHERE 句を追加してください。すべての行が対象の場合は WHERE true を指定してください。このチェックの報告方法は pg_strict.lint_functions で設定します。",
+        ],
+    ),
+    (
+        "custom.detail",
+        [
+            "Reported by the {check} check.",
+            "Gemeldet von der Prüfung {check}.",
+            "Signalé par la vérification {check}.",
+            "{check} チェックによる報告です。",
+        ],
+    ),
+    (
+        "custom.hint",
+        [
+            "The mode of this check is set with {setting}.",
+            "Der Modus dieser Prüfung wird mit {setting} eingestellt.",
+            "Le mode de cette vérification se règle avec {setting}.",
+            "このチェックのモードは {setting} で設定します。",
+        ],
+    ),
+    (
+        "custom_rule.detail",
+        [
+            "The statement matches custom rule {id}, pattern {pattern}.",
+            "Die Anweisung entspricht der benutzerdefinierten Regel {id}, Muster {pattern}.",
+            "L'instruction correspond à la règle personnalisée {id}, motif {pattern}.",
+            "文がカスタムルール {id} (パターン {pattern}) に一致しました。",
+        ],
+    ),
+    (
+        "custom_rule.hint",
+        [
+            "Custom rules are defined in strict.custom_rules.",
+            "Benutzerdefinierte Regeln stehen in strict.custom_rules.",
+            "Les règles personnalisées sont définies dans strict.custom_rules.",
+            "カスタムルールは strict.custom_rules で定義されています。",
+        ],
+    ),
+    (
+        "truncate.message",
+        [
+            "pg_strict: TRUNCATE of {tables} detected. This operation removes all rows in the table.",
+            "pg_strict: TRUNCATE von {tables} erkannt. Diese Operation entfernt alle Zeilen der Tabelle.",
+            "pg_strict: TRUNCATE de {tables} détecté. Cette opération supprime toutes les lignes de la table.",
+            "pg_strict: {tables} の TRUNCATE を検出しました。この操作はテーブルのすべての行を削除します。",
+        ],
+    ),
+    (
+        "truncate.detail",
+        [
+            "TRUNCATE targets {tables}.",
+            "TRUNCATE zielt auf {tables}.",
+            "TRUNCATE cible {tables}.",
+            "TRUNCATE の対象は {tables} です。",
+        ],
+    ),
+    (
+        "truncate.hint",
+        [
+            "Use DELETE with a WHERE clause to remove only some rows, or relax pg_strict.guard_truncate with SET LOCAL for this transaction.",
+            "Verwenden Sie DELETE mit einer WHERE-Klausel, um nur einige Zeilen zu entfernen, oder lockern Sie pg_strict.guard_truncate mit SET LOCAL für diese Transaktion.",
+            "Utilisez DELETE avec une clause WHERE pour ne supprimer que certaines lignes, ou assouplissez pg_strict.guard_truncate avec SET LOCAL pour cette transaction.",
+            "一部の行だけを削除するには WHERE 句付きの DELETE を使用するか、このトランザクションに限り SET LOCAL で pg_strict.guard_truncate を緩和してください。",
+        ],
+    ),
+    (
+        "too_selective.message",
+        [
+            "pg_strict: {operation} on table {table} is estimated to affect {percent}% of its rows.",
+            "pg_strict: {operation} auf Tabelle {table} betrifft schätzungsweise {percent}% ihrer Zeilen.",
+            "pg_strict: {operation} sur la table {table} affecterait environ {percent} % de ses lignes.",
+            "pg_strict: テーブル {table} に対する {operation} は行の約 {percent}% に影響すると見積もられています。",
+        ],
+    ),
+    (
+        "too_selective.detail",
+        [
+            "The planner estimates {rows} of {reltuples} rows, above pg_strict.max_affected_percent ({threshold}%).",
+            "Der Planer schätzt {rows} von {reltuples} Zeilen, mehr als pg_strict.max_affected_percent ({threshold}%).",
+            "Le planificateur estime {rows} lignes sur {reltuples}, au-delà de pg_strict.max_affected_percent ({threshold} %).",
+            "プランナーの見積もりは {reltuples} 行中 {rows} 行で、pg_strict.max_affected_percent ({threshold}%) を超えています。",
+        ],
+    ),
+    (
+        "too_selective.hint_update",
+        [
+            "Narrow the WHERE clause, run it with CALL pg_strict_update_in_batches(), or raise pg_strict.max_affected_percent with SET LOCAL for this transaction.",
+            "Schränken Sie die WHERE-Klausel ein, führen Sie die Änderung mit CALL pg_strict_update_in_batches() aus, oder erhöhen Sie pg_strict.max_affected_percent mit SET LOCAL für diese Transaktion.",
+            "Restreignez la clause WHERE, exécutez la modification avec CALL pg_strict_update_in_batches(), ou augmentez pg_strict.max_affected_percent avec SET LOCAL pour cette transaction.",
+            "WHERE 句を絞り込むか、CALL pg_strict_update_in_batches() で実行するか、このトランザクションに限り SET LOCAL で pg_strict.max_affected_percent を引き上げてください。",
+        ],
+    ),
+    (
+        "too_selective.hint_delete",
+        [
+            "Narrow the WHERE clause, run it with CALL pg_strict_delete_in_batches(), or raise pg_strict.max_affected_percent with SET LOCAL for this transaction.",
+            "Schränken Sie die WHERE-Klausel ein, führen Sie die Löschung mit CALL pg_strict_delete_in_batches() aus, oder erhöhen Sie pg_strict.max_affected_percent mit SET LOCAL für diese Transaktion.",
+            "Restreignez la clause WHERE, exécutez la suppression avec CALL pg_strict_delete_in_batches(), ou augmentez pg_strict.max_affected_percent avec SET LOCAL pour cette transaction.",
+            "WHERE 句を絞り込むか、CALL pg_strict_delete_in_batches() で実行するか、このトランザクションに限り SET LOCAL で pg_strict.max_affected_percent を引き上げてください。",
+        ],
+    ),
+    (
+        "costly_statement.message",
+        [
+            "pg_strict: {command} statement has an estimated cost of {cost}, above pg_strict.max_statement_cost.",
+            "pg_strict: Die {command}-Anweisung hat geschätzte Kosten von {cost}, mehr als pg_strict.max_statement_cost.",
+            "pg_strict: l'instruction {command} a un coût estimé de {cost}, au-delà de pg_strict.max_statement_cost.",
+            "pg_strict: {command} 文の推定コストは {cost} で、pg_strict.max_statement_cost を超えています。",
+        ],
+    ),
+    (
+        "costly_statement.detail",
+        [
+            "The planner's total cost estimate exceeds the limit of {threshold}.",
+            "Die geschätzten Gesamtkosten des Planers überschreiten die Grenze von {threshold}.",
+            "Le coût total estimé par le planificateur dépasse la limite de {threshold}.",
+            "プランナーの総コスト見積もりが上限 {threshold} を超えています。",
+        ],
+    ),
+    (
+        "costly_statement.hint",
+        [
+            "Add selective conditions or supporting indexes, or raise pg_strict.max_statement_cost with SET LOCAL for this transaction.",
+            "Fügen Sie selektive Bedingungen oder passende Indizes hinzu, oder erhöhen Sie pg_strict.max_statement_cost mit SET LOCAL für diese Transaktion.",
+            "Ajoutez des conditions sélectives ou des index adaptés, ou augmentez pg_strict.max_statement_cost avec SET LOCAL pour cette transaction.",
+            "選択的な条件や適切なインデックスを追加するか、このトランザクションに限り SET LOCAL で pg_strict.max_statement_cost を引き上げてください。",
+        ],
+    ),
+    (
+        "sequential_scan.message",
+        [
+            "pg_strict: {operation} reads table {table} ({size_kb} kB) with a sequential scan.",
+            "pg_strict: {operation} liest Tabelle {table} ({size_kb} kB) mit einem sequenziellen Scan.",
+            "pg_strict: {operation} lit la table {table} ({size_kb} ko) par un parcours séquentiel.",
+            "pg_strict: {operation} はテーブル {table} ({size_kb} kB) をシーケンシャルスキャンで読み取ります。",
+        ],
+    ),
+    (
+        "sequential_scan.detail",
+        [
+            "The plan reads every row despite the WHERE clause, and the table is larger than pg_strict.seqscan_warn_size.",
+            "Der Plan liest trotz WHERE-Klausel jede Zeile, und die Tabelle ist größer als pg_strict.seqscan_warn_size.",
+            "Le plan lit toutes les lignes malgré la clause WHERE, et la table dépasse pg_strict.seqscan_warn_size.",
+            "WHERE 句があるにもかかわらずプランはすべての行を読み取り、テーブルは pg_strict.seqscan_warn_size より大きくなっています。",
+        ],
+    ),
+    (
+        "sequential_scan.hint",
+        [
+            "Filter on an indexed column, or add an index that matches the WHERE clause.",
+            "Filtern Sie nach einer indizierten Spalte, oder legen Sie einen zur WHERE-Klausel passenden Index an.",
+            "Filtrez sur une colonne indexée, ou ajoutez un index correspondant à la clause WHERE.",
+            "インデックスのある列で絞り込むか、WHERE 句に合うインデックスを追加してください。",
+        ],
+    ),
+    (
+        "outside_transaction.message",
+        [
+            "pg_strict: {operation} flagged by pg_strict must run inside a transaction block.",
+            "pg_strict: Ein von pg_strict beanstandetes {operation} muss in einem Transaktionsblock laufen.",
+            "pg_strict: un {operation} signalé par pg_strict doit s'exécuter dans un bloc de transaction.",
+            "pg_strict: pg_strict が検出した {operation} はトランザクションブロック内で実行する必要があります。",
+        ],
+    ),
+    (
+        "outside_transaction.detail",
+        [
+            "pg_strict.require_transaction is on and the statement would run in autocommit mode.",
+            "pg_strict.require_transaction ist aktiv, und die Anweisung würde im Autocommit-Modus laufen.",
+            "pg_strict.require_transaction est activé et l'instruction s'exécuterait en mode autocommit.",
+            "pg_strict.require_transaction が有効で、この文は自動コミットモードで実行されます。",
+        ],
+    ),
+    (
+        "outside_transaction.hint",
+        [
+            "Run BEGIN first, check the result, then COMMIT or ROLLBACK.",
+            "Führen Sie zuerst BEGIN aus, prüfen Sie das Ergebnis und dann COMMIT oder ROLLBACK.",
+            "Exécutez d'abord BEGIN, vérifiez le résultat, puis COMMIT ou ROLLBACK.",
+            "先に BEGIN を実行し、結果を確認してから COMMIT または ROLLBACK してください。",
+        ],
+    ),
+    (
+        "unbounded_timeouts.message",
+        [
+            "pg_strict: {operation} flagged by pg_strict must run with statement_timeout and lock_timeout set.",
+            "pg_strict: Ein von pg_strict beanstandetes {operation} muss mit gesetztem statement_timeout und lock_timeout laufen.",
+            "pg_strict: un {operation} signalé par pg_strict doit s'exécuter avec statement_timeout et lock_timeout définis.",
+            "pg_strict: pg_strict が検出した {operation} は statement_timeout と lock_timeout を設定して実行する必要があります。",
+        ],
+    ),
+    (
+        "unbounded_timeouts.detail",
+        [
+            "pg_strict.require_timeouts is on and {problems}.",
+            "pg_strict.require_timeouts ist aktiv: {problems}.",
+            "pg_strict.require_timeouts est activé : {problems}.",
+            "pg_strict.require_timeouts が有効です: {problems}。",
+        ],
+    ),
+    (
+        "unbounded_timeouts.hint",
+        [
+            "Set both for the transaction first, for example SET LOCAL statement_timeout = '5min' and SET LOCAL lock_timeout = '5s'.",
+            "Setzen Sie zuerst beide für die Transaktion, zum Beispiel SET LOCAL statement_timeout = '5min' und SET LOCAL lock_timeout = '5s'.",
+            "Définissez d'abord les deux pour la transaction, par exemple SET LOCAL statement_timeout = '5min' et SET LOCAL lock_timeout = '5s'.",
+            "先にトランザクションで両方を設定してください。例: SET LOCAL statement_timeout = '5min' と SET LOCAL lock_timeout = '5s'。",
+        ],
+    ),
+    (
+        "stale_backup.message",
+        [
+            "pg_strict: {operation} on table {table} requires a recent backup.",
+            "pg_strict: {operation} auf Tabelle {table} erfordert eine aktuelle Sicherung.",
+            "pg_strict: {operation} sur la table {table} exige une sauvegarde récente.",
+            "pg_strict: テーブル {table} に対する {operation} には最近のバックアップが必要です。",
+        ],
+    ),
+    (
+        "stale_backup.hint",
+        [
+            "Take a backup first. Backup tooling should update pg_strict.last_backup_time, or pg_strict.backup_probe should find the new backup.",
+            "Erstellen Sie zuerst eine Sicherung. Das Sicherungswerkzeug sollte pg_strict.last_backup_time aktualisieren, oder pg_strict.backup_probe sollte die neue Sicherung finden.",
+            "Faites d'abord une sauvegarde. L'outil de sauvegarde doit mettre à jour pg_strict.last_backup_time, ou pg_strict.backup_probe doit trouver la nouvelle sauvegarde.",
+            "先にバックアップを取得してください。バックアップツールが pg_strict.last_backup_time を更新するか、pg_strict.backup_probe が新しいバックアップを検出する必要があります。",
+        ],
+    ),
+    (
+        "row_limit_exceeded.message",
+        [
+            "pg_strict: {operation} aborted after modifying more than {limit} rows.",
+            "pg_strict: {operation} wurde nach Änderung von mehr als {limit} Zeilen abgebrochen.",
+            "pg_strict: {operation} interrompu après avoir modifié plus de {limit} lignes.",
+            "pg_strict: {limit} 行を超えて変更したため {operation} を中止しました。",
+        ],
+    ),
+    (
+        "row_limit_exceeded.detail",
+        [
+            "The statement exceeded pg_strict.max_affected_rows ({limit}) and was rolled back.",
+            "Die Anweisung hat pg_strict.max_affected_rows ({limit}) überschritten und wurde zurückgerollt.",
+            "L'instruction a dépassé pg_strict.max_affected_rows ({limit}) et a été annulée.",
+            "この文は pg_strict.max_affected_rows ({limit}) を超えたためロールバックされました。",
+        ],
+    ),
+    (
+        "row_limit_exceeded.hint_update",
+        [
+            "Modify the rows in smaller batches with CALL pg_strict_update_in_batches(), or raise pg_strict.max_affected_rows with SET LOCAL for this transaction.",
+            "Ändern Sie die Zeilen in kleineren Portionen mit CALL pg_strict_update_in_batches(), oder erhöhen Sie pg_strict.max_affected_rows mit SET LOCAL für diese Transaktion.",
+            "Modifiez les lignes par petits lots avec CALL pg_strict_update_in_batches(), ou augmentez pg_strict.max_affected_rows avec SET LOCAL pour cette transaction.",
+            "CALL pg_strict_update_in_batches() で小さなバッチに分けて変更するか、このトランザクションに限り SET LOCAL で pg_strict.max_affected_rows を引き上げてください。",
+        ],
+    ),
+    (
+        "row_limit_exceeded.hint_delete",
+        [
+            "Modify the rows in smaller batches with CALL pg_strict_delete_in_batches(), or raise pg_strict.max_affected_rows with SET LOCAL for this transaction.",
+            "Löschen Sie die Zeilen in kleineren Portionen mit CALL pg_strict_delete_in_batches(), oder erhöhen Sie pg_strict.max_affected_rows mit SET LOCAL für diese Transaktion.",
+            "Supprimez les lignes par petits lots avec CALL pg_strict_delete_in_batches(), ou augmentez pg_strict.max_affected_rows avec SET LOCAL pour cette transaction.",
+            "CALL pg_strict_delete_in_batches() で小さなバッチに分けて削除するか、このトランザクションに限り SET LOCAL で pg_strict.max_affected_rows を引き上げてください。",
+        ],
+    ),
+    (
+        "report.cascades",
+        [
+            "Foreign keys extend it to {cascades}.",
+            "Fremdschlüssel erweitern sie auf {cascades}.",
+            "Les clés étrangères l'étendent à {cascades}.",
+            "外部キーにより {cascades} にも及びます。",
+        ],
+    ),
+    (
+        "report.triggers",
+        [
+            "Row-level triggers fire for each row: {triggers}.",
+            "Zeilentrigger werden für jede Zeile ausgelöst: {triggers}.",
+            "Des triggers de niveau ligne se déclenchent pour chaque ligne : {triggers}.",
+            "行レベルトリガーが各行に対して起動します: {triggers}。",
+        ],
+    ),
+    (
+        "report.rewrite_rule",
+        [
+            "The statement was added by a rewrite rule.",
+            "Die Anweisung wurde von einer Umschreiberegel hinzugefügt.",
+            "L'instruction a été ajoutée par une règle de réécriture.",
+            "この文は書き換えルールによって追加されました。",
+        ],
+    ),
+    (
+        "report.origin",
+        [
+            "It was run by {origin}.",
+            "Sie wurde von {origin} ausgeführt.",
+            "Elle a été exécutée par {origin}.",
+            "{origin} によって実行されました。",
+        ],
+    ),
+    (
+        "report.query_id",
+        [
+            "Query identifier: {query_id}.",
+            "Abfragekennung: {query_id}.",
+            "Identifiant de requête : {query_id}.",
+            "クエリ識別子: {query_id}。",
+        ],
+    ),
+    (
+        "report.suppressed",
+        [
+            "{count} similar warnings were suppressed (pg_strict.max_warnings_per_minute).",
+            "{count} ähnliche Warnungen wurden unterdrückt (pg_strict.max_warnings_per_minute).",
+            "{count} avertissements similaires ont été supprimés (pg_strict.max_warnings_per_minute).",
+            "{count} 件の同様の警告が抑制されました (pg_strict.max_warnings_per_minute)。",
+        ],
+    ),
+    (
+        "report.escalation",
+        [
+            "It will be blocked from {deadline} (pg_strict.escalate_at).",
+            "Sie wird ab {deadline} blockiert (pg_strict.escalate_at).",
+            "Elle sera bloquée à partir de {deadline} (pg_strict.escalate_at).",
+            "{deadline} からブロックされます (pg_strict.escalate_at)。",
+        ],
+    ),
+];
+
+/// The text for `key` in `pg_strict.message_language`.
+pub fn lookup(key: &str) -> &'static str {
+    lookup_in(message_language(), key)
+}
+
+pub fn lookup_in(language: MessageLanguage, key: &str) -> &'static str {
+    let Some((_, texts)) = CATALOG.iter().find(|(name, _)| *name == key) else {
+        pgrx::error!("pg_strict: no message text for \"{}\"", key);
+    };
+    texts[language as usize]
+}
+
+/// The text for `key` with its `{name}` placeholders filled in.
+pub fn text(key: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(lookup(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
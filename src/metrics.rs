@@ -0,0 +1,124 @@
+use crate::guc::{metrics_address, metrics_port};
+use crate::shmem;
+use crate::stats;
+use pgrx::bgworkers::{BackgroundWorker, BackgroundWorkerBuilder, SignalWakeFlags};
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Starts the metrics worker when pg_strict is preloaded with
+/// `pg_strict.metrics_port` set.
+pub fn register_worker() {
+    if !shmem::available() || metrics_port() == 0 {
+        return;
+    }
+
+    BackgroundWorkerBuilder::new("pg_strict metrics")
+        .set_type("pg_strict metrics")
+        .set_function("pg_strict_metrics_main")
+        .set_library("pg_strict")
+        .enable_shmem_access(None)
+        .set_restart_time(Some(Duration::from_secs(10)))
+        .load();
+}
+
+#[pg_guard]
+#[unsafe(no_mangle)]
+pub extern "C-unwind" fn pg_strict_metrics_main(_arg: pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+
+    let address = format!("{}:{}", metrics_address(), metrics_port());
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(error) => pgrx::error!("pg_strict: could not listen on {}: {}", address, error),
+    };
+    if let Err(error) = listener.set_nonblocking(true) {
+        pgrx::error!("pg_strict: could not configure metrics listener: {}", error);
+    }
+    pgrx::log!("pg_strict: serving metrics on {}", address);
+
+    while BackgroundWorker::wait_latch(Some(Duration::from_millis(100))) {
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => serve(stream),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    pgrx::log!("pg_strict: metrics connection failed: {}", error);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream) {
+    // Every request gets the metrics, whatever its path; read it only so the
+    // client does not see a reset connection.
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+    let mut request = [0u8; 1024];
+    let _ = stream.read(&mut request);
+
+    let body = render();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Current counters in the Prometheus text exposition format.
+pub fn render() -> String {
+    let stats = stats::snapshot();
+    let mut out = String::new();
+
+    metric(
+        &mut out,
+        "pg_strict_statements_checked_total",
+        "UPDATE and DELETE statements checked.",
+        &[("", stats.statements_checked as f64)],
+    );
+    metric(
+        &mut out,
+        "pg_strict_violations_total",
+        "Statements without a WHERE clause, by action taken.",
+        &[
+            ("action=\"warned\"", stats.violations_warned as f64),
+            ("action=\"blocked\"", stats.violations_blocked as f64),
+        ],
+    );
+    metric(
+        &mut out,
+        "pg_strict_bypasses_total",
+        "Statements allowed through by a bypass.",
+        &[("", stats.bypasses as f64)],
+    );
+    metric(
+        &mut out,
+        "pg_strict_parse_failures_total",
+        "Statements the validation functions could not parse.",
+        &[("", stats.parse_failures as f64)],
+    );
+    metric(
+        &mut out,
+        "pg_strict_check_seconds_total",
+        "Time spent checking statements.",
+        &[("", stats.check_time_us as f64 / 1_000_000.0)],
+    );
+
+    out
+}
+
+fn metric(out: &mut String, name: &str, help: &str, samples: &[(&str, f64)]) {
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} counter\n"));
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            out.push_str(&format!("{name} {value}\n"));
+        } else {
+            out.push_str(&format!("{name}{{{labels}}} {value}\n"));
+        }
+    }
+}
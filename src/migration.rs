@@ -0,0 +1,196 @@
+use crate::catalog;
+use crate::guc::StrictMode;
+use crate::lint;
+use crate::utility::relation_oid;
+use pgrx::PgList;
+use pgrx::pg_sys;
+
+/// How much a finding of `pg_strict_validate_migration()` should hold up a
+/// deployment.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Blocker,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Blocker => "blocker",
+        }
+    }
+}
+
+pub struct Finding {
+    pub statement: i32,
+    pub severity: Severity,
+    pub rule: &'static str,
+    pub operation: String,
+    pub table: Option<String>,
+    pub message: String,
+    pub fix: String,
+}
+
+/// Checks a migration script without running it: the statements pg_strict
+/// would report, graded by the mode that applies to them, and the DDL that
+/// holds long locks on existing tables. Blockers first, then by statement.
+pub fn validate(script: &str) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = lint::lint(script)
+        .into_iter()
+        .map(|finding| Finding {
+            statement: finding.statement,
+            severity: match finding.mode {
+                StrictMode::On => Severity::Blocker,
+                StrictMode::Warn => Severity::Warning,
+                _ => Severity::Info,
+            },
+            rule: finding.rule,
+            operation: finding.operation.to_string(),
+            table: finding.table,
+            message: finding.message,
+            fix: finding.fix,
+        })
+        .collect();
+
+    let c_script = lint::c_script(script);
+    for (index, raw) in lint::parse(&c_script).iter_ptr().enumerate() {
+        let node = unsafe { (*raw).stmt };
+        if !node.is_null() {
+            unsafe { check_ddl(index as i32 + 1, node, &mut findings) };
+        }
+    }
+
+    findings.sort_by_key(|finding| (std::cmp::Reverse(finding.severity), finding.statement));
+    findings
+}
+
+unsafe fn check_ddl(statement: i32, node: *mut pg_sys::Node, findings: &mut Vec<Finding>) {
+    match unsafe { (*node).type_ } {
+        pg_sys::NodeTag::T_IndexStmt => {
+            let index = node as *mut pg_sys::IndexStmt;
+            if unsafe { (*index).concurrent } {
+                return;
+            }
+            // Tables created earlier in the same migration are still empty.
+            let Some(relid) = (unsafe { relation_oid((*index).relation) }) else {
+                return;
+            };
+            findings.push(Finding {
+                statement,
+                severity: Severity::Warning,
+                rule: "index_without_concurrently",
+                operation: "CREATE INDEX".to_string(),
+                table: Some(catalog::qualified_relation_name(relid)),
+                message: "blocks writes to the table until the index is built".to_string(),
+                fix: "Use CREATE INDEX CONCURRENTLY, outside a transaction block.".to_string(),
+            });
+        }
+        pg_sys::NodeTag::T_AlterTableStmt => {
+            let alter = node as *mut pg_sys::AlterTableStmt;
+            let Some(relid) = (unsafe { relation_oid((*alter).relation) }) else {
+                return;
+            };
+            let table = catalog::qualified_relation_name(relid);
+            let commands = unsafe { PgList::<pg_sys::AlterTableCmd>::from_pg((*alter).cmds) };
+            for command in commands.iter_ptr() {
+                if let Some((severity, rule, message, fix)) = unsafe { locking_alter(command) } {
+                    findings.push(Finding {
+                        statement,
+                        severity,
+                        rule,
+                        operation: "ALTER TABLE".to_string(),
+                        table: Some(table.clone()),
+                        message: message.to_string(),
+                        fix: fix.to_string(),
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// What an ALTER TABLE subcommand costs on a table that already has rows,
+/// for the subcommands that hold an ACCESS EXCLUSIVE lock for long.
+unsafe fn locking_alter(
+    command: *mut pg_sys::AlterTableCmd,
+) -> Option<(Severity, &'static str, &'static str, &'static str)> {
+    match unsafe { (*command).subtype } {
+        pg_sys::AlterTableType::AT_AlterColumnType => Some((
+            Severity::Blocker,
+            "column_type_change",
+            "rewrites the whole table under an ACCESS EXCLUSIVE lock",
+            "Add a new column, backfill it in batches, and switch over to it.",
+        )),
+        pg_sys::AlterTableType::AT_SetNotNull => Some((
+            Severity::Warning,
+            "set_not_null",
+            "scans the whole table under an ACCESS EXCLUSIVE lock",
+            "Add CHECK (column IS NOT NULL) NOT VALID, VALIDATE it, then SET NOT NULL.",
+        )),
+        pg_sys::AlterTableType::AT_AddConstraint => {
+            let constraint = unsafe { (*command).def } as *mut pg_sys::Constraint;
+            if constraint.is_null()
+                || unsafe { (*(constraint as *mut pg_sys::Node)).type_ }
+                    != pg_sys::NodeTag::T_Constraint
+            {
+                return None;
+            }
+            match unsafe { (*constraint).contype } {
+                pg_sys::ConstrType::CONSTR_FOREIGN | pg_sys::ConstrType::CONSTR_CHECK
+                    if !unsafe { (*constraint).skip_validation } =>
+                {
+                    Some((
+                        Severity::Warning,
+                        "constraint_validation",
+                        "checks every row while holding the lock",
+                        "Add the constraint NOT VALID, then VALIDATE CONSTRAINT in a later step.",
+                    ))
+                }
+                pg_sys::ConstrType::CONSTR_PRIMARY | pg_sys::ConstrType::CONSTR_UNIQUE
+                    if unsafe { (*constraint).indexname }.is_null() =>
+                {
+                    Some((
+                        Severity::Warning,
+                        "constraint_index_build",
+                        "builds its index under an ACCESS EXCLUSIVE lock",
+                        "CREATE UNIQUE INDEX CONCURRENTLY first, then ADD CONSTRAINT ... USING INDEX.",
+                    ))
+                }
+                _ => None,
+            }
+        }
+        pg_sys::AlterTableType::AT_AddColumn if unsafe { has_call_default(command) } => Some((
+            Severity::Info,
+            "column_default_rewrite",
+            "rewrites the table if the default is volatile",
+            "Add the column without a default, then set the default and backfill in batches.",
+        )),
+        pg_sys::AlterTableType::AT_DropColumn => Some((
+            Severity::Info,
+            "drop_column",
+            "breaks clients that still read the column",
+            "Stop using the column in the application before dropping it.",
+        )),
+        _ => None,
+    }
+}
+
+/// Whether an ADD COLUMN sets a default computed by a function call.
+unsafe fn has_call_default(command: *mut pg_sys::AlterTableCmd) -> bool {
+    let column = unsafe { (*command).def } as *mut pg_sys::ColumnDef;
+    if column.is_null()
+        || unsafe { (*(column as *mut pg_sys::Node)).type_ } != pg_sys::NodeTag::T_ColumnDef
+    {
+        return false;
+    }
+    let constraints = unsafe { PgList::<pg_sys::Constraint>::from_pg((*column).constraints) };
+    constraints.iter_ptr().any(|constraint| unsafe {
+        (*constraint).contype == pg_sys::ConstrType::CONSTR_DEFAULT
+            && !(*constraint).raw_expr.is_null()
+            && (*(*constraint).raw_expr).type_ == pg_sys::NodeTag::T_FuncCall
+    })
+}
@@ -0,0 +1,106 @@
+use crate::delivery::{self, BlockedNotification, Delivery};
+use crate::guc::notify_channel;
+use pgrx::pg_sys;
+use std::ffi::{CStr, CString};
+
+// NOTIFY payloads must stay below the server's 8000 byte limit.
+const MAX_PAYLOAD_LEN: usize = 7999;
+
+// Notifications for blocked statements, which cannot be sent from their own
+// aborted transaction. Without the delivery worker, the session's next
+// statement sends them.
+static mut PENDING_NOTIFICATIONS: Vec<(String, String)> = Vec::new();
+
+/// Announces a violation on `pg_strict.notify_channel` for LISTEN-based tooling.
+#[allow(static_mut_refs)]
+pub fn notify_violation(operation: &str, fingerprint: i64, blocked: bool) {
+    let Some(channel) = notify_channel() else {
+        return;
+    };
+
+    let payload = violation_payload(operation, fingerprint, blocked);
+    if blocked {
+        let notification = BlockedNotification::new(&channel, &payload);
+        if !delivery::enqueue(Delivery::Notification(notification)) {
+            unsafe { PENDING_NOTIFICATIONS.push((channel, payload)) };
+        }
+    } else {
+        send(&channel, &payload);
+    }
+}
+
+/// The JSON object describing a violation, shared by NOTIFY and the webhook.
+pub fn violation_payload(operation: &str, fingerprint: i64, blocked: bool) -> String {
+    format!(
+        "{{\"user\":{},\"database\":{},\"operation\":{},\"fingerprint\":{},\"action\":{}}}",
+        json_string(&session_user_name().unwrap_or_default()),
+        json_string(&database_name().unwrap_or_default()),
+        json_string(operation),
+        fingerprint,
+        json_string(if blocked { "blocked" } else { "warned" }),
+    )
+}
+
+#[allow(static_mut_refs)]
+pub fn flush_pending_notifications() {
+    if unsafe { PENDING_NOTIFICATIONS.is_empty() } {
+        return;
+    }
+    if unsafe { pg_sys::IsAbortedTransactionBlockState() } {
+        return;
+    }
+
+    let pending = unsafe { std::mem::take(&mut PENDING_NOTIFICATIONS) };
+    for (channel, payload) in &pending {
+        send(channel, payload);
+    }
+}
+
+pub fn send(channel: &str, payload: &str) {
+    let mut len = payload.len().min(MAX_PAYLOAD_LEN);
+    while !payload.is_char_boundary(len) {
+        len -= 1;
+    }
+    let (Ok(channel), Ok(payload)) = (CString::new(channel), CString::new(&payload[..len])) else {
+        return;
+    };
+    unsafe { pg_sys::Async_Notify(channel.as_ptr(), payload.as_ptr()) };
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn session_user_name() -> Option<String> {
+    unsafe {
+        let name = pg_sys::GetUserNameFromId(pg_sys::GetSessionUserId(), true);
+        if name.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+    }
+}
+
+pub fn database_name() -> Option<String> {
+    unsafe {
+        let name = pg_sys::get_database_name(pg_sys::MyDatabaseId);
+        if name.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+    }
+}
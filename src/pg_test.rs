@@ -2,5 +2,13 @@ pub fn setup(_options: Vec<&str>) {}
 
 #[must_use]
 pub fn postgresql_conf_options() -> Vec<&'static str> {
-    vec![]
+    // Test statements run through SPI inside the test function, one executor
+    // level down.
+    let mut options = vec![
+        "shared_preload_libraries = 'pg_strict'",
+        "pg_strict.check_nested = 'all'",
+    ];
+    #[cfg(not(feature = "pg13"))]
+    options.push("compute_query_id = on");
+    options
 }
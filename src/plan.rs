@@ -0,0 +1,41 @@
+use crate::guc::{PlanFormat, log_plans};
+use crate::subxact;
+use pgrx::prelude::*;
+
+/// Logs the planner's view of a violating statement, auto_explain style, when
+/// `pg_strict.log_plans` is set. The statement is only planned, never executed.
+pub fn log_plan(operation: &str, blocked: bool, statement: &str) {
+    let format = match log_plans() {
+        PlanFormat::Off => return,
+        PlanFormat::Text => "TEXT",
+        PlanFormat::Json => "JSON",
+    };
+
+    let Some(plan) = explain(&format!("EXPLAIN (FORMAT {format}) {statement}")) else {
+        return;
+    };
+    pgrx::log!(
+        "pg_strict: plan of {} {} statement:\n{}",
+        if blocked { "blocked" } else { "warned" },
+        operation,
+        plan
+    );
+}
+
+/// Runs EXPLAIN in a subtransaction, so statements that cannot be planned on
+/// their own (for example ones with parameters) do not disturb the caller.
+fn explain(sql: &str) -> Option<String> {
+    subxact::try_in_subtransaction(|| {
+        Spi::connect(|client| {
+            let mut lines = Vec::new();
+            for row in client.select(sql, None, &[])? {
+                if let Some(line) = row.get::<String>(1)? {
+                    lines.push(line);
+                }
+            }
+            Ok::<_, pgrx::spi::Error>(lines)
+        })
+        .ok()
+        .map(|lines| lines.join("\n"))
+    })
+}
@@ -0,0 +1,174 @@
+use pgrx::pg_guard;
+use pgrx::pg_sys;
+use std::ffi::{CStr, c_char, c_int, c_uint, c_void};
+
+// plpgsql.h is not part of pgrx's bindings. Only the leading fields pg_strict
+// reads are declared; they have kept their layout since PostgreSQL 12.
+#[repr(C)]
+struct PlpgsqlFunction {
+    fn_signature: *mut c_char,
+}
+
+#[repr(C)]
+struct PlpgsqlStmt {
+    cmd_type: c_uint,
+    lineno: c_int,
+}
+
+type FunctionCallback = unsafe extern "C-unwind" fn(*mut c_void, *mut PlpgsqlFunction);
+type StmtCallback = unsafe extern "C-unwind" fn(*mut c_void, *mut PlpgsqlStmt);
+
+// PLpgSQL_plugin. The trailing pointers are filled in by PL/pgSQL itself and
+// only need room; PostgreSQL 13 has fewer of them.
+#[repr(C)]
+struct PlpgsqlPlugin {
+    func_setup: Option<FunctionCallback>,
+    func_beg: Option<FunctionCallback>,
+    func_end: Option<FunctionCallback>,
+    stmt_beg: Option<StmtCallback>,
+    stmt_end: Option<StmtCallback>,
+    plpgsql_owned: [*mut c_void; 5],
+}
+
+// PLpgSQL_stmt_type values of the statements that run dynamic SQL.
+const PLPGSQL_STMT_DYNEXECUTE: c_uint = 17;
+const PLPGSQL_STMT_DYNFORS: c_uint = 18;
+
+static mut PLUGIN: PlpgsqlPlugin = PlpgsqlPlugin {
+    func_setup: None,
+    func_beg: Some(function_begin),
+    func_end: Some(function_end),
+    stmt_beg: Some(statement_begin),
+    stmt_end: Some(statement_end),
+    plpgsql_owned: [std::ptr::null_mut(); 5],
+};
+
+/// A PL/pgSQL function or DO block that is currently executing.
+struct Frame {
+    estate: usize,
+    function: String,
+    lineno: i32,
+    dynamic: bool,
+}
+
+static mut FRAMES: Vec<Frame> = Vec::new();
+
+fn plugin_slot() -> *mut *mut PlpgsqlPlugin {
+    unsafe {
+        pg_sys::find_rendezvous_variable(c"PLpgSQL_plugin".as_ptr()) as *mut *mut PlpgsqlPlugin
+    }
+}
+
+/// Registers pg_strict as the PL/pgSQL instrumentation plugin, unless another
+/// extension (a debugger or profiler) already holds the single plugin slot.
+pub fn install() {
+    let slot = plugin_slot();
+    unsafe {
+        if (*slot).is_null() {
+            *slot = &raw mut PLUGIN;
+        }
+    }
+}
+
+pub fn uninstall() {
+    let slot = plugin_slot();
+    unsafe {
+        if *slot == &raw mut PLUGIN {
+            *slot = std::ptr::null_mut();
+        }
+    }
+}
+
+/// Where the statement being checked was run from, when it is dynamic SQL
+/// run by EXECUTE in a PL/pgSQL function or DO block.
+#[allow(static_mut_refs)]
+pub fn dynamic_origin() -> Option<String> {
+    let frame = unsafe { FRAMES.last() }?;
+    frame
+        .dynamic
+        .then(|| describe_origin(&frame.function, frame.lineno))
+}
+
+pub fn describe_origin(function: &str, lineno: i32) -> String {
+    if function == "inline_code_block" {
+        format!("EXECUTE at line {lineno} of a DO block")
+    } else {
+        format!("EXECUTE at line {lineno} of function {function}")
+    }
+}
+
+/// Drops frames left behind by functions that ended with an error.
+#[allow(static_mut_refs)]
+pub fn reset() {
+    unsafe { FRAMES.clear() };
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn function_begin(estate: *mut c_void, function: *mut PlpgsqlFunction) {
+    let signature = unsafe { (*function).fn_signature };
+    let name = if signature.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(signature) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    unsafe {
+        FRAMES.push(Frame {
+            estate: estate as usize,
+            function: name,
+            lineno: 0,
+            dynamic: false,
+        })
+    };
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn function_end(estate: *mut c_void, _function: *mut PlpgsqlFunction) {
+    unsafe {
+        if let Some(index) = FRAMES
+            .iter()
+            .rposition(|frame| frame.estate == estate as usize)
+        {
+            FRAMES.truncate(index);
+        }
+    }
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn statement_begin(estate: *mut c_void, stmt: *mut PlpgsqlStmt) {
+    unsafe {
+        let Some(index) = FRAMES
+            .iter()
+            .rposition(|frame| frame.estate == estate as usize)
+        else {
+            return;
+        };
+        // Frames above this one belong to calls that were cut short by an
+        // error caught in an exception block.
+        FRAMES.truncate(index + 1);
+        let frame = &mut FRAMES[index];
+        frame.lineno = (*stmt).lineno;
+        frame.dynamic = matches!(
+            (*stmt).cmd_type,
+            PLPGSQL_STMT_DYNEXECUTE | PLPGSQL_STMT_DYNFORS
+        );
+    }
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn statement_end(estate: *mut c_void, _stmt: *mut PlpgsqlStmt) {
+    unsafe {
+        if let Some(frame) = FRAMES
+            .iter_mut()
+            .rev()
+            .find(|frame| frame.estate == estate as usize)
+        {
+            frame.dynamic = false;
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::guc::StrictMode;
+use crate::report::Violation;
+use crate::rules::parse_mode;
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use std::ffi::{CStr, CString, c_char, c_void};
+
+/// A per-statement check added by another extension or an embedder. It runs
+/// from pg_strict's parse analysis hook after the built-in checks, and its
+/// violations are recorded, logged and raised like theirs.
+pub trait StrictRule {
+    /// Identifies the check in logs, audit records and notifications.
+    fn name(&self) -> &str;
+    /// `off` and `learn` skip the check.
+    fn mode(&self) -> StrictMode;
+    /// Looks at an analyzed statement; `statement` is its source text.
+    fn check(&self, query: *mut pg_sys::Query, statement: &str) -> Option<Violation>;
+}
+
+/// The C form of a `StrictRule`, passed to `pg_strict_register_check()`.
+/// The strings are copied, so they need not outlive the call.
+#[repr(C)]
+pub struct PgStrictCheck {
+    pub name: *const c_char,
+    /// A setting holding the check's mode, with pg_strict's mode values.
+    /// Unset or unknown values leave the check off.
+    pub mode_setting: *const c_char,
+    /// Returns NULL when the statement passes, or the message to report it.
+    pub check: Option<
+        unsafe extern "C-unwind" fn(
+            query: *mut pg_sys::Query,
+            statement: *const c_char,
+            arg: *mut c_void,
+        ) -> *const c_char,
+    >,
+    pub arg: *mut c_void,
+}
+
+// Registered in _PG_init of the registering library, so every backend has
+// the same list.
+static mut RULES: Vec<Box<dyn StrictRule>> = Vec::new();
+
+/// Adds a check to the ones pg_strict runs on every statement.
+#[allow(static_mut_refs)]
+pub fn register_rule(rule: Box<dyn StrictRule>) {
+    unsafe { RULES.push(rule) };
+}
+
+#[allow(static_mut_refs)]
+pub fn any() -> bool {
+    unsafe { !RULES.is_empty() }
+}
+
+/// Runs the registered checks that are not off, returning the name, mode and
+/// violation of each one that flagged the statement.
+#[allow(static_mut_refs)]
+pub fn check(query: *mut pg_sys::Query, statement: &str) -> Vec<(String, StrictMode, Violation)> {
+    unsafe { RULES.iter() }
+        .filter_map(|rule| {
+            let mode = rule.mode();
+            if matches!(mode, StrictMode::Off | StrictMode::Learn) {
+                return None;
+            }
+            let violation = rule.check(query, statement)?;
+            Some((rule.name().to_string(), mode, violation))
+        })
+        .collect()
+}
+
+struct CRule {
+    name: String,
+    mode_setting: CString,
+    check: unsafe extern "C-unwind" fn(
+        *mut pg_sys::Query,
+        *const c_char,
+        *mut c_void,
+    ) -> *const c_char,
+    arg: *mut c_void,
+}
+
+impl StrictRule for CRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn mode(&self) -> StrictMode {
+        let value = unsafe { pg_sys::GetConfigOption(self.mode_setting.as_ptr(), true, false) };
+        if value.is_null() {
+            return StrictMode::Off;
+        }
+        parse_mode(&unsafe { CStr::from_ptr(value) }.to_string_lossy()).unwrap_or(StrictMode::Off)
+    }
+
+    fn check(&self, query: *mut pg_sys::Query, statement: &str) -> Option<Violation> {
+        let statement = CString::new(statement).ok()?;
+        let message = unsafe { (self.check)(query, statement.as_ptr(), self.arg) };
+        if message.is_null() {
+            return None;
+        }
+        let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+        Some(Violation::custom(
+            &self.name,
+            &message,
+            &self.mode_setting.to_string_lossy(),
+        ))
+    }
+}
+
+/// Registers a check written in C. Returns false when the name, the mode
+/// setting or the function is missing. Other libraries find it through the
+/// rendezvous variable of the same name, so pg_strict must be loaded first.
+#[pg_guard]
+#[unsafe(no_mangle)]
+pub unsafe extern "C-unwind" fn pg_strict_register_check(check: *const PgStrictCheck) -> bool {
+    if check.is_null() {
+        return false;
+    }
+    let check = unsafe { &*check };
+    let Some(function) = check.check else {
+        return false;
+    };
+    if check.name.is_null() || check.mode_setting.is_null() {
+        return false;
+    }
+    register_rule(Box::new(CRule {
+        name: unsafe { CStr::from_ptr(check.name) }
+            .to_string_lossy()
+            .into_owned(),
+        mode_setting: unsafe { CStr::from_ptr(check.mode_setting) }.to_owned(),
+        check: function,
+        arg: check.arg,
+    }));
+    true
+}
+
+/// Publishes `pg_strict_register_check()` for libraries loaded after this one.
+pub fn publish() {
+    unsafe {
+        let slot = pg_sys::find_rendezvous_variable(c"pg_strict_register_check".as_ptr());
+        *slot = pg_strict_register_check as *mut c_void;
+    }
+}
@@ -0,0 +1,192 @@
+use crate::analyzer::Operation;
+use crate::guc::{
+    StrictEnforce, StrictMode, StrictPreset, current_enforce, current_modes, current_preset,
+    escalate_at, maintenance_windows, max_affected_percent, max_affected_rows,
+    protect_catalogs_mode, sample_rate, setting_is_default,
+};
+use crate::rules;
+use crate::schedule;
+use pgrx::prelude::*;
+
+pub const UPDATE_MODE_GUC: &str = "pg_strict.require_where_on_update";
+pub const DELETE_MODE_GUC: &str = "pg_strict.require_where_on_delete";
+pub const PROTECT_CATALOGS_GUC: &str = "pg_strict.protect_catalogs";
+pub const GUARD_TRUNCATE_GUC: &str = "pg_strict.guard_truncate";
+pub const LINT_FUNCTIONS_GUC: &str = "pg_strict.lint_functions";
+pub const MAX_AFFECTED_ROWS_GUC: &str = "pg_strict.max_affected_rows";
+pub const MAX_AFFECTED_PERCENT_GUC: &str = "pg_strict.max_affected_percent";
+
+/// The mode pg_strict actually enforces for `operation` on `relid`, after
+/// applying the preset, `pg_strict.enforce`, a matching row of strict.rules,
+/// grace-period escalation and maintenance windows.
+pub fn effective_mode(operation: Operation, relid: Option<pg_sys::Oid>) -> StrictMode {
+    let rule_mode = rules::mode(operation, relid).map(|(_, mode)| mode);
+    let mut mode = if forced() {
+        StrictMode::On
+    } else if let Some(rule_mode) = rule_mode {
+        rule_mode
+    } else {
+        let (update_mode, delete_mode) = current_modes();
+        match operation {
+            Operation::Update => resolve(UPDATE_MODE_GUC, update_mode),
+            Operation::Delete => resolve(DELETE_MODE_GUC, delete_mode),
+        }
+    };
+
+    if mode.is_warning() && escalation() == Some(Escalation::Reached) {
+        mode = StrictMode::On;
+    }
+
+    if mode == StrictMode::On && in_maintenance_window() {
+        return StrictMode::Warn;
+    }
+    mode
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Escalation {
+    /// Warn mode will become on at the given (display-formatted) time.
+    Pending(String),
+    Reached,
+}
+
+/// State of the `pg_strict.escalate_at` grace period, if one is configured.
+pub fn escalation() -> Option<Escalation> {
+    let configured = escalate_at()?;
+    let Ok(deadline) = configured.parse::<TimestampWithTimeZone>() else {
+        pgrx::warning!(
+            "pg_strict: ignoring invalid pg_strict.escalate_at value \"{}\".",
+            configured
+        );
+        return None;
+    };
+    let deadline_usecs = deadline.into_datum()?.value() as i64;
+
+    if unsafe { pg_sys::GetCurrentTimestamp() } >= deadline_usecs {
+        Some(Escalation::Reached)
+    } else {
+        Some(Escalation::Pending(configured))
+    }
+}
+
+pub fn in_maintenance_window() -> bool {
+    let windows = maintenance_windows();
+    if windows.is_empty() {
+        return false;
+    }
+    schedule::local_now()
+        .map(|now| schedule::in_any_window(&windows, now))
+        .unwrap_or(false)
+}
+
+pub fn forced() -> bool {
+    current_enforce() == StrictEnforce::Force
+}
+
+/// Whether session-level bypasses (allow_next, override, snooze) may be honoured.
+pub fn session_bypass_allowed() -> bool {
+    !forced() || unsafe { pg_sys::superuser() }
+}
+
+/// Whether a violation reported in `mode` falls in the `pg_strict.sample_rate`
+/// sample. Only the warning modes and learn are sampled; blocking always applies.
+pub fn sampled(mode: StrictMode) -> bool {
+    if !(mode.is_warning() || mode == StrictMode::Learn) {
+        return true;
+    }
+    let rate = sample_rate();
+    rate >= 1.0 || (rate > 0.0 && next_random() < rate)
+}
+
+static mut RANDOM_STATE: u64 = 0;
+
+/// Uniform value in [0, 1) from a per-backend xorshift generator.
+fn next_random() -> f64 {
+    unsafe {
+        if RANDOM_STATE == 0 {
+            RANDOM_STATE =
+                (pg_sys::GetCurrentTimestamp() as u64 ^ ((pg_sys::MyProcPid as u64) << 32)) | 1;
+        }
+        RANDOM_STATE ^= RANDOM_STATE << 13;
+        RANDOM_STATE ^= RANDOM_STATE >> 7;
+        RANDOM_STATE ^= RANDOM_STATE << 17;
+        (RANDOM_STATE >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Cheap pre-check so the hook can skip catalog lookups when the rule cannot apply.
+pub fn catalog_protection_configured() -> bool {
+    protect_catalogs_mode() != StrictMode::Off || current_preset() != StrictPreset::None
+}
+
+pub fn effective_protect_catalogs_mode() -> StrictMode {
+    resolve(PROTECT_CATALOGS_GUC, protect_catalogs_mode())
+}
+
+/// `pg_strict.max_affected_rows`, or the preset's ceiling if it is unset.
+pub fn effective_max_affected_rows() -> i32 {
+    resolve_threshold(MAX_AFFECTED_ROWS_GUC, f64::from(max_affected_rows())) as i32
+}
+
+/// `pg_strict.max_affected_percent`, or the preset's threshold if it is unset.
+pub fn effective_max_affected_percent() -> f64 {
+    resolve_threshold(MAX_AFFECTED_PERCENT_GUC, max_affected_percent())
+}
+
+pub fn preset_mode(preset: StrictPreset, guc_name: &str) -> Option<StrictMode> {
+    let mode = match (preset, guc_name) {
+        (StrictPreset::None, _) => return None,
+        (
+            StrictPreset::Production,
+            UPDATE_MODE_GUC | DELETE_MODE_GUC | PROTECT_CATALOGS_GUC | GUARD_TRUNCATE_GUC,
+        ) => StrictMode::On,
+        (StrictPreset::Production, LINT_FUNCTIONS_GUC) => StrictMode::Warn,
+        (
+            StrictPreset::Staging,
+            UPDATE_MODE_GUC | DELETE_MODE_GUC | PROTECT_CATALOGS_GUC | GUARD_TRUNCATE_GUC
+            | LINT_FUNCTIONS_GUC,
+        ) => StrictMode::Warn,
+        (StrictPreset::Development, UPDATE_MODE_GUC | GUARD_TRUNCATE_GUC | LINT_FUNCTIONS_GUC) => {
+            StrictMode::Off
+        }
+        (StrictPreset::Development, DELETE_MODE_GUC | PROTECT_CATALOGS_GUC) => StrictMode::Warn,
+        _ => return None,
+    };
+    Some(mode)
+}
+
+/// The row ceiling or percentage a preset supplies for an unset threshold.
+pub fn preset_threshold(preset: StrictPreset, guc_name: &str) -> Option<f64> {
+    let threshold = match (preset, guc_name) {
+        (StrictPreset::Production, MAX_AFFECTED_ROWS_GUC) => 100_000.0,
+        (StrictPreset::Production, MAX_AFFECTED_PERCENT_GUC) => 50.0,
+        (StrictPreset::Staging, MAX_AFFECTED_ROWS_GUC) => 1_000_000.0,
+        (StrictPreset::Staging, MAX_AFFECTED_PERCENT_GUC) => 90.0,
+        _ => return None,
+    };
+    Some(threshold)
+}
+
+/// The mode of `guc_name`, or the preset's mode if nobody has set it.
+pub fn resolve(guc_name: &str, configured: StrictMode) -> StrictMode {
+    let preset = current_preset();
+    let Some(preset_mode) = preset_mode(preset, guc_name) else {
+        return configured;
+    };
+    if setting_is_default(guc_name) {
+        preset_mode
+    } else {
+        configured
+    }
+}
+
+fn resolve_threshold(guc_name: &str, configured: f64) -> f64 {
+    let Some(preset_threshold) = preset_threshold(current_preset(), guc_name) else {
+        return configured;
+    };
+    if setting_is_default(guc_name) {
+        preset_threshold
+    } else {
+        configured
+    }
+}
@@ -0,0 +1,123 @@
+use crate::analyzer::{self, Operation, QueryAnalyzer};
+use crate::guc::{
+    recycle_bin_database, recycle_bin_enabled, recycle_bin_max_rows, recycle_bin_retention,
+};
+use crate::logging::loggable_statement;
+use crate::schema::extension_installed;
+use crate::shmem;
+use crate::subxact;
+use pgrx::bgworkers::{BackgroundWorker, BackgroundWorkerBuilder, SignalWakeFlags};
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use pgrx::spi::quote_literal;
+use std::time::Duration;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Saves the rows `statement` is about to modify into `strict.recycle_bin`
+/// when `pg_strict.recycle_bin` is on. Failing to capture never stops the
+/// statement; a warning says the rows were not saved.
+pub fn capture(operation: Operation, relid: pg_sys::Oid, statement: &str) {
+    if !recycle_bin_enabled() || !extension_installed() {
+        return;
+    }
+
+    let select = QueryAnalyzer::new(statement).ok().and_then(|analyzer| {
+        let target = analyzer.single_target()?;
+        analyzer::affected_rows_select(statement, target).ok()
+    });
+    let Some(select) = select else {
+        pgrx::warning!(
+            "pg_strict: could not save the rows of this {} to the recycle bin.",
+            operation.as_str()
+        );
+        return;
+    };
+
+    // Built as a literal query: the statement's own $n parameters, if any,
+    // cannot be bound here, and such statements are reported as not saved.
+    let max_rows = recycle_bin_max_rows();
+    let sql = format!(
+        "SELECT strict.capture_pre_images({}, {}, {}, {}, \
+             ARRAY(SELECT pg_catalog.to_jsonb(src) FROM ({}) AS src LIMIT {}))",
+        quote_literal(operation.as_str()),
+        relid.as_u32(),
+        quote_literal(&loggable_statement(statement)),
+        max_rows,
+        select,
+        max_rows as i64 + 1
+    );
+
+    match subxact::try_in_subtransaction(|| Spi::get_one::<i64>(&sql).ok()) {
+        Some(Some(bin_id)) => pgrx::notice!(
+            "pg_strict: saved the rows this {} modifies in recycle bin {}.",
+            operation.as_str(),
+            bin_id
+        ),
+        Some(None) => pgrx::warning!(
+            "pg_strict: this {} modifies more than {} rows (pg_strict.recycle_bin_max_rows), so they were not saved to the recycle bin.",
+            operation.as_str(),
+            max_rows
+        ),
+        None => pgrx::warning!(
+            "pg_strict: could not save the rows of this {} to the recycle bin.",
+            operation.as_str()
+        ),
+    }
+}
+
+/// Starts the retention worker when pg_strict is preloaded with
+/// `pg_strict.recycle_bin_retention` set.
+pub fn register_worker() {
+    if !shmem::available() || recycle_bin_retention() == 0 {
+        return;
+    }
+
+    BackgroundWorkerBuilder::new("pg_strict recycle bin")
+        .set_type("pg_strict recycle bin")
+        .set_function("pg_strict_recycle_bin_main")
+        .set_library("pg_strict")
+        .enable_spi_access()
+        .set_restart_time(Some(Duration::from_secs(10)))
+        .load();
+}
+
+#[pg_guard]
+#[unsafe(no_mangle)]
+pub extern "C-unwind" fn pg_strict_recycle_bin_main(_arg: pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+    BackgroundWorker::connect_worker_to_spi(Some(&recycle_bin_database()), None);
+
+    while BackgroundWorker::wait_latch(Some(PURGE_INTERVAL)) {
+        if BackgroundWorker::sighup_received() {
+            unsafe { pg_sys::ProcessConfigFile(pg_sys::GucContext::PGC_SIGHUP) };
+        }
+
+        let retention = recycle_bin_retention();
+        if retention == 0 {
+            continue;
+        }
+        BackgroundWorker::transaction(|| purge_expired(retention));
+    }
+}
+
+fn purge_expired(retention_seconds: i32) {
+    if !extension_installed() {
+        return;
+    }
+
+    let purged = Spi::get_three_with_args::<i64, i64, i64>(
+        "SELECT bins, row_count, bytes FROM strict.purge_recycle_bin($1)",
+        &[retention_seconds.into()],
+    );
+    match purged {
+        Ok((Some(bins), rows, bytes)) if bins > 0 => pgrx::log!(
+            "pg_strict: recycle bin retention purged {} bins ({} rows, {} kB reclaimed)",
+            bins,
+            rows.unwrap_or(0),
+            bytes.unwrap_or(0) / 1024
+        ),
+        Ok(_) => {}
+        Err(err) => pgrx::warning!("pg_strict: could not purge the recycle bin: {err}"),
+    }
+}
@@ -0,0 +1,470 @@
+use crate::analyzer::Operation;
+use crate::catalog;
+use crate::guc::{StrictMode, max_warnings_per_minute, message_template};
+use crate::messages;
+use crate::sqlstate;
+use pgrx::pg_sys::panic::ErrorReport;
+use pgrx::{PgLogLevel, PgSqlErrorCode, function_name, pg_sys};
+use std::time::Instant;
+
+/// SQLSTATE carried by every pg_strict rejection and warning (2F003,
+/// prohibited_sql_statement_attempted), so clients can tell them apart from
+/// other errors without matching message text. Blocks reach the client with
+/// `pg_strict.error_code` instead when it is set.
+pub const VIOLATION_SQLSTATE: PgSqlErrorCode =
+    PgSqlErrorCode::ERRCODE_S_R_E_PROHIBITED_SQL_STATEMENT_ATTEMPTED;
+
+/// A violation as reported to the client.
+#[derive(Default)]
+pub struct Violation {
+    pub message: String,
+    pub detail: String,
+    pub hint: String,
+    // What `pg_strict.message_template` can refer to.
+    pub operation: Option<String>,
+    pub table: Option<String>,
+    pub rows_estimated: Option<f64>,
+}
+
+impl Violation {
+    pub fn missing_where(operation: Operation, table: Option<&str>) -> Self {
+        let op = operation.as_str();
+        let detail = match table {
+            Some(table) => messages::text(
+                "missing_where.detail_table",
+                &[("operation", op), ("table", table)],
+            ),
+            None => messages::text("missing_where.detail", &[("operation", op)]),
+        };
+        Self {
+            message: messages::text("missing_where.message", &[("operation", op)]),
+            detail,
+            hint: messages::lookup(match operation {
+                Operation::Update => "missing_where.hint_update",
+                Operation::Delete => "missing_where.hint_delete",
+            })
+            .to_string(),
+            operation: Some(op.to_string()),
+            table: table.map(str::to_string),
+            ..Self::default()
+        }
+    }
+
+    pub fn catalog_modification(command: &str, table: &str) -> Self {
+        let args = [("command", command), ("table", table)];
+        Self {
+            message: messages::text("catalog_modification.message", &args),
+            detail: messages::text("catalog_modification.detail", &args),
+            hint: messages::lookup("catalog_modification.hint").to_string(),
+            operation: Some(command.to_string()),
+            table: Some(table.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn truncate(tables: &str) -> Self {
+        let args = [("tables", tables)];
+        Self {
+            message: messages::text("truncate.message", &args),
+            detail: messages::text("truncate.detail", &args),
+            hint: messages::lookup("truncate.hint").to_string(),
+            operation: Some("TRUNCATE".to_string()),
+            table: Some(tables.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn function_body(function: &str, statements: &str) -> Self {
+        Self {
+            message: messages::text("function_body.message", &[("function", function)]),
+            detail: messages::text("function_body.detail", &[("statements", statements)]),
+            hint: messages::lookup("function_body.hint").to_string(),
+            operation: Some("CREATE FUNCTION".to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// A violation found by a check registered in C; see `plugins`.
+    pub fn custom(check: &str, message: &str, setting: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            detail: messages::text("custom.detail", &[("check", check)]),
+            hint: messages::text("custom.hint", &[("setting", setting)]),
+            operation: Some(check.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// A statement matched by a row of strict.custom_rules.
+    pub fn custom_rule(id: i64, message: &str, pattern: &str) -> Self {
+        let id = id.to_string();
+        Self {
+            message: format!("pg_strict: {}", message),
+            detail: messages::text("custom_rule.detail", &[("id", &id), ("pattern", pattern)]),
+            hint: messages::lookup("custom_rule.hint").to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn too_selective(
+        operation: Operation,
+        table: &str,
+        rows: f64,
+        reltuples: f64,
+        percent: f64,
+        threshold: f64,
+    ) -> Self {
+        let op = operation.as_str();
+        Self {
+            message: messages::text(
+                "too_selective.message",
+                &[
+                    ("operation", op),
+                    ("table", table),
+                    ("percent", format!("{percent:.0}").as_str()),
+                ],
+            ),
+            detail: messages::text(
+                "too_selective.detail",
+                &[
+                    ("rows", format!("{rows:.0}").as_str()),
+                    ("reltuples", format!("{reltuples:.0}").as_str()),
+                    ("threshold", threshold.to_string().as_str()),
+                ],
+            ),
+            hint: messages::lookup(match operation {
+                Operation::Update => "too_selective.hint_update",
+                Operation::Delete => "too_selective.hint_delete",
+            })
+            .to_string(),
+            operation: Some(op.to_string()),
+            table: Some(table.to_string()),
+            rows_estimated: Some(rows),
+        }
+    }
+
+    pub fn costly_statement(command: &str, cost: f64, threshold: f64) -> Self {
+        Self {
+            message: messages::text(
+                "costly_statement.message",
+                &[
+                    ("command", command),
+                    ("cost", format!("{cost:.0}").as_str()),
+                ],
+            ),
+            detail: messages::text(
+                "costly_statement.detail",
+                &[("threshold", threshold.to_string().as_str())],
+            ),
+            hint: messages::lookup("costly_statement.hint").to_string(),
+            operation: Some(command.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn sequential_scan(operation: Operation, table: &str, size_kb: i64) -> Self {
+        let op = operation.as_str();
+        Self {
+            message: messages::text(
+                "sequential_scan.message",
+                &[
+                    ("operation", op),
+                    ("table", table),
+                    ("size_kb", size_kb.to_string().as_str()),
+                ],
+            ),
+            detail: messages::lookup("sequential_scan.detail").to_string(),
+            hint: messages::lookup("sequential_scan.hint").to_string(),
+            operation: Some(op.to_string()),
+            table: Some(table.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn outside_transaction(operation: Operation) -> Self {
+        let op = operation.as_str();
+        Self {
+            message: messages::text("outside_transaction.message", &[("operation", op)]),
+            detail: messages::lookup("outside_transaction.detail").to_string(),
+            hint: messages::lookup("outside_transaction.hint").to_string(),
+            operation: Some(op.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn unbounded_timeouts(operation: Operation, problems: &[String]) -> Self {
+        let op = operation.as_str();
+        Self {
+            message: messages::text("unbounded_timeouts.message", &[("operation", op)]),
+            detail: messages::text(
+                "unbounded_timeouts.detail",
+                &[("problems", problems.join(" and ").as_str())],
+            ),
+            hint: messages::lookup("unbounded_timeouts.hint").to_string(),
+            operation: Some(op.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn stale_backup(operation: Operation, table: &str, detail: String) -> Self {
+        let op = operation.as_str();
+        Self {
+            message: messages::text(
+                "stale_backup.message",
+                &[("operation", op), ("table", table)],
+            ),
+            detail,
+            hint: messages::lookup("stale_backup.hint").to_string(),
+            operation: Some(op.to_string()),
+            table: Some(table.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn row_limit_exceeded(operation: Operation, limit: u64) -> Self {
+        let op = operation.as_str();
+        let limit = limit.to_string();
+        Self {
+            message: messages::text(
+                "row_limit_exceeded.message",
+                &[("operation", op), ("limit", limit.as_str())],
+            ),
+            detail: messages::text("row_limit_exceeded.detail", &[("limit", limit.as_str())]),
+            hint: messages::lookup(match operation {
+                Operation::Update => "row_limit_exceeded.hint_update",
+                Operation::Delete => "row_limit_exceeded.hint_delete",
+            })
+            .to_string(),
+            operation: Some(op.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the row estimate `%rows_estimated%` stands for in
+    /// `pg_strict.message_template`.
+    pub fn with_rows_estimated(mut self, rows: Option<f64>) -> Self {
+        self.rows_estimated = rows;
+        self
+    }
+
+    /// Replaces the generic hint of a missing WHERE clause with one worked out
+    /// from the target table: the primary key for an UPDATE, TRUNCATE or the
+    /// batched-delete helper for a DELETE, and the estimated row count.
+    pub fn with_table_hint(mut self, operation: Operation, relid: pg_sys::Oid) -> Self {
+        let table = catalog::quoted_relation_name(relid);
+        // The batches hint also names the table inside a string literal.
+        let table_literal = table.replace('\'', "''");
+        let suggestion = match operation {
+            Operation::Update => {
+                let key = catalog::primary_key_columns(relid);
+                (!key.is_empty()).then(|| {
+                    let condition = key
+                        .iter()
+                        .map(|column| format!("{column} = ..."))
+                        .collect::<Vec<_>>()
+                        .join(" AND ");
+                    messages::text(
+                        "missing_where.hint_update_key",
+                        &[("table", &table), ("condition", &condition)],
+                    )
+                })
+            }
+            Operation::Delete if catalog::is_referenced(relid) => Some(messages::text(
+                "missing_where.hint_delete_batches",
+                &[("table", &table), ("table_literal", &table_literal)],
+            )),
+            Operation::Delete => Some(messages::text(
+                "missing_where.hint_delete_truncate",
+                &[("table", &table), ("table_literal", &table_literal)],
+            )),
+        };
+        if let Some(suggestion) = suggestion {
+            self.hint = suggestion;
+        }
+        if let Some(rows) = self.rows_estimated {
+            let rows = group_digits(rows.max(0.0).round() as u64);
+            self.hint = format!(
+                "{} {}",
+                self.hint,
+                messages::text("missing_where.hint_rows", &[("rows", &rows)])
+            );
+        }
+        self
+    }
+
+    /// Adds the tables a DELETE reaches through foreign key actions.
+    pub fn with_cascades(mut self, cascades: &str) -> Self {
+        self.detail = format!(
+            "{} {}",
+            self.detail,
+            messages::text("report.cascades", &[("cascades", cascades)])
+        );
+        self
+    }
+
+    /// Adds the row-level triggers that will fire for every affected row.
+    pub fn with_triggers(mut self, triggers: &str) -> Self {
+        self.detail = format!(
+            "{} {}",
+            self.detail,
+            messages::text("report.triggers", &[("triggers", triggers)])
+        );
+        self
+    }
+
+    /// Names the PL/pgSQL statement that ran the dynamic SQL being reported.
+    pub fn with_origin(mut self, origin: Option<String>) -> Self {
+        if let Some(origin) = origin {
+            self.message = format!(
+                "{} {}",
+                self.message,
+                messages::text("report.origin", &[("origin", &origin)])
+            );
+        }
+        self
+    }
+
+    /// Adds the statement's query identifier, for joining against
+    /// pg_stat_statements. It is 0 when compute_query_id is off.
+    pub fn with_query_id(mut self, query_id: i64) -> Self {
+        if query_id != 0 {
+            let query_id = query_id.to_string();
+            self.detail = format!(
+                "{} {}",
+                self.detail,
+                messages::text("report.query_id", &[("query_id", &query_id)])
+            )
+            .trim_start()
+            .to_string();
+        }
+        self
+    }
+
+    /// Raises the violation as an ERROR in `on` mode, a WARNING in `warn` mode,
+    /// a NOTICE in `notice` mode and a server log message in `log` mode.
+    /// Reports beyond `pg_strict.max_warnings_per_minute` are suppressed.
+    pub fn raise(mut self, mode: StrictMode) {
+        let level = match mode {
+            StrictMode::On => return self.report(PgLogLevel::ERROR),
+            StrictMode::Warn => PgLogLevel::WARNING,
+            StrictMode::Notice => PgLogLevel::NOTICE,
+            StrictMode::Log => PgLogLevel::LOG_SERVER_ONLY,
+            StrictMode::Off | StrictMode::Learn => return,
+        };
+        match take_warning_token() {
+            Some(0) => {}
+            Some(suppressed) => {
+                let count = suppressed.to_string();
+                self.detail = format!(
+                    "{} {}",
+                    self.detail,
+                    messages::text("report.suppressed", &[("count", &count)])
+                );
+            }
+            None => return,
+        }
+        self.report(level);
+    }
+
+    fn report(self, level: PgLogLevel) {
+        let message = match message_template() {
+            Some(template) => self.render(&template),
+            None => self.message,
+        };
+        if matches!(level, PgLogLevel::ERROR) {
+            sqlstate::expect_block(&message);
+        }
+        let mut report =
+            ErrorReport::new(VIOLATION_SQLSTATE, message, function_name!()).set_detail(self.detail);
+        // Checks registered by other extensions may come without a hint.
+        if !self.hint.is_empty() {
+            report = report.set_hint(self.hint);
+        }
+        report.report(level);
+    }
+
+    /// Fills in `pg_strict.message_template`. `%message%` goes last so text in
+    /// the built-in message is never taken for a placeholder.
+    pub fn render(&self, template: &str) -> String {
+        let rows = self
+            .rows_estimated
+            .map(|rows| group_digits(rows.max(0.0).round() as u64))
+            .unwrap_or_else(|| "unknown".to_string());
+        template
+            .replace(
+                "%operation%",
+                self.operation.as_deref().unwrap_or("statement"),
+            )
+            .replace("%table%", self.table.as_deref().unwrap_or("unknown table"))
+            .replace("%rows_estimated%", &rows)
+            .replace("%hint%", &self.hint)
+            .replace("%message%", &self.message)
+    }
+}
+
+struct WarningBucket {
+    tokens: f64,
+    refilled_at: Instant,
+    suppressed: u64,
+}
+
+// Per-backend token bucket throttling violation warnings.
+static mut WARNING_BUCKET: Option<WarningBucket> = None;
+
+/// Takes a token for a warning. Returns the number of warnings suppressed since
+/// the last one emitted, or `None` when this warning must be suppressed too.
+#[allow(static_mut_refs)]
+fn take_warning_token() -> Option<u64> {
+    let limit = max_warnings_per_minute();
+    if limit <= 0 {
+        return Some(0);
+    }
+
+    let capacity = limit as f64;
+    let now = Instant::now();
+    let bucket = unsafe {
+        WARNING_BUCKET.get_or_insert(WarningBucket {
+            tokens: capacity,
+            refilled_at: now,
+            suppressed: 0,
+        })
+    };
+
+    let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * capacity / 60.0).min(capacity);
+    bucket.refilled_at = now;
+
+    if bucket.tokens < 1.0 {
+        bucket.suppressed += 1;
+        return None;
+    }
+    bucket.tokens -= 1.0;
+    Some(std::mem::take(&mut bucket.suppressed))
+}
+
+/// Formats `n` with thousands separators, as in `1,204,543`.
+pub fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+    grouped
+}
+
+/// Describes why a timeout setting of `value_ms` does not bound a statement,
+/// given the largest accepted value `max_ms` (0 for no maximum).
+pub fn timeout_problem(name: &str, value_ms: i32, max_ms: i32) -> Option<String> {
+    if value_ms <= 0 {
+        Some(format!("{name} is disabled"))
+    } else if max_ms > 0 && value_ms > max_ms {
+        Some(format!(
+            "{name} is {value_ms} ms, above pg_strict.max_{name} ({max_ms} ms)"
+        ))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,168 @@
+use crate::analyzer::Operation;
+use crate::exemptions;
+use crate::guc::StrictMode;
+use crate::report::Violation;
+use crate::rules;
+use crate::stats::{self, Counter};
+use pgrx::pg_guard;
+use pgrx::pg_sys;
+
+/// A ModifyTable input node whose ExecProcNode has been wrapped to count the
+/// rows it feeds into the UPDATE or DELETE.
+struct LimitedNode {
+    query_desc: usize,
+    node: usize,
+    original: pg_sys::ExecProcNodeMtd,
+    operation: Operation,
+    limit: u64,
+    rows: u64,
+}
+
+static mut LIMITED_NODES: Vec<LimitedNode> = Vec::new();
+
+/// Called after ExecutorStart. Installs the row counter on the plan feeding an
+/// UPDATE or DELETE when `pg_strict.max_affected_rows`, or a row of
+/// strict.rules for its target, sets a limit.
+pub unsafe fn install(query_desc: *mut pg_sys::QueryDesc, eflags: i32) {
+    if eflags & pg_sys::EXEC_FLAG_EXPLAIN_ONLY as i32 != 0 {
+        return;
+    }
+    let operation = match unsafe { (*query_desc).operation } {
+        pg_sys::CmdType::CMD_UPDATE => Operation::Update,
+        pg_sys::CmdType::CMD_DELETE => Operation::Delete,
+        _ => return,
+    };
+    if exemptions::statement_is_exempt() {
+        return;
+    }
+
+    let planstate = unsafe { (*query_desc).planstate };
+    if planstate.is_null() || unsafe { (*planstate).type_ } != pg_sys::NodeTag::T_ModifyTableState {
+        return;
+    }
+    let mtstate = planstate as *mut pg_sys::ModifyTableState;
+    let limit = rules::max_affected_rows(operation, unsafe { target_relation(mtstate) });
+    if limit <= 0 {
+        return;
+    }
+
+    for node in unsafe { modify_table_inputs(mtstate) } {
+        unsafe { wrap_node(query_desc, node, operation, limit as u64) };
+    }
+}
+
+/// Called from ExecutorEnd, and from the transaction callback with a null
+/// descriptor to drop everything left behind by an aborted statement.
+#[allow(static_mut_refs)]
+pub fn release(query_desc: *mut pg_sys::QueryDesc) {
+    unsafe {
+        if query_desc.is_null() {
+            LIMITED_NODES.clear();
+        } else {
+            LIMITED_NODES.retain(|entry| entry.query_desc != query_desc as usize);
+        }
+    }
+}
+
+unsafe fn target_relation(mtstate: *mut pg_sys::ModifyTableState) -> Option<pg_sys::Oid> {
+    let result_rel = unsafe { (*mtstate).resultRelInfo };
+    if result_rel.is_null() || unsafe { (*result_rel).ri_RelationDesc }.is_null() {
+        return None;
+    }
+    Some(unsafe { (*(*result_rel).ri_RelationDesc).rd_id })
+}
+
+#[cfg(feature = "pg13")]
+unsafe fn modify_table_inputs(
+    mtstate: *mut pg_sys::ModifyTableState,
+) -> Vec<*mut pg_sys::PlanState> {
+    let count = unsafe { (*mtstate).mt_nplans }.max(0) as usize;
+    let plans = unsafe { (*mtstate).mt_plans };
+    if plans.is_null() {
+        return Vec::new();
+    }
+    (0..count)
+        .map(|i| unsafe { *plans.add(i) })
+        .filter(|node| !node.is_null())
+        .collect()
+}
+
+#[cfg(not(feature = "pg13"))]
+unsafe fn modify_table_inputs(
+    mtstate: *mut pg_sys::ModifyTableState,
+) -> Vec<*mut pg_sys::PlanState> {
+    let outer = unsafe { (*mtstate).ps.lefttree };
+    if outer.is_null() {
+        Vec::new()
+    } else {
+        vec![outer]
+    }
+}
+
+#[allow(static_mut_refs)]
+unsafe fn wrap_node(
+    query_desc: *mut pg_sys::QueryDesc,
+    node: *mut pg_sys::PlanState,
+    operation: Operation,
+    limit: u64,
+) {
+    // ExecProcNodeFirst replaces ExecProcNode with ExecProcNodeReal on the first
+    // call, so the real entry point is the one to wrap.
+    let original = unsafe { (*node).ExecProcNodeReal };
+    if original.is_none() {
+        return;
+    }
+    unsafe {
+        // A stale entry for a node freed by an aborted statement may share the address.
+        LIMITED_NODES.retain(|entry| entry.node != node as usize);
+        LIMITED_NODES.push(LimitedNode {
+            query_desc: query_desc as usize,
+            node: node as usize,
+            original,
+            operation,
+            limit,
+            rows: 0,
+        });
+        (*node).ExecProcNodeReal = Some(counting_exec_proc_node);
+    }
+}
+
+#[pg_guard]
+#[allow(static_mut_refs)]
+unsafe extern "C-unwind" fn counting_exec_proc_node(
+    node: *mut pg_sys::PlanState,
+) -> *mut pg_sys::TupleTableSlot {
+    let Some(index) = (unsafe { LIMITED_NODES.iter().position(|e| e.node == node as usize) })
+    else {
+        pgrx::error!("pg_strict: lost track of a row-limited plan node");
+    };
+    let original = unsafe { LIMITED_NODES[index].original };
+    let slot = match original {
+        Some(exec) => unsafe { exec(node) },
+        None => std::ptr::null_mut(),
+    };
+    if slot_is_empty(slot) {
+        return slot;
+    }
+
+    // Functions evaluated by the plan may have run DML of their own and moved
+    // the entry, so look it up again.
+    let exceeded = unsafe {
+        LIMITED_NODES
+            .iter_mut()
+            .find(|entry| entry.node == node as usize)
+            .and_then(|entry| {
+                entry.rows += 1;
+                (entry.rows > entry.limit).then_some((entry.operation, entry.limit))
+            })
+    };
+    if let Some((operation, limit)) = exceeded {
+        stats::increment(Counter::ViolationsBlocked);
+        Violation::row_limit_exceeded(operation, limit).raise(StrictMode::On);
+    }
+    slot
+}
+
+fn slot_is_empty(slot: *mut pg_sys::TupleTableSlot) -> bool {
+    slot.is_null() || unsafe { (*slot).tts_flags as u32 & pg_sys::TTS_FLAG_EMPTY != 0 }
+}
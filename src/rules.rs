@@ -0,0 +1,328 @@
+use crate::analyzer::Operation;
+use crate::catalog;
+use crate::custom_rules;
+use crate::exemptions::{current_application_name, glob_match};
+use crate::guc::StrictMode;
+use crate::policy;
+use crate::shmem;
+use pgrx::prelude::*;
+use std::ffi::CStr;
+use std::sync::atomic::Ordering;
+
+/// A row of `strict.rules`. Unset columns match anything or, for the mode and
+/// thresholds, leave the configured setting in place.
+#[derive(Clone)]
+pub struct Rule {
+    pub id: i64,
+    pub target: String,
+    pub operation: Option<Operation>,
+    pub mode: Option<StrictMode>,
+    pub max_affected_rows: Option<i64>,
+    pub max_affected_percent: Option<f64>,
+    role: Option<String>,
+    application: Option<String>,
+    valid_from: Option<i64>,
+    valid_until: Option<i64>,
+}
+
+impl Rule {
+    /// How specific the rule is, higher first: the target (a table name, a
+    /// pattern, `*`), then whether it is for one operation.
+    fn precedence(&self) -> (u8, bool) {
+        let target = if self.target == "*" {
+            0
+        } else if self.target.contains(['*', '?']) {
+            1
+        } else {
+            2
+        };
+        (target, self.operation.is_some())
+    }
+}
+
+// Rules as last loaded by this backend, with the shared generation counter
+// they were loaded at.
+static mut CACHE: Option<(u64, Vec<Rule>)> = None;
+// Set while the rules are being read, so the SPI query does not look them up again.
+static mut LOADING: bool = false;
+// Set when this transaction changed strict.rules or strict.custom_rules; the
+// counter is bumped at commit.
+static mut CHANGED: bool = false;
+// strict.rules as of the last load. Without shared memory there is no
+// counter, so a change instead invalidates this relation's relcache entry in
+// every backend, and the callback drops the cached rules.
+static mut RULES_RELID: pg_sys::Oid = pg_sys::InvalidOid;
+
+// inval.h is not among pgrx's bindings.
+#[pg_guard]
+unsafe extern "C-unwind" {
+    fn CacheInvalidateRelcacheByRelid(relid: pg_sys::Oid);
+    fn CacheRegisterRelcacheCallback(
+        func: unsafe extern "C-unwind" fn(arg: pg_sys::Datum, relid: pg_sys::Oid),
+        arg: pg_sys::Datum,
+    );
+}
+
+// Timestamps are compared in PostgreSQL's microseconds since 2000-01-01.
+const LOAD_SQL: &str = "SELECT id, target, operation, mode, max_affected_rows, max_affected_percent, \
+     role_name, application_name, \
+     (extract(epoch FROM valid_from - timestamptz '2000-01-01 00:00:00+00') * 1000000)::int8, \
+     (extract(epoch FROM valid_until - timestamptz '2000-01-01 00:00:00+00') * 1000000)::int8 \
+     FROM strict.rules ORDER BY id";
+
+/// The rules that apply to `operation` on `relid` for the current role and
+/// application at this moment, in order of precedence: a rule naming the
+/// table outranks a pattern, which outranks `*`; among those, a rule for the
+/// operation outranks one for every operation; ties go to the lower id.
+pub fn matching(operation: Operation, relid: Option<pg_sys::Oid>) -> Vec<Rule> {
+    let rules = applicable();
+    if rules.is_empty() {
+        return rules;
+    }
+    let table = relid.map(catalog::qualified_relation_name);
+
+    let mut rules: Vec<Rule> = rules
+        .into_iter()
+        .filter(|rule| {
+            rule.operation.is_none_or(|op| op == operation)
+                && (rule.target == "*"
+                    || table
+                        .as_deref()
+                        .is_some_and(|table| glob_match(&rule.target, table)))
+        })
+        .collect();
+    // Stable, so the id order of applicable() breaks ties.
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.precedence()));
+    rules
+}
+
+/// The mode set by the rule of highest precedence that sets one, with its id.
+pub fn mode(operation: Operation, relid: Option<pg_sys::Oid>) -> Option<(i64, StrictMode)> {
+    matching(operation, relid)
+        .into_iter()
+        .find_map(|rule| rule.mode.map(|mode| (rule.id, mode)))
+}
+
+/// The rules that apply to the current role and application at this moment,
+/// whatever their table and operation, in id order.
+pub fn applicable() -> Vec<Rule> {
+    let rules = current_rules();
+    if rules.is_empty() {
+        return rules;
+    }
+    let role = current_role_name().unwrap_or_default();
+    let application = current_application_name().unwrap_or_default();
+    let now = unsafe { pg_sys::GetCurrentTimestamp() };
+
+    rules
+        .into_iter()
+        .filter(|rule| {
+            rule.role
+                .as_deref()
+                .is_none_or(|pattern| glob_match(pattern, &role))
+                && rule
+                    .application
+                    .as_deref()
+                    .is_none_or(|pattern| glob_match(pattern, &application))
+                && rule.valid_from.is_none_or(|from| now >= from)
+                && rule.valid_until.is_none_or(|until| now < until)
+        })
+        .collect()
+}
+
+/// `pg_strict.max_affected_rows`, or the threshold of the matching rule of
+/// highest precedence that sets one.
+pub fn max_affected_rows(operation: Operation, relid: Option<pg_sys::Oid>) -> i64 {
+    matching(operation, relid)
+        .into_iter()
+        .find_map(|rule| rule.max_affected_rows)
+        .unwrap_or_else(|| policy::effective_max_affected_rows() as i64)
+}
+
+/// `pg_strict.max_affected_percent`, or the threshold of the matching rule of
+/// highest precedence that sets one.
+pub fn max_affected_percent(operation: Operation, relid: Option<pg_sys::Oid>) -> f64 {
+    matching(operation, relid)
+        .into_iter()
+        .find_map(|rule| rule.max_affected_percent)
+        .unwrap_or_else(policy::effective_max_affected_percent)
+}
+
+/// Called when strict.rules or strict.custom_rules changes: this backend
+/// rereads the tables for the rest of the transaction, the others reload once
+/// the change commits.
+pub fn invalidate() {
+    unsafe {
+        CACHE = None;
+        CHANGED = true;
+    }
+    custom_rules::invalidate();
+    if !shmem::available() {
+        let relid = rules_relid();
+        if relid != pg_sys::InvalidOid {
+            // Queued with the transaction, so other backends only see it
+            // once the change commits, and never if it rolls back.
+            unsafe { CacheInvalidateRelcacheByRelid(relid) };
+        }
+    }
+}
+
+/// Drops the cached rules when strict.rules is invalidated, which is how
+/// changes reach backends without shared memory. Called once from `_PG_init`.
+pub fn register_invalidation_callback() {
+    unsafe { CacheRegisterRelcacheCallback(rules_relcache_callback, pg_sys::Datum::from(0)) };
+}
+
+#[pg_guard]
+unsafe extern "C-unwind" fn rules_relcache_callback(_arg: pg_sys::Datum, relid: pg_sys::Oid) {
+    // InvalidOid means every relation, as after a lost invalidation message.
+    if relid == pg_sys::InvalidOid || relid == unsafe { RULES_RELID } {
+        unsafe { CACHE = None };
+        custom_rules::invalidate();
+    }
+}
+
+fn rules_relid() -> pg_sys::Oid {
+    unsafe {
+        let namespace = pg_sys::get_namespace_oid(c"strict".as_ptr(), true);
+        if namespace == pg_sys::InvalidOid {
+            return pg_sys::InvalidOid;
+        }
+        pg_sys::get_relname_relid(c"rules".as_ptr(), namespace)
+    }
+}
+
+/// Publishes a committed change to every backend.
+pub fn at_commit() {
+    if unsafe { CHANGED } && shmem::available() {
+        shmem::RULES_GENERATION.get().fetch_add(1, Ordering::AcqRel);
+    }
+    end_transaction();
+}
+
+/// An aborted change has nothing to publish.
+pub fn at_abort() {
+    end_transaction();
+}
+
+fn end_transaction() {
+    unsafe { CHANGED = false };
+}
+
+/// Whether this transaction changed strict.rules or strict.custom_rules.
+pub fn changed_in_transaction() -> bool {
+    unsafe { CHANGED }
+}
+
+#[allow(static_mut_refs)]
+fn current_rules() -> Vec<Rule> {
+    if unsafe { LOADING } {
+        return Vec::new();
+    }
+    // Uncommitted changes may still be rolled back to a savepoint, so they
+    // are read afresh rather than cached.
+    if unsafe { CHANGED } {
+        let _loading = LoadingGuard::enter();
+        return load();
+    }
+    let generation = if shmem::available() {
+        shmem::RULES_GENERATION.get().load(Ordering::Acquire)
+    } else {
+        0
+    };
+    unsafe {
+        if let Some((loaded_at, rules)) = CACHE.as_ref() {
+            if *loaded_at == generation {
+                return rules.clone();
+            }
+        }
+    }
+    let rules = {
+        let _loading = LoadingGuard::enter();
+        load()
+    };
+    track_relation();
+    unsafe { CACHE = Some((generation, rules.clone())) };
+    rules
+}
+
+/// Notes which relation holds the rules, so that invalidating it also drops
+/// rules cached by `custom_rules`.
+pub fn track_relation() {
+    unsafe { RULES_RELID = rules_relid() };
+}
+
+struct LoadingGuard;
+
+impl LoadingGuard {
+    fn enter() -> Self {
+        unsafe { LOADING = true };
+        LoadingGuard
+    }
+}
+
+impl Drop for LoadingGuard {
+    fn drop(&mut self) {
+        unsafe { LOADING = false };
+    }
+}
+
+fn load() -> Vec<Rule> {
+    let exists = Spi::get_one::<bool>("SELECT to_regclass('strict.rules') IS NOT NULL")
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+    if !exists {
+        return Vec::new();
+    }
+
+    Spi::connect(|client| {
+        let mut rules = Vec::new();
+        for row in client.select(LOAD_SQL, None, &[])? {
+            let operation = match row.get::<String>(3)?.as_deref() {
+                Some("update") => Some(Operation::Update),
+                Some("delete") => Some(Operation::Delete),
+                _ => None,
+            };
+            rules.push(Rule {
+                id: row.get::<i64>(1)?.unwrap_or_default(),
+                target: row.get::<String>(2)?.unwrap_or_else(|| "*".to_string()),
+                operation,
+                mode: row.get::<String>(4)?.as_deref().and_then(parse_mode),
+                max_affected_rows: row.get::<i64>(5)?,
+                max_affected_percent: row.get::<f64>(6)?,
+                role: row.get::<String>(7)?,
+                application: row.get::<String>(8)?,
+                valid_from: row.get::<i64>(9)?,
+                valid_until: row.get::<i64>(10)?,
+            });
+        }
+        Ok::<_, pgrx::spi::Error>(rules)
+    })
+    .unwrap_or_else(|error| {
+        pgrx::warning!("pg_strict: could not load strict.rules: {}", error);
+        Vec::new()
+    })
+}
+
+pub fn parse_mode(mode: &str) -> Option<StrictMode> {
+    match mode {
+        "off" => Some(StrictMode::Off),
+        "warn" => Some(StrictMode::Warn),
+        "on" => Some(StrictMode::On),
+        "learn" => Some(StrictMode::Learn),
+        "notice" => Some(StrictMode::Notice),
+        "log" => Some(StrictMode::Log),
+        _ => None,
+    }
+}
+
+fn current_role_name() -> Option<String> {
+    unsafe {
+        let name = pg_sys::GetUserNameFromId(pg_sys::GetUserId(), true);
+        if name.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+    }
+}
@@ -0,0 +1,131 @@
+use pgrx::pg_sys;
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A weekly window such as `sat 02:00-04:00` or `mon-fri 22:00-01:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    days: [bool; 7],
+    start_minute: u16,
+    end_minute: u16,
+}
+
+impl Window {
+    /// `dow` is 0 for Sunday; `minute` counts from local midnight.
+    pub fn contains(&self, dow: usize, minute: u16) -> bool {
+        if !self.days[dow % 7] {
+            return false;
+        }
+        if self.start_minute <= self.end_minute {
+            minute >= self.start_minute && minute < self.end_minute
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+}
+
+pub fn parse_window(spec: &str) -> Result<Window, String> {
+    let mut parts = spec.split_whitespace();
+    let (days, times) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(days), Some(times), None) => (days, times),
+        (Some(times), None, None) => ("*", times),
+        _ => return Err(format!("expected \"<days> HH:MM-HH:MM\", got \"{spec}\"")),
+    };
+
+    let (start, end) = times
+        .split_once('-')
+        .ok_or_else(|| format!("expected a time range HH:MM-HH:MM, got \"{times}\""))?;
+
+    Ok(Window {
+        days: parse_days(days)?,
+        start_minute: parse_time(start)?,
+        end_minute: parse_time(end)?,
+    })
+}
+
+fn parse_days(days: &str) -> Result<[bool; 7], String> {
+    let days = days.to_ascii_lowercase();
+    if days == "*" {
+        return Ok([true; 7]);
+    }
+
+    let mut selected = [false; 7];
+    match days.split_once('-') {
+        Some((first, last)) => {
+            let (first, last) = (parse_day(first)?, parse_day(last)?);
+            let mut day = first;
+            loop {
+                selected[day] = true;
+                if day == last {
+                    break;
+                }
+                day = (day + 1) % 7;
+            }
+        }
+        None => selected[parse_day(&days)?] = true,
+    }
+    Ok(selected)
+}
+
+fn parse_day(day: &str) -> Result<usize, String> {
+    let prefix = day.get(..3).unwrap_or(day);
+    DAY_NAMES
+        .iter()
+        .position(|name| *name == prefix)
+        .ok_or_else(|| format!("unknown day \"{day}\""))
+}
+
+fn parse_time(time: &str) -> Result<u16, String> {
+    let (hours, minutes) = time
+        .split_once(':')
+        .ok_or_else(|| format!("expected HH:MM, got \"{time}\""))?;
+    let hours: u16 = hours
+        .parse()
+        .map_err(|_| format!("invalid hour in \"{time}\""))?;
+    let minutes: u16 = minutes
+        .parse()
+        .map_err(|_| format!("invalid minute in \"{time}\""))?;
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes != 0) {
+        return Err(format!("time out of range: \"{time}\""));
+    }
+    Ok(hours * 60 + minutes)
+}
+
+/// Current day of week (0 = Sunday) and minute of day in the session time zone.
+pub fn local_now() -> Option<(usize, u16)> {
+    unsafe {
+        let now = pg_sys::GetCurrentTimestamp();
+        let mut tm: pg_sys::pg_tm = std::mem::zeroed();
+        let mut fsec: pg_sys::fsec_t = 0;
+        let mut tz: i32 = 0;
+        if pg_sys::timestamp2tm(
+            now,
+            &mut tz,
+            &mut tm,
+            &mut fsec,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        ) != 0
+        {
+            return None;
+        }
+        let dow = pg_sys::j2day(pg_sys::date2j(tm.tm_year, tm.tm_mon, tm.tm_mday));
+        Some((dow as usize, (tm.tm_hour * 60 + tm.tm_min) as u16))
+    }
+}
+
+/// The comma-separated windows in `spec`. Malformed entries, which the
+/// setting's check hook already rejects, are skipped.
+pub fn parse_windows(spec: &str) -> Vec<Window> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| parse_window(entry).ok())
+        .collect()
+}
+
+/// True when `now` falls inside any of `windows`.
+pub fn in_any_window(windows: &[Window], now: (usize, u16)) -> bool {
+    let (dow, minute) = now;
+    windows.iter().any(|window| window.contains(dow, minute))
+}
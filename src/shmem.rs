@@ -0,0 +1,192 @@
+use crate::delivery::DeliveryQueue;
+use crate::stats::{OffenderStats, TableStats};
+use crate::webhook::WebhookQueue;
+use pgrx::prelude::*;
+use pgrx::shmem::PGRXSharedMemory;
+use pgrx::{PgAtomic, PgLwLock, pg_shmem_init};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const MAX_ALLOWED_QUERYIDS: usize = 256;
+
+#[derive(Copy, Clone)]
+pub struct QueryIdAllowlist {
+    len: usize,
+    ids: [i64; MAX_ALLOWED_QUERYIDS],
+}
+
+impl Default for QueryIdAllowlist {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            ids: [0; MAX_ALLOWED_QUERYIDS],
+        }
+    }
+}
+
+unsafe impl PGRXSharedMemory for QueryIdAllowlist {}
+
+impl QueryIdAllowlist {
+    pub fn contains(&self, query_id: i64) -> bool {
+        self.ids[..self.len].contains(&query_id)
+    }
+
+    pub fn insert(&mut self, query_id: i64) -> bool {
+        if self.contains(query_id) {
+            return true;
+        }
+        if self.len == MAX_ALLOWED_QUERYIDS {
+            return false;
+        }
+        self.ids[self.len] = query_id;
+        self.len += 1;
+        true
+    }
+
+    pub fn remove(&mut self, query_id: i64) -> bool {
+        let Some(index) = self.ids[..self.len].iter().position(|id| *id == query_id) else {
+            return false;
+        };
+        self.ids[index] = self.ids[self.len - 1];
+        self.len -= 1;
+        true
+    }
+
+    pub fn ids(&self) -> Vec<i64> {
+        self.ids[..self.len].to_vec()
+    }
+}
+
+pub static QUERYID_ALLOWLIST: PgLwLock<QueryIdAllowlist> =
+    PgLwLock::new(c"pg_strict_queryid_allowlist");
+
+// Cluster-wide enforcement counters. Every checked statement bumps them, so
+// they are atomics rather than one locked structure.
+pub static STATEMENTS_CHECKED: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_statements_checked");
+pub static VIOLATIONS_WARNED: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_violations_warned");
+pub static VIOLATIONS_BLOCKED: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_violations_blocked");
+pub static PARSE_FAILURES: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_parse_failures");
+pub static BYPASSES: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_bypasses");
+pub static CHECK_TIME_US: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_check_time_us");
+
+pub static TABLE_STATS: PgLwLock<TableStats> = PgLwLock::new(c"pg_strict_table_stats");
+
+pub static OFFENDERS: PgLwLock<OffenderStats> = PgLwLock::new(c"pg_strict_offenders");
+
+pub static WEBHOOK_QUEUE: PgLwLock<WebhookQueue> = PgLwLock::new(c"pg_strict_webhook_queue");
+
+pub static DELIVERY_QUEUE: PgLwLock<DeliveryQueue> = PgLwLock::new(c"pg_strict_delivery_queue");
+
+/// Bumped whenever a change to strict.rules commits; each backend reloads its
+/// cached rules when the value differs from the one it loaded them at.
+pub static RULES_GENERATION: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_rules_generation");
+
+// Identifies the layout of the structures above, hashed from the size and
+// alignment of each one in the order `init` attaches them, so that changing,
+// adding or removing one changes the value by itself. A backend that loads a
+// different build of the library into a running server (on Windows every
+// backend loads it afresh) then refuses the shared memory instead of
+// misreading it. Reordering fields without changing a size goes unnoticed.
+const LAYOUT_MAGIC: u64 = 0x7067_7374_0000_0000; // "pgst"
+const LAYOUT_VERSION: u64 = layout_hash(&[
+    layout_of::<QueryIdAllowlist>(),
+    layout_of::<AtomicU64>(), // STATEMENTS_CHECKED
+    layout_of::<AtomicU64>(), // VIOLATIONS_WARNED
+    layout_of::<AtomicU64>(), // VIOLATIONS_BLOCKED
+    layout_of::<AtomicU64>(), // PARSE_FAILURES
+    layout_of::<AtomicU64>(), // BYPASSES
+    layout_of::<AtomicU64>(), // CHECK_TIME_US
+    layout_of::<TableStats>(),
+    layout_of::<OffenderStats>(),
+    layout_of::<WebhookQueue>(),
+    layout_of::<DeliveryQueue>(),
+    layout_of::<AtomicU64>(), // RULES_GENERATION
+]);
+static LAYOUT: PgAtomic<AtomicU64> = PgAtomic::new(c"pg_strict_shmem_layout");
+
+const fn layout_of<T>() -> (usize, usize) {
+    (size_of::<T>(), align_of::<T>())
+}
+
+// FNV-1a over the sizes and alignments, folded into the 32 bits below
+// LAYOUT_MAGIC.
+const fn layout_hash(layouts: &[(usize, usize)]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < layouts.len() {
+        let (size, align) = layouts[i];
+        hash = (hash ^ size as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        hash = (hash ^ align as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        i += 1;
+    }
+    (hash ^ (hash >> 32)) & 0xffff_ffff
+}
+
+static mut SHMEM_READY: bool = false;
+static mut PREV_SHMEM_STARTUP_HOOK: pg_sys::shmem_startup_hook_type = None;
+
+/// Requests shared memory when loaded via shared_preload_libraries. Backends
+/// forked afterwards inherit the ready flag; on Windows, where backends are
+/// started rather than forked, each one loads the preloaded libraries again
+/// and sets it itself.
+///
+/// `pg_shmem_init!` sizes each structure from `shmem_request_hook` on
+/// PostgreSQL 15 and later (directly from `_PG_init` before that) and attaches
+/// it in `shmem_startup_hook`, each `PgLwLock` with its own named LWLock
+/// tranche. The layout only changes with a new library, which needs a restart
+/// because pg_strict must be preloaded; `check_layout` disables shared memory
+/// in a backend that attaches to structures created by a different build.
+pub fn init() {
+    if !unsafe { pg_sys::process_shared_preload_libraries_in_progress } {
+        return;
+    }
+
+    pg_shmem_init!(QUERYID_ALLOWLIST);
+    pg_shmem_init!(STATEMENTS_CHECKED);
+    pg_shmem_init!(VIOLATIONS_WARNED);
+    pg_shmem_init!(VIOLATIONS_BLOCKED);
+    pg_shmem_init!(PARSE_FAILURES);
+    pg_shmem_init!(BYPASSES);
+    pg_shmem_init!(CHECK_TIME_US);
+    pg_shmem_init!(TABLE_STATS);
+    pg_shmem_init!(OFFENDERS);
+    pg_shmem_init!(WEBHOOK_QUEUE);
+    pg_shmem_init!(DELIVERY_QUEUE);
+    pg_shmem_init!(RULES_GENERATION);
+    pg_shmem_init!(LAYOUT = AtomicU64::new(LAYOUT_MAGIC | LAYOUT_VERSION));
+    unsafe {
+        PREV_SHMEM_STARTUP_HOOK = pg_sys::shmem_startup_hook;
+        pg_sys::shmem_startup_hook = Some(check_layout);
+        SHMEM_READY = true;
+    }
+}
+
+// Runs after the structures are created or attached to.
+#[pg_guard]
+unsafe extern "C-unwind" fn check_layout() {
+    unsafe {
+        if let Some(prev) = PREV_SHMEM_STARTUP_HOOK {
+            prev();
+        }
+    }
+    let found = LAYOUT.get().load(Ordering::Relaxed);
+    if found != LAYOUT_MAGIC | LAYOUT_VERSION {
+        unsafe { SHMEM_READY = false };
+        pgrx::warning!(
+            "pg_strict: shared memory was set up by a different pg_strict build (layout {:#x}, expected {:#x}); features that need it are disabled until PostgreSQL is restarted.",
+            found,
+            LAYOUT_MAGIC | LAYOUT_VERSION
+        );
+    }
+}
+
+pub fn available() -> bool {
+    unsafe { SHMEM_READY }
+}
+
+pub fn require() {
+    if !available() {
+        pgrx::error!(
+            "pg_strict: shared memory is not available. Add pg_strict to shared_preload_libraries and restart PostgreSQL."
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::guc;
+use pgrx::pg_sys;
+use std::ffi::CStr;
+
+// pgrx raises errors with the SQLSTATEs it knows about, so a site-chosen code
+// is put in place by the emit_log_hook, just before the error is reported.
+// This holds the message of the block that is to carry it.
+static mut PENDING: Option<String> = None;
+
+/// The integer form PostgreSQL keeps a SQLSTATE in (MAKE_SQLSTATE).
+pub fn encode(code: &str) -> Option<i32> {
+    let bytes = code.as_bytes();
+    if bytes.len() != 5 {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &ch)| (((ch as i32) - ('0' as i32)) & 0x3F) << (6 * i))
+            .sum(),
+    )
+}
+
+/// Called just before a block is raised with `message`.
+pub fn expect_block(message: &str) {
+    if guc::error_code().is_some() {
+        unsafe { PENDING = Some(message.to_string()) };
+    }
+}
+
+/// Gives the error being reported `pg_strict.error_code` when it is the block
+/// announced by `expect_block()`.
+#[allow(static_mut_refs)]
+pub unsafe fn rewrite(edata: *mut pg_sys::ErrorData) {
+    let data = unsafe { &mut *edata };
+    if data.elevel < pg_sys::ERROR as i32 || Some(data.sqlerrcode) != encode("2F003") {
+        return;
+    }
+    let Some(message) = (unsafe { PENDING.take() }) else {
+        return;
+    };
+    let Some(code) = guc::error_code().as_deref().and_then(encode) else {
+        return;
+    };
+    if !data.message.is_null()
+        && unsafe { CStr::from_ptr(data.message) }.to_string_lossy() == message
+    {
+        data.sqlerrcode = code;
+    }
+}
+
+/// Drops an announcement whose error was caught before it was reported.
+pub fn reset() {
+    unsafe { PENDING = None };
+}
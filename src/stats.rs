@@ -0,0 +1,350 @@
+use crate::analyzer::Operation;
+use crate::guc::StrictMode;
+use crate::shmem;
+use pgrx::pg_sys;
+use pgrx::shmem::PGRXSharedMemory;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// A snapshot of the cluster-wide enforcement counters.
+#[derive(Copy, Clone, Default)]
+pub struct StrictStats {
+    pub statements_checked: i64,
+    pub violations_warned: i64,
+    pub violations_blocked: i64,
+    pub parse_failures: i64,
+    pub bypasses: i64,
+    pub check_time_us: i64,
+}
+
+pub const MAX_TRACKED_TABLES: usize = 512;
+
+#[derive(Copy, Clone, Default)]
+pub struct TableCounters {
+    pub database: u32,
+    pub relid: u32,
+    pub update_violations: i64,
+    pub delete_violations: i64,
+    pub blocked: i64,
+}
+
+impl TableCounters {
+    fn total(&self) -> i64 {
+        self.update_violations + self.delete_violations
+    }
+}
+
+/// Violation counts per target relation. When the table is full, the least
+/// violated relation makes room for a new one.
+#[derive(Copy, Clone)]
+pub struct TableStats {
+    len: usize,
+    entries: [TableCounters; MAX_TRACKED_TABLES],
+}
+
+impl Default for TableStats {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            entries: [TableCounters::default(); MAX_TRACKED_TABLES],
+        }
+    }
+}
+
+unsafe impl PGRXSharedMemory for TableStats {}
+
+impl TableStats {
+    fn entry(&mut self, database: u32, relid: u32) -> &mut TableCounters {
+        let index = match self.entries[..self.len]
+            .iter()
+            .position(|entry| entry.database == database && entry.relid == relid)
+        {
+            Some(index) => index,
+            None if self.len < MAX_TRACKED_TABLES => {
+                self.len += 1;
+                self.len - 1
+            }
+            None => self.entries[..self.len]
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.total())
+                .map(|(index, _)| index)
+                .unwrap_or(0),
+        };
+
+        let entry = &mut self.entries[index];
+        if entry.database != database || entry.relid != relid {
+            *entry = TableCounters {
+                database,
+                relid,
+                ..TableCounters::default()
+            };
+        }
+        entry
+    }
+
+    pub fn entries(&self) -> Vec<TableCounters> {
+        self.entries[..self.len].to_vec()
+    }
+}
+
+pub const MAX_TRACKED_OFFENDERS: usize = 128;
+pub const OFFENDER_QUERY_LEN: usize = 256;
+
+#[derive(Copy, Clone)]
+pub struct Offender {
+    pub query_id: i64,
+    pub violations: i64,
+    pub last_seen: pg_sys::TimestampTz,
+    query_len: usize,
+    query: [u8; OFFENDER_QUERY_LEN],
+}
+
+impl Default for Offender {
+    fn default() -> Self {
+        Self {
+            query_id: 0,
+            violations: 0,
+            last_seen: 0,
+            query_len: 0,
+            query: [0; OFFENDER_QUERY_LEN],
+        }
+    }
+}
+
+impl Offender {
+    pub fn query(&self) -> String {
+        String::from_utf8_lossy(&self.query[..self.query_len]).into_owned()
+    }
+
+    fn set_query(&mut self, query: &str) {
+        // Truncate on a character boundary so the stored text stays valid UTF-8.
+        let mut len = query.len().min(OFFENDER_QUERY_LEN);
+        while !query.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.query[..len].copy_from_slice(&query.as_bytes()[..len]);
+        self.query_len = len;
+    }
+}
+
+/// Statements that caused violations, keyed by query identifier. When full, the
+/// least recently seen statement is evicted.
+#[derive(Copy, Clone)]
+pub struct OffenderStats {
+    len: usize,
+    entries: [Offender; MAX_TRACKED_OFFENDERS],
+}
+
+impl Default for OffenderStats {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            entries: [Offender::default(); MAX_TRACKED_OFFENDERS],
+        }
+    }
+}
+
+unsafe impl PGRXSharedMemory for OffenderStats {}
+
+impl OffenderStats {
+    fn record(&mut self, query_id: i64, query: &str, now: pg_sys::TimestampTz) {
+        let index = match self.entries[..self.len]
+            .iter()
+            .position(|entry| entry.query_id == query_id)
+        {
+            Some(index) => index,
+            None => {
+                let index = if self.len < MAX_TRACKED_OFFENDERS {
+                    self.len += 1;
+                    self.len - 1
+                } else {
+                    self.entries[..self.len]
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, entry)| entry.last_seen)
+                        .map(|(index, _)| index)
+                        .unwrap_or(0)
+                };
+                let mut entry = Offender {
+                    query_id,
+                    ..Offender::default()
+                };
+                entry.set_query(query);
+                self.entries[index] = entry;
+                index
+            }
+        };
+
+        let entry = &mut self.entries[index];
+        entry.violations += 1;
+        entry.last_seen = now;
+    }
+
+    pub fn entries(&self) -> Vec<Offender> {
+        self.entries[..self.len].to_vec()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    ViolationsWarned,
+    ViolationsBlocked,
+    ParseFailures,
+    Bypasses,
+}
+
+/// Bumps `counter` when pg_strict is preloaded; otherwise there is nowhere to keep it.
+pub fn increment(counter: Counter) {
+    if !shmem::available() {
+        return;
+    }
+
+    let atomic = match counter {
+        Counter::ViolationsWarned => &shmem::VIOLATIONS_WARNED,
+        Counter::ViolationsBlocked => &shmem::VIOLATIONS_BLOCKED,
+        Counter::ParseFailures => &shmem::PARSE_FAILURES,
+        Counter::Bypasses => &shmem::BYPASSES,
+    };
+    atomic.get().fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a checked statement and the time spent checking it, once it is done
+/// (including when the check raises an error).
+pub struct CheckTimer {
+    started: Instant,
+}
+
+impl CheckTimer {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for CheckTimer {
+    fn drop(&mut self) {
+        if !shmem::available() {
+            return;
+        }
+
+        let elapsed = self.started.elapsed().as_micros() as u64;
+        shmem::STATEMENTS_CHECKED
+            .get()
+            .fetch_add(1, Ordering::Relaxed);
+        shmem::CHECK_TIME_US
+            .get()
+            .fetch_add(elapsed, Ordering::Relaxed);
+    }
+}
+
+pub fn count_violation(mode: StrictMode) {
+    match mode {
+        StrictMode::On => increment(Counter::ViolationsBlocked),
+        StrictMode::Warn | StrictMode::Notice | StrictMode::Log => {
+            increment(Counter::ViolationsWarned)
+        }
+        StrictMode::Off | StrictMode::Learn => {}
+    }
+}
+
+/// Attributes a WHERE clause violation to the relation it targets.
+pub fn count_table_violation(relid: pg_sys::Oid, operation: Operation, mode: StrictMode) {
+    if !shmem::available() {
+        return;
+    }
+
+    let database = unsafe { pg_sys::MyDatabaseId }.as_u32();
+    let mut tables = shmem::TABLE_STATS.exclusive();
+    let entry = tables.entry(database, relid.as_u32());
+    match operation {
+        Operation::Update => entry.update_violations += 1,
+        Operation::Delete => entry.delete_violations += 1,
+    }
+    if mode == StrictMode::On {
+        entry.blocked += 1;
+    }
+}
+
+/// Identifies a statement by its query identifier or, with compute_query_id
+/// off, by a hash of its normalized text.
+pub fn fingerprint(query_id: i64, normalized_query: &str) -> i64 {
+    if query_id != 0 {
+        return query_id;
+    }
+    let mut hasher = DefaultHasher::new();
+    normalized_query.hash(&mut hasher);
+    hasher.finish() as i64
+}
+
+/// Counts a violation against its statement, given as loggable text.
+pub fn count_offender(query_id: i64, query: &str) {
+    if !shmem::available() {
+        return;
+    }
+
+    let query_id = fingerprint(query_id, query);
+    let now = unsafe { pg_sys::GetCurrentTimestamp() };
+    shmem::OFFENDERS.exclusive().record(query_id, query, now);
+}
+
+/// The `limit` statements with the most violations.
+pub fn top_offenders(limit: usize) -> Vec<Offender> {
+    let mut offenders = shmem::OFFENDERS.share().entries();
+    offenders.sort_by(|a, b| {
+        b.violations
+            .cmp(&a.violations)
+            .then(b.last_seen.cmp(&a.last_seen))
+    });
+    offenders.truncate(limit);
+    offenders
+}
+
+pub fn table_snapshot() -> Vec<TableCounters> {
+    shmem::TABLE_STATS.share().entries()
+}
+
+fn counters() -> [&'static AtomicU64; 6] {
+    [
+        shmem::STATEMENTS_CHECKED.get(),
+        shmem::VIOLATIONS_WARNED.get(),
+        shmem::VIOLATIONS_BLOCKED.get(),
+        shmem::PARSE_FAILURES.get(),
+        shmem::BYPASSES.get(),
+        shmem::CHECK_TIME_US.get(),
+    ]
+}
+
+/// Zeroes the counters, per-table statistics and offender tracking.
+/// Statements finishing meanwhile may still count towards the new totals.
+pub fn reset() {
+    for counter in counters() {
+        counter.store(0, Ordering::Relaxed);
+    }
+    *shmem::TABLE_STATS.exclusive() = TableStats::default();
+    *shmem::OFFENDERS.exclusive() = OffenderStats::default();
+}
+
+/// The counters as of now. They are read one by one, so a snapshot taken
+/// while statements are checked need not be consistent across counters.
+pub fn snapshot() -> StrictStats {
+    let [
+        checked,
+        warned,
+        blocked,
+        parse_failures,
+        bypasses,
+        check_time_us,
+    ] = counters().map(|counter| counter.load(Ordering::Relaxed) as i64);
+    StrictStats {
+        statements_checked: checked,
+        violations_warned: warned,
+        violations_blocked: blocked,
+        parse_failures,
+        bypasses,
+        check_time_us,
+    }
+}
@@ -0,0 +1,33 @@
+use pgrx::PgTryBuilder;
+use pgrx::pg_sys;
+
+/// Runs `f` in an internal subtransaction. An error raised inside is rolled
+/// back and turned into `None`, so pg_strict's own work on a statement's
+/// behalf never aborts the statement.
+pub fn try_in_subtransaction<R>(f: impl FnOnce() -> Option<R>) -> Option<R> {
+    let memory_context = unsafe { pg_sys::CurrentMemoryContext };
+    let resource_owner = unsafe { pg_sys::CurrentResourceOwner };
+    unsafe { pg_sys::BeginInternalSubTransaction(std::ptr::null()) };
+    unsafe { pg_sys::MemoryContextSwitchTo(memory_context) };
+
+    PgTryBuilder::new(|| {
+        let result = f();
+
+        unsafe {
+            pg_sys::ReleaseCurrentSubTransaction();
+            pg_sys::MemoryContextSwitchTo(memory_context);
+            pg_sys::CurrentResourceOwner = resource_owner;
+        }
+        result
+    })
+    .catch_others(|_| {
+        unsafe {
+            pg_sys::MemoryContextSwitchTo(memory_context);
+            pg_sys::RollbackAndReleaseCurrentSubTransaction();
+            pg_sys::MemoryContextSwitchTo(memory_context);
+            pg_sys::CurrentResourceOwner = resource_owner;
+        }
+        None
+    })
+    .execute()
+}
@@ -0,0 +1,117 @@
+use crate::analyzer::Operation;
+use crate::guc::summary_at_commit_enabled;
+use crate::report::group_digits;
+use pgrx::pg_sys;
+
+/// A flagged UPDATE or DELETE that was allowed to run in the current transaction.
+pub struct FlaggedStatement {
+    pub operation: Operation,
+    pub table: Option<String>,
+    pub executor_depth: u32,
+    pub rows: Option<u64>,
+}
+
+static mut FLAGGED: Vec<FlaggedStatement> = Vec::new();
+
+/// Remembers a warned or bypassed statement for the summary sent at COMMIT.
+/// Its row count is filled in when it finishes executing at `executor_depth`.
+#[allow(static_mut_refs)]
+pub fn note(operation: Operation, table: Option<&str>, executor_depth: u32) {
+    if !summary_at_commit_enabled() {
+        return;
+    }
+    unsafe {
+        FLAGGED.push(FlaggedStatement {
+            operation,
+            table: table.map(str::to_string),
+            executor_depth,
+            rows: None,
+        })
+    };
+}
+
+/// Called when an UPDATE/DELETE finishes at `executor_depth`.
+#[allow(static_mut_refs)]
+pub fn record_rows_affected(executor_depth: u32, rows: u64) {
+    unsafe {
+        if let Some(flagged) = FLAGGED
+            .iter_mut()
+            .rev()
+            .find(|flagged| flagged.executor_depth == executor_depth && flagged.rows.is_none())
+        {
+            flagged.rows = Some(rows);
+        }
+    }
+}
+
+/// Summary of the flagged statements run so far in this transaction.
+#[allow(static_mut_refs)]
+pub fn current() -> Option<String> {
+    describe(unsafe { &FLAGGED })
+}
+
+/// Sends the summary just before an explicit transaction block commits.
+/// Single statements outside a block were already reported on their own.
+pub fn report_at_commit() {
+    if !summary_at_commit_enabled() || !unsafe { pg_sys::IsTransactionBlock() } {
+        return;
+    }
+    if let Some(summary) = current() {
+        pgrx::notice!("pg_strict: {}", summary);
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn reset() {
+    unsafe { FLAGGED.clear() };
+}
+
+/// Groups flagged statements by operation and table, in the order they first ran.
+pub fn describe(flagged: &[FlaggedStatement]) -> Option<String> {
+    if flagged.is_empty() {
+        return None;
+    }
+
+    let mut groups: Vec<(Operation, Option<&str>, usize, u64)> = Vec::new();
+    for statement in flagged {
+        let table = statement.table.as_deref();
+        let rows = statement.rows.unwrap_or(0);
+        match groups
+            .iter_mut()
+            .find(|(operation, name, _, _)| *operation == statement.operation && *name == table)
+        {
+            Some(group) => {
+                group.2 += 1;
+                group.3 += rows;
+            }
+            None => groups.push((statement.operation, table, 1, rows)),
+        }
+    }
+
+    let total_rows: u64 = groups.iter().map(|group| group.3).sum();
+    let details = groups
+        .iter()
+        .map(|(operation, table, count, rows)| {
+            let statements = if *count == 1 {
+                String::new()
+            } else {
+                format!("{count} statements, ")
+            };
+            format!(
+                "{} on {} ({}{} rows)",
+                operation.as_str(),
+                table.unwrap_or("unknown table"),
+                statements,
+                group_digits(*rows)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    Some(format!(
+        "this transaction ran {} flagged statement{} modifying {} rows: {}.",
+        flagged.len(),
+        if flagged.len() == 1 { "" } else { "s" },
+        group_digits(total_rows),
+        details
+    ))
+}
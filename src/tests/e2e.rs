@@ -78,3 +78,1632 @@ fn test_e2e_delete_cte_with_where_allowed_when_on() {
     )
     .expect("cte delete with where should succeed");
 }
+
+#[pg_test]
+fn test_e2e_exempt_application_name_allows_unconditional_delete() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_exempt(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_exempt VALUES (1), (2);").expect("seed temp table");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.exempt_application_names = 'pg_cron, nightly_*';")
+        .expect("set exempt application names");
+    Spi::run("SET application_name = 'nightly_staging_cleanup';").expect("set application_name");
+    Spi::run("DELETE FROM pg_strict_e2e_exempt;").expect("exempt session delete should succeed");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_non_matching_application_name_is_still_blocked() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_not_exempt(id int primary key);")
+        .expect("create temp table");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.exempt_application_names = 'pg_cron';")
+        .expect("set exempt application names");
+    Spi::run("SET application_name = 'psql';").expect("set application_name");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_not_exempt;");
+}
+
+#[pg_test]
+fn test_e2e_trusted_function_dml_is_not_checked() {
+    Spi::run("CREATE SCHEMA pg_strict_e2e_maint;").expect("create schema");
+    Spi::run("CREATE TABLE pg_strict_e2e_maint.expired(id int primary key);")
+        .expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_maint.expired VALUES (1), (2);").expect("seed table");
+    Spi::run(
+        "CREATE FUNCTION pg_strict_e2e_maint.purge_expired() RETURNS void \
+             LANGUAGE plpgsql AS $$ BEGIN DELETE FROM pg_strict_e2e_maint.expired; END $$;",
+    )
+    .expect("create trusted function");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.trusted_functions = 'pg_strict_e2e_maint.purge_expired()';")
+        .expect("set trusted functions");
+    Spi::run("SELECT pg_strict_e2e_maint.purge_expired();")
+        .expect("trusted function delete should succeed");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_untrusted_function_dml_is_blocked() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_untrusted(id int primary key);")
+        .expect("create temp table");
+    Spi::run(
+        "CREATE FUNCTION pg_temp.purge_untrusted() RETURNS void \
+             LANGUAGE plpgsql AS $$ BEGIN DELETE FROM pg_strict_e2e_untrusted; END $$;",
+    )
+    .expect("create function");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.trusted_functions = 'maintenance.purge_expired';")
+        .expect("set trusted functions");
+    let _ = Spi::run("SELECT pg_temp.purge_untrusted();");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE on system catalog pg_catalog.pg_description detected")]
+fn test_e2e_catalog_dml_blocked_with_where_when_protected() {
+    Spi::run("SET pg_strict.protect_catalogs = 'on';").expect("set protect_catalogs");
+    let _ = Spi::run("DELETE FROM pg_catalog.pg_description WHERE objoid = 0;");
+}
+
+#[pg_test]
+fn test_e2e_catalog_dml_allowed_when_protection_off() {
+    Spi::run("SET pg_strict.protect_catalogs = 'off';").expect("set protect_catalogs");
+    Spi::run("DELETE FROM pg_catalog.pg_description WHERE objoid = 0;")
+        .expect("catalog delete should succeed when protection is off");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_production_preset_blocks_unset_settings() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_preset(id int primary key);")
+        .expect("create temp table");
+
+    Spi::run("SET pg_strict.preset = 'production';").expect("set preset");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_preset;");
+}
+
+#[pg_test]
+#[should_panic(expected = "TRUNCATE of public.pg_strict_e2e_preset_truncate detected")]
+fn test_e2e_production_preset_guards_truncate() {
+    Spi::run("CREATE TABLE pg_strict_e2e_preset_truncate(id int);").expect("create table");
+
+    Spi::run("SET pg_strict.preset = 'production';").expect("set preset");
+    let _ = Spi::run("TRUNCATE pg_strict_e2e_preset_truncate;");
+}
+
+#[pg_test]
+fn test_e2e_explicit_setting_overrides_preset() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_preset_override(id int primary key);")
+        .expect("create temp table");
+
+    Spi::run("SET pg_strict.preset = 'production';").expect("set preset");
+    Spi::run("SET pg_strict.require_where_on_delete = 'off';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_preset_override;")
+        .expect("explicit off should override the preset");
+}
+
+#[pg_test]
+fn test_e2e_allow_next_bypasses_next_statement() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_allow_next(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    assert!(api::pg_strict_allow_next(Some("e2e test")));
+    Spi::run("DELETE FROM pg_strict_e2e_allow_next;").expect("armed bypass should allow delete");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_allow_next_covers_only_one_statement() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_allow_once(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    assert!(api::pg_strict_allow_next(None));
+    Spi::run("DELETE FROM pg_strict_e2e_allow_once;").expect("armed bypass should allow delete");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_allow_once;");
+}
+
+#[pg_test]
+fn test_e2e_allow_next_is_kept_by_statement_with_where() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_allow_kept(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_allow_kept VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_update = 'on';").expect("set update mode");
+
+    assert!(api::pg_strict_allow_next(Some("e2e test")));
+    Spi::run("UPDATE pg_strict_e2e_allow_kept SET id = id + 10 WHERE id = 1;")
+        .expect("qualified update should run");
+    Spi::run("UPDATE pg_strict_e2e_allow_kept SET id = id + 100;")
+        .expect("armed bypass should still allow the unqualified update");
+}
+
+#[pg_test]
+#[should_panic(expected = "a reason is required to bypass enforcement")]
+fn test_allow_next_requires_reason_when_configured() {
+    Spi::run("SET pg_strict.require_bypass_reason = on;").expect("set require_bypass_reason");
+    api::pg_strict_allow_next(Some("   "));
+}
+
+#[pg_test]
+fn test_e2e_override_with_reason_relaxes_transaction() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_override(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run("SET LOCAL pg_strict.override = 'ticket-1234: backfill';").expect("set override");
+    Spi::run("DELETE FROM pg_strict_e2e_override;").expect("override should allow delete");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_blank_override_reason_does_not_relax() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_blank_override(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run("SET LOCAL pg_strict.override = '   ';").expect("set override");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_blank_override;");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_override_set_without_local_does_not_relax() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_session_override(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run("SET pg_strict.override = 'ticket-1234: backfill';").expect("set override");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_session_override;");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_override_from_set_config_does_not_relax() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_set_config_override(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run("SELECT set_config('pg_strict.override', 'ticket-1234: backfill', true);")
+        .expect("set override");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_set_config_override;");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_preset_applies_again_after_reset() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_preset_reset(id int primary key);")
+        .expect("create temp table");
+
+    Spi::run("SET pg_strict.preset = 'production';").expect("set preset");
+    Spi::run("SET pg_strict.require_where_on_delete = 'off';").expect("set delete mode");
+    Spi::run("RESET pg_strict.require_where_on_delete;").expect("reset delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_preset_reset;");
+}
+
+#[pg_test]
+fn test_e2e_snooze_relaxes_until_unsnoozed() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_snooze(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run("SELECT pg_strict_snooze('5 minutes', 'e2e test');").expect("snooze");
+    Spi::run("DELETE FROM pg_strict_e2e_snooze;").expect("snoozed delete should succeed");
+    assert!(api::pg_strict_unsnooze());
+    assert!(!api::pg_strict_unsnooze());
+}
+
+#[pg_test]
+fn test_e2e_approved_statement_runs_once() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_approved(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    let request_id = api::pg_strict_request_approval("DELETE FROM pg_strict_e2e_approved");
+    Spi::run_with_args(
+        "UPDATE strict.approvals SET approved_by = 'someone_else', approved_at = now() WHERE id = $1",
+        &[request_id.into()],
+    )
+    .expect("simulate approval by another role");
+
+    Spi::run("DELETE FROM pg_strict_e2e_approved;").expect("approved delete should succeed");
+    let consumed = Spi::get_one_with_args::<bool>(
+        "SELECT consumed_at IS NOT NULL FROM strict.approvals WHERE id = $1",
+        &[request_id.into()],
+    )
+    .expect("query approval");
+    assert_eq!(consumed, Some(true));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_approval_of_another_role_is_not_used() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_foreign_approval(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    let request_id = api::pg_strict_request_approval("DELETE FROM pg_strict_e2e_foreign_approval");
+    Spi::run_with_args(
+        "UPDATE strict.approvals SET requested_by = 'someone_else', approved_by = 'approver', \
+             approved_at = now() WHERE id = $1",
+        &[request_id.into()],
+    )
+    .expect("simulate another role's approved request");
+
+    Spi::run("DELETE FROM pg_strict_e2e_foreign_approval;").expect("delete");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_force_mode_ignores_user_setting_off() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_force(id int primary key);")
+        .expect("create temp table");
+
+    Spi::run("SET pg_strict.enforce = 'force';").expect("set enforce");
+    Spi::run("SET pg_strict.require_where_on_delete = 'off';").expect("set delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_force;");
+}
+
+#[pg_test]
+fn test_e2e_force_mode_honours_superuser_bypass() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_force_su(id int primary key);")
+        .expect("create temp table");
+
+    Spi::run("SET pg_strict.enforce = 'force';").expect("set enforce");
+    assert!(api::pg_strict_allow_next(Some("superuser break-glass")));
+    Spi::run("DELETE FROM pg_strict_e2e_force_su;")
+        .expect("superuser bypass should be honoured in force mode");
+}
+
+#[pg_test]
+fn test_e2e_maintenance_window_downgrades_on_to_warn() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_window(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.maintenance_windows = '* 00:00-24:00';")
+        .expect("set maintenance windows");
+    Spi::run("DELETE FROM pg_strict_e2e_window;").expect("delete should only warn in window");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_warn_escalates_to_on_after_deadline() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_escalate(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("SET pg_strict.escalate_at = '2000-01-01 00:00:00+00';").expect("set escalate_at");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_escalate;");
+}
+
+#[pg_test]
+fn test_e2e_warn_before_deadline_still_runs() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_grace(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("SET pg_strict.escalate_at = '2999-01-01 00:00:00+00';").expect("set escalate_at");
+    Spi::run("DELETE FROM pg_strict_e2e_grace;").expect("delete should only warn before deadline");
+}
+
+#[pg_test]
+fn test_e2e_bypass_is_written_to_bypass_log() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_bypass_log(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_bypass_log VALUES (1), (2), (3);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    assert!(api::pg_strict_allow_next(Some("audited purge")));
+    Spi::run("DELETE FROM pg_strict_e2e_bypass_log;").expect("bypassed delete should succeed");
+
+    let (mechanism, rows) = Spi::get_two::<String, i64>(
+        "SELECT mechanism, rows_affected FROM strict.bypass_log \
+             WHERE reason = 'audited purge' ORDER BY id DESC LIMIT 1",
+    )
+    .expect("read bypass log");
+    assert_eq!(mechanism.as_deref(), Some("allow_next"));
+    assert_eq!(rows, Some(3));
+}
+
+#[pg_test]
+#[should_panic(expected = "permission denied for function log_bypass")]
+fn test_e2e_log_bypass_is_not_executable_by_public() {
+    Spi::run("CREATE ROLE pg_strict_e2e_bypass_forger;").expect("create role");
+    Spi::run("SET ROLE pg_strict_e2e_bypass_forger;").expect("switch role");
+    Spi::run("SELECT strict.log_bypass('override', 'forged', 'DELETE FROM t');")
+        .expect("forged bypass");
+}
+
+#[pg_test]
+fn test_e2e_warned_violation_is_written_to_violations() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_audit(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_audit VALUES (1);").expect("seed table");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+
+    Spi::run("UPDATE pg_strict_e2e_audit SET id = id + 1;").expect("warned update should run");
+
+    let (mode, query) = Spi::get_two::<String, String>(
+        "SELECT mode, query FROM strict.violations \
+             WHERE operation = 'UPDATE' ORDER BY id DESC LIMIT 1",
+    )
+    .expect("read violations");
+    assert_eq!(mode.as_deref(), Some("warn"));
+    assert_eq!(
+        query.as_deref(),
+        Some("UPDATE pg_strict_e2e_audit SET id = id + 1")
+    );
+}
+
+#[pg_test]
+fn test_e2e_unprivileged_role_violation_is_written_to_violations() {
+    Spi::run("CREATE ROLE pg_strict_e2e_auditee;").expect("create role");
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_audit_role(id int primary key);")
+        .expect("create temp table");
+    Spi::run("GRANT SELECT, UPDATE ON pg_strict_e2e_audit_role TO pg_strict_e2e_auditee;")
+        .expect("grant update");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+
+    Spi::run("SET ROLE pg_strict_e2e_auditee;").expect("switch role");
+    Spi::run("UPDATE pg_strict_e2e_audit_role SET id = id + 1;")
+        .expect("warned update should run");
+    Spi::run("RESET ROLE;").expect("reset role");
+
+    let count = Spi::get_one::<i64>(
+        "SELECT count(*) FROM strict.violations \
+             WHERE query = 'UPDATE pg_strict_e2e_audit_role SET id = id + 1'",
+    )
+    .expect("read violations");
+    assert_eq!(count, Some(1));
+}
+
+#[pg_test]
+#[should_panic(expected = "permission denied for function log_violation")]
+fn test_e2e_log_violation_is_not_executable_by_public() {
+    Spi::run("CREATE ROLE pg_strict_e2e_forger;").expect("create role");
+    Spi::run("SET ROLE pg_strict_e2e_forger;").expect("switch role");
+    Spi::run(
+        "SELECT strict.log_violation(now(), '', 'on', 'DELETE', 0, 'DELETE FROM t', 'someone_else');",
+    )
+    .expect("forged violation");
+}
+
+#[pg_test]
+fn test_e2e_stats_count_warned_violations() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_stats(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    let before = Spi::get_one::<i64>("SELECT violations_warned FROM pg_strict_stats()")
+        .expect("read stats")
+        .unwrap_or_default();
+    Spi::run("DELETE FROM pg_strict_e2e_stats;").expect("warned delete should run");
+    let after = Spi::get_one::<i64>("SELECT violations_warned FROM pg_strict_stats()")
+        .expect("read stats")
+        .unwrap_or_default();
+
+    assert_eq!(after, before + 1);
+}
+
+#[pg_test]
+fn test_e2e_table_stats_track_violations_per_relation() {
+    Spi::run("CREATE TABLE pg_strict_e2e_table_stats(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+
+    Spi::run("UPDATE pg_strict_e2e_table_stats SET id = id;").expect("warned update should run");
+    Spi::run("UPDATE pg_strict_e2e_table_stats SET id = id;").expect("warned update should run");
+
+    let updates = Spi::get_one::<i64>(
+        "SELECT update_violations FROM pg_strict_table_stats() \
+             WHERE relid = 'pg_strict_e2e_table_stats'::regclass",
+    )
+    .expect("read table stats");
+    assert_eq!(updates, Some(2));
+}
+
+#[pg_test]
+fn test_e2e_top_offenders_group_repeated_statements() {
+    Spi::run("CREATE TABLE pg_strict_e2e_offenders(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    for _ in 0..3 {
+        Spi::run("DELETE FROM pg_strict_e2e_offenders;").expect("warned delete should run");
+    }
+
+    let violations = Spi::get_one::<i64>(
+        "SELECT violations FROM pg_strict_top_offenders(128) \
+             WHERE query = 'DELETE FROM pg_strict_e2e_offenders'",
+    )
+    .expect("read top offenders");
+    assert_eq!(violations, Some(3));
+}
+
+#[pg_test]
+fn test_e2e_violation_carries_sqlstate_detail_and_hint() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_sqlstate(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run(
+        "DO $$
+         DECLARE
+             detail text;
+             hint text;
+         BEGIN
+             DELETE FROM pg_strict_e2e_sqlstate;
+             RAISE EXCEPTION 'DELETE was not blocked';
+         EXCEPTION WHEN SQLSTATE '2F003' THEN
+             GET STACKED DIAGNOSTICS detail = PG_EXCEPTION_DETAIL, hint = PG_EXCEPTION_HINT;
+             IF detail NOT LIKE 'DELETE on table %pg_strict_e2e_sqlstate has no WHERE clause.' THEN
+                 RAISE EXCEPTION 'unexpected detail: %', detail;
+             END IF;
+             IF hint NOT LIKE '%TRUNCATE%' THEN
+                 RAISE EXCEPTION 'unexpected hint: %', hint;
+             END IF;
+         END $$;",
+    )
+    .expect("violation should raise SQLSTATE 2F003 with detail and hint");
+}
+
+// PostgreSQL 13 only computes query identifiers with pg_stat_statements loaded.
+#[cfg(not(feature = "pg13"))]
+#[pg_test]
+fn test_e2e_violation_records_query_id() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_queryid(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    Spi::run("DELETE FROM pg_strict_e2e_queryid;").expect("warned delete should run");
+
+    let query_id = Spi::get_one::<i64>(
+        "SELECT queryid FROM strict.violations \
+             WHERE query = 'DELETE FROM pg_strict_e2e_queryid' ORDER BY id DESC LIMIT 1",
+    )
+    .expect("read violations");
+    assert!(query_id.is_some_and(|id| id != 0));
+}
+
+#[pg_test]
+fn test_e2e_rate_limited_warnings_still_count() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_rate_limit(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("SET pg_strict.max_warnings_per_minute = 1;").expect("set warning limit");
+
+    let before = Spi::get_one::<i64>("SELECT violations_warned FROM pg_strict_stats()")
+        .expect("read stats")
+        .unwrap_or_default();
+    for _ in 0..5 {
+        Spi::run("DELETE FROM pg_strict_e2e_rate_limit;").expect("warned delete should run");
+    }
+    let after = Spi::get_one::<i64>("SELECT violations_warned FROM pg_strict_stats()")
+        .expect("read stats")
+        .unwrap_or_default();
+
+    assert_eq!(after, before + 5);
+}
+
+#[pg_test]
+fn test_e2e_learn_mode_records_without_reporting() {
+    Spi::run("CREATE TABLE pg_strict_e2e_learn(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'learn';").expect("set delete mode");
+
+    Spi::run("DELETE FROM pg_strict_e2e_learn;").expect("learn mode should not block");
+
+    let learned = Spi::get_one::<i64>(
+        "SELECT count(*) FROM strict.learned_statements \
+             WHERE table_name LIKE '%.pg_strict_e2e_learn' AND operation = 'DELETE'",
+    )
+    .expect("read learned statements");
+    assert_eq!(learned, Some(1));
+
+    let recommendation = Spi::get_one::<String>(
+        "SELECT recommendation FROM pg_strict_recommendations() \
+             WHERE category = 'protect_table' AND target LIKE '%.pg_strict_e2e_learn'",
+    )
+    .expect("read recommendations");
+    assert!(recommendation.is_some());
+}
+
+#[pg_test]
+fn test_e2e_export_audit_includes_violations() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_export(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_export;").expect("warned delete should run");
+
+    let operation = Spi::get_one::<String>(
+        "SELECT line::jsonb ->> 'operation' \
+         FROM pg_strict_export_audit(now() - interval '1 hour', 'json') AS line \
+         WHERE line::jsonb ->> 'statement' = 'DELETE FROM pg_strict_e2e_export'",
+    )
+    .expect("export json");
+    assert_eq!(operation.as_deref(), Some("DELETE"));
+
+    let header = Spi::get_one::<String>(
+        "SELECT line FROM pg_strict_export_audit(now() - interval '1 hour', 'csv') AS line LIMIT 1",
+    )
+    .expect("export csv");
+    assert!(header.is_some_and(|line| line.starts_with("event_time,event_type,username")));
+}
+
+#[pg_test]
+fn test_e2e_plan_logging_does_not_disturb_warned_statement() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_plans(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_plans VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.log_plans = 'json';").expect("enable plan logging");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    Spi::run("DELETE FROM pg_strict_e2e_plans;").expect("warned delete should run");
+
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_plans").expect("count rows");
+    assert_eq!(remaining, Some(0));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE aborted after modifying more than 2 rows")]
+fn test_e2e_max_affected_rows_aborts_large_delete() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_ceiling(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_ceiling SELECT generate_series(1, 10);")
+        .expect("seed table");
+    Spi::run("SET pg_strict.max_affected_rows = 2;").expect("set row ceiling");
+    Spi::run("DELETE FROM pg_strict_e2e_ceiling WHERE id > 0;").unwrap();
+}
+
+#[pg_test]
+fn test_e2e_max_affected_rows_allows_small_update() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_ceiling_ok(id int primary key, v int);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_ceiling_ok SELECT g, 0 FROM generate_series(1, 10) g;")
+        .expect("seed table");
+    Spi::run("SET pg_strict.max_affected_rows = 2;").expect("set row ceiling");
+    Spi::run("UPDATE pg_strict_e2e_ceiling_ok SET v = 1 WHERE id <= 2;")
+        .expect("update within the ceiling should run");
+
+    let updated = Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_ceiling_ok WHERE v = 1")
+        .expect("count rows");
+    assert_eq!(updated, Some(2));
+}
+
+#[pg_test]
+#[should_panic(expected = "is estimated to affect 100% of its rows")]
+fn test_e2e_max_affected_percent_blocks_unselective_delete() {
+    Spi::run("CREATE TABLE pg_strict_e2e_selectivity(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_selectivity SELECT generate_series(1, 1000);")
+        .expect("seed table");
+    Spi::run("ANALYZE pg_strict_e2e_selectivity;").expect("analyze table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.max_affected_percent = 50;").expect("set threshold");
+    Spi::run("DELETE FROM pg_strict_e2e_selectivity WHERE id > 0;").unwrap();
+}
+
+#[pg_test]
+fn test_e2e_max_affected_percent_allows_selective_delete() {
+    Spi::run("CREATE TABLE pg_strict_e2e_selective(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_selective SELECT generate_series(1, 1000);")
+        .expect("seed table");
+    Spi::run("ANALYZE pg_strict_e2e_selective;").expect("analyze table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.max_affected_percent = 50;").expect("set threshold");
+    Spi::run("DELETE FROM pg_strict_e2e_selective WHERE id = 1;")
+        .expect("selective delete should run");
+}
+
+#[pg_test]
+fn test_e2e_count_affected_reports_estimate() {
+    Spi::run("CREATE TABLE pg_strict_e2e_count(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_count SELECT generate_series(1, 100);")
+        .expect("seed table");
+    Spi::run("ANALYZE pg_strict_e2e_count;").expect("analyze table");
+
+    let (operation, table_rows) = Spi::get_two::<String, f64>(
+        "SELECT operation, table_rows \
+         FROM pg_strict_count_affected('DELETE FROM pg_strict_e2e_count WHERE id <= 10')",
+    )
+    .expect("count affected");
+    assert_eq!(operation.as_deref(), Some("DELETE"));
+    assert_eq!(table_rows, Some(100.0));
+
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_count").expect("count rows");
+    assert_eq!(remaining, Some(100));
+}
+
+#[pg_test]
+#[should_panic(expected = "expected a single UPDATE or DELETE statement")]
+fn test_e2e_count_affected_rejects_select() {
+    Spi::run("SELECT * FROM pg_strict_count_affected('SELECT 1')").unwrap();
+}
+
+#[pg_test]
+fn test_e2e_preview_returns_affected_rows_without_modifying() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_preview(id int primary key, v int);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_preview SELECT g, 0 FROM generate_series(1, 10) g;")
+        .expect("seed table");
+
+    let previewed = Spi::get_one::<i64>(
+        "SELECT count(*) FROM pg_strict_preview(\
+         'UPDATE pg_strict_e2e_preview p SET v = 1 WHERE p.id <= 3', 2)",
+    )
+    .expect("preview update");
+    assert_eq!(previewed, Some(2));
+
+    let first = Spi::get_one::<i32>(
+        "SELECT min((row ->> 'id')::int) FROM pg_strict_preview(\
+         'DELETE FROM pg_strict_e2e_preview WHERE id > 8;') AS row",
+    )
+    .expect("preview delete");
+    assert_eq!(first, Some(9));
+
+    let untouched = Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_preview WHERE v = 0")
+        .expect("count rows");
+    assert_eq!(untouched, Some(10));
+}
+
+#[pg_test]
+fn test_e2e_cascade_impact_follows_cascading_keys() {
+    Spi::run(
+        "CREATE TABLE pg_strict_e2e_parent(id int primary key); \
+         CREATE TABLE pg_strict_e2e_child(id int primary key, \
+             parent_id int REFERENCES pg_strict_e2e_parent ON DELETE CASCADE); \
+         CREATE TABLE pg_strict_e2e_grandchild(id int primary key, \
+             child_id int REFERENCES pg_strict_e2e_child ON DELETE SET NULL);",
+    )
+    .expect("create tables");
+
+    let reached = Spi::get_one::<String>(
+        "SELECT string_agg(depth || ':' || on_delete, ',' ORDER BY depth) \
+         FROM pg_strict_cascade_impact('pg_strict_e2e_parent')",
+    )
+    .expect("cascade impact");
+    assert_eq!(reached.as_deref(), Some("1:CASCADE,2:SET NULL"));
+}
+
+#[pg_test]
+#[should_panic(expected = "above pg_strict.max_statement_cost")]
+fn test_e2e_statement_cost_blocks_expensive_select() {
+    Spi::run("SET pg_strict.statement_cost_mode = 'on';").expect("set cost mode");
+    Spi::run("SET pg_strict.max_statement_cost = 1;").expect("set cost threshold");
+    Spi::run("SELECT count(*) FROM generate_series(1, 100000);").unwrap();
+}
+
+#[pg_test]
+fn test_e2e_statement_cost_allows_plain_explain() {
+    Spi::run("SET pg_strict.statement_cost_mode = 'on';").expect("set cost mode");
+    Spi::run("SET pg_strict.max_statement_cost = 1;").expect("set cost threshold");
+    Spi::run("EXPLAIN SELECT count(*) FROM generate_series(1, 100000);")
+        .expect("plain EXPLAIN should not be blocked");
+}
+
+#[pg_test]
+fn test_e2e_seqscan_warning_does_not_block() {
+    Spi::run("CREATE TABLE pg_strict_e2e_seqscan(id int primary key, v int);")
+        .expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_seqscan SELECT g, g FROM generate_series(1, 1000) g;")
+        .expect("seed table");
+    Spi::run("ANALYZE pg_strict_e2e_seqscan;").expect("analyze table");
+    Spi::run("SET pg_strict.seqscan_warn_size = 1;").expect("set size threshold");
+
+    Spi::run("UPDATE pg_strict_e2e_seqscan SET v = 0 WHERE v > 500;")
+        .expect("sequentially scanned update should only warn");
+    let updated = Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_seqscan WHERE v = 0")
+        .expect("count rows");
+    assert_eq!(updated, Some(500));
+}
+
+#[pg_test]
+fn test_e2e_recycle_bin_captures_warned_delete() {
+    Spi::run("CREATE TABLE pg_strict_e2e_recycle(id int primary key, note text);")
+        .expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_recycle VALUES (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("seed table");
+    Spi::run("SET pg_strict.recycle_bin = on;").expect("enable recycle bin");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    Spi::run("DELETE FROM pg_strict_e2e_recycle;").expect("warned delete should run");
+
+    let (row_count, saved) = Spi::get_two::<i64, i64>(
+        "SELECT b.row_count, (SELECT count(*) FROM strict.recycle_bin_rows r WHERE r.bin_id = b.bin_id) \
+         FROM strict.recycle_bin b \
+         WHERE b.relid = 'pg_strict_e2e_recycle'::regclass",
+    )
+    .expect("read recycle bin");
+    assert_eq!(row_count, Some(3));
+    assert_eq!(saved, Some(3));
+}
+
+#[pg_test]
+fn test_e2e_restore_reinserts_deleted_rows() {
+    Spi::run("CREATE TABLE pg_strict_e2e_restore(id int primary key, note text);")
+        .expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_restore VALUES (1, 'a'), (2, 'b'), (3, 'c');")
+        .expect("seed table");
+    Spi::run("SET pg_strict.recycle_bin = on;").expect("enable recycle bin");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_restore;").expect("warned delete should run");
+
+    let restored = Spi::get_one::<i64>(
+        "SELECT pg_strict_restore(bin_id, 'id <> 2') FROM strict.recycle_bin \
+         WHERE relid = 'pg_strict_e2e_restore'::regclass",
+    )
+    .expect("restore bin");
+    assert_eq!(restored, Some(2));
+
+    let notes = Spi::get_one::<String>(
+        "SELECT string_agg(note, ',' ORDER BY id) FROM pg_strict_e2e_restore",
+    )
+    .expect("read restored rows");
+    assert_eq!(notes.as_deref(), Some("a,c"));
+}
+
+#[pg_test]
+fn test_e2e_restore_reverts_updated_rows() {
+    Spi::run("CREATE TABLE pg_strict_e2e_restore_upd(id int primary key, note text);")
+        .expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_restore_upd VALUES (1, 'a'), (2, 'b');")
+        .expect("seed table");
+    Spi::run("SET pg_strict.recycle_bin = on;").expect("enable recycle bin");
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+    Spi::run("UPDATE pg_strict_e2e_restore_upd SET note = 'oops';").expect("warned update should run");
+
+    Spi::run(
+        "SELECT pg_strict_restore(bin_id) FROM strict.recycle_bin \
+         WHERE relid = 'pg_strict_e2e_restore_upd'::regclass",
+    )
+    .expect("restore bin");
+
+    let notes = Spi::get_one::<String>(
+        "SELECT string_agg(note, ',' ORDER BY id) FROM pg_strict_e2e_restore_upd",
+    )
+    .expect("read restored rows");
+    assert_eq!(notes.as_deref(), Some("a,b"));
+}
+
+#[pg_test]
+fn test_e2e_transaction_summary_lists_warned_delete() {
+    Spi::run("CREATE TABLE pg_strict_e2e_summary(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_summary VALUES (1), (2), (3);").expect("seed table");
+    Spi::run("SET pg_strict.summary_at_commit = on;").expect("enable summary");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_summary;").expect("warned delete should run");
+
+    let summary = Spi::get_one::<String>("SELECT pg_strict_transaction_summary()")
+        .expect("read summary")
+        .expect("summary should not be empty");
+    assert!(summary.contains("1 flagged statement modifying 3 rows"));
+    assert!(summary.contains("DELETE on"));
+}
+
+#[pg_test]
+#[should_panic(expected = "where_sql must not be empty")]
+fn test_e2e_delete_in_batches_requires_condition() {
+    Spi::run("CREATE TABLE pg_strict_e2e_batches(id int primary key);").expect("create table");
+    let _ = Spi::run("CALL pg_strict_delete_in_batches('pg_strict_e2e_batches', ' ');");
+}
+
+#[pg_test]
+fn test_e2e_require_transaction_allows_warned_delete_in_block() {
+    Spi::run("CREATE TABLE pg_strict_e2e_require_xact(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_require_xact VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.require_transaction = on;").expect("require transaction");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    // Tests run inside a transaction block, so the warned DELETE may run.
+    Spi::run("DELETE FROM pg_strict_e2e_require_xact;").expect("warned delete should run");
+    let remaining = Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_require_xact")
+        .expect("count rows");
+    assert_eq!(remaining, Some(0));
+}
+
+#[pg_test]
+#[should_panic(expected = "must run with statement_timeout and lock_timeout set")]
+fn test_e2e_require_timeouts_rejects_warned_delete_without_timeouts() {
+    Spi::run("CREATE TABLE pg_strict_e2e_timeouts(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.require_timeouts = on;").expect("require timeouts");
+    Spi::run("SET statement_timeout = '1min';").expect("set statement_timeout");
+    Spi::run("SET lock_timeout = 0;").expect("disable lock_timeout");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_timeouts;");
+}
+
+#[pg_test]
+#[should_panic(expected = "requires a recent backup")]
+fn test_e2e_require_recent_backup_rejects_stale_backup() {
+    Spi::run("CREATE TABLE pg_strict_e2e_backup(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.require_recent_backup = '1h';").expect("set backup window");
+    Spi::run("SET pg_strict.backup_probe = 'SELECT now() - interval ''2 days''';")
+        .expect("set backup probe");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_backup;");
+}
+
+#[pg_test]
+fn test_e2e_require_recent_backup_allows_fresh_backup() {
+    Spi::run("CREATE TABLE pg_strict_e2e_backup_ok(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.require_recent_backup = '1h';").expect("set backup window");
+    Spi::run("SET pg_strict.backup_probe = 'SELECT now() - interval ''5 minutes''';")
+        .expect("set backup probe");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_backup_ok;").expect("warned delete should run");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_rule_action_without_where_is_blocked() {
+    Spi::run("CREATE TABLE pg_strict_e2e_rule_src(id int);").expect("create source table");
+    Spi::run("CREATE TABLE pg_strict_e2e_rule_dst(id int);").expect("create target table");
+    Spi::run("INSERT INTO pg_strict_e2e_rule_dst VALUES (1), (2);").expect("seed target table");
+    Spi::run(
+        "CREATE RULE pg_strict_e2e_wipe AS ON INSERT TO pg_strict_e2e_rule_src \
+         DO ALSO DELETE FROM pg_strict_e2e_rule_dst;",
+    )
+    .expect("create rule");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    let _ = Spi::run("INSERT INTO pg_strict_e2e_rule_src VALUES (1);");
+}
+
+#[pg_test]
+fn test_e2e_rule_action_with_where_runs() {
+    Spi::run("CREATE TABLE pg_strict_e2e_rule_src2(id int);").expect("create source table");
+    Spi::run("CREATE TABLE pg_strict_e2e_rule_dst2(id int);").expect("create target table");
+    Spi::run("INSERT INTO pg_strict_e2e_rule_dst2 VALUES (1), (2);").expect("seed target table");
+    Spi::run(
+        "CREATE RULE pg_strict_e2e_prune AS ON INSERT TO pg_strict_e2e_rule_src2 \
+         DO ALSO DELETE FROM pg_strict_e2e_rule_dst2 WHERE id = NEW.id;",
+    )
+    .expect("create rule");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("INSERT INTO pg_strict_e2e_rule_src2 VALUES (1);").expect("insert should run");
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_rule_dst2").expect("count rows");
+    assert_eq!(remaining, Some(1));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_prepare_without_where_is_blocked() {
+    Spi::run("CREATE TABLE pg_strict_e2e_prepare(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    // Rejected by PREPARE itself; the statement never reaches EXECUTE.
+    let _ = Spi::run("PREPARE pg_strict_e2e_wipe AS DELETE FROM pg_strict_e2e_prepare;");
+}
+
+#[pg_test]
+fn test_e2e_planner_enforcement_accepts_filtered_view() {
+    Spi::run("CREATE TABLE pg_strict_e2e_view_base(id int, archived bool);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_view_base VALUES (1, true), (2, false);")
+        .expect("seed table");
+    Spi::run(
+        "CREATE VIEW pg_strict_e2e_archived AS \
+         SELECT * FROM pg_strict_e2e_view_base WHERE archived;",
+    )
+    .expect("create view");
+    Spi::run("SET pg_strict.enforcement_point = 'planner';").expect("set enforcement point");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run("DELETE FROM pg_strict_e2e_archived;").expect("view condition should count");
+    let remaining = Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_view_base")
+        .expect("count rows");
+    assert_eq!(remaining, Some(1));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_planner_enforcement_blocks_delete_without_where() {
+    Spi::run("CREATE TABLE pg_strict_e2e_planner(id int);").expect("create table");
+    Spi::run("SET pg_strict.enforcement_point = 'planner';").expect("set enforcement point");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_planner;");
+}
+
+#[pg_test]
+fn test_e2e_multi_statement_string_reports_only_offending_statement() {
+    Spi::run("CREATE TABLE pg_strict_e2e_multi(id int primary key, v int);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_multi VALUES (1, 0), (2, 0);").expect("seed table");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    Spi::run(
+        "UPDATE pg_strict_e2e_multi SET v = 1 WHERE id = 1; DELETE FROM pg_strict_e2e_multi;",
+    )
+    .expect("warned statements should run");
+
+    let queries = Spi::get_one::<String>(
+        "SELECT string_agg(operation || ': ' || query, ' | ' ORDER BY id) FROM strict.violations \
+         WHERE query LIKE '%pg_strict_e2e_multi%'",
+    )
+    .expect("read violations");
+    assert_eq!(queries.as_deref(), Some("DELETE: DELETE FROM pg_strict_e2e_multi"));
+}
+
+#[pg_test]
+#[should_panic(expected = "TRUNCATE of public.pg_strict_e2e_truncate detected")]
+fn test_e2e_guard_truncate_blocks_truncate() {
+    Spi::run("CREATE TABLE pg_strict_e2e_truncate(id int);").expect("create table");
+    Spi::run("SET pg_strict.guard_truncate = 'on';").expect("set truncate mode");
+    let _ = Spi::run("TRUNCATE pg_strict_e2e_truncate;");
+}
+
+#[pg_test]
+fn test_e2e_guard_truncate_off_allows_truncate() {
+    Spi::run("CREATE TABLE pg_strict_e2e_truncate_off(id int);").expect("create table");
+    Spi::run("SET pg_strict.guard_truncate = 'off';").expect("set truncate mode");
+    Spi::run("TRUNCATE pg_strict_e2e_truncate_off;").expect("truncate should run");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_explain_analyze_block() {
+    Spi::run("CREATE TABLE pg_strict_e2e_explain_block(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'off';").expect("set delete mode");
+    Spi::run("SET pg_strict.allow_explain_analyze = 'block';").expect("set explain mode");
+    let _ = Spi::run("EXPLAIN ANALYZE DELETE FROM pg_strict_e2e_explain_block;");
+}
+
+#[pg_test]
+fn test_e2e_explain_analyze_exempt() {
+    Spi::run("CREATE TABLE pg_strict_e2e_explain_exempt(id int);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_explain_exempt VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.allow_explain_analyze = 'exempt';").expect("set explain mode");
+    Spi::run("EXPLAIN ANALYZE DELETE FROM pg_strict_e2e_explain_exempt;")
+        .expect("exempt EXPLAIN ANALYZE should run");
+
+    let remaining = Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_explain_exempt")
+        .expect("count rows");
+    assert_eq!(remaining, Some(0));
+}
+
+#[pg_test]
+fn test_e2e_instead_nothing_rule_is_not_reported() {
+    Spi::run("CREATE TABLE pg_strict_e2e_rule_noop(id int);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_rule_noop VALUES (1), (2);").expect("seed table");
+    Spi::run(
+        "CREATE RULE pg_strict_e2e_keep AS ON DELETE TO pg_strict_e2e_rule_noop DO INSTEAD NOTHING;",
+    )
+    .expect("create rule");
+
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_rule_noop;").expect("rewritten delete should run");
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_rule_noop").expect("count rows");
+    assert_eq!(remaining, Some(2));
+}
+
+#[pg_test]
+#[should_panic(expected = "It was run by EXECUTE at line 1 of a DO block")]
+fn test_e2e_dynamic_execute_names_its_origin() {
+    Spi::run("CREATE TABLE pg_strict_e2e_dynamic(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    let _ = Spi::run("DO $$ BEGIN EXECUTE 'DELETE FROM pg_strict_e2e_dynamic'; END $$;");
+}
+
+#[pg_test]
+fn test_e2e_check_nested_top_level_skips_nested_statements() {
+    Spi::run("CREATE TABLE pg_strict_e2e_nested(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    // Statements run through SPI by the test function are nested.
+    Spi::run("SET pg_strict.check_nested = 'top_level';").expect("set nested policy");
+    Spi::run("DELETE FROM pg_strict_e2e_nested;").expect("nested delete should run");
+}
+
+#[pg_test]
+fn test_e2e_bound_parameter_counts_as_where_clause() {
+    Spi::run("CREATE TABLE pg_strict_e2e_params(id int);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_params VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    Spi::run_with_args("DELETE FROM pg_strict_e2e_params WHERE id = $1", &[1.into()])
+        .expect("parameterized delete should run");
+    Spi::run(
+        "PREPARE pg_strict_e2e_delete_one(int) AS \
+         DELETE FROM pg_strict_e2e_params WHERE id = $1;",
+    )
+    .expect("prepare parameterized delete");
+    Spi::run("EXECUTE pg_strict_e2e_delete_one(2);").expect("execute parameterized delete");
+
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_params").expect("count rows");
+    assert_eq!(remaining, Some(0));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_execute_checks_statement_prepared_while_off() {
+    Spi::run("CREATE TABLE pg_strict_e2e_execute(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'off';").expect("disable delete rule");
+    Spi::run("PREPARE pg_strict_e2e_wipe_later AS DELETE FROM pg_strict_e2e_execute;")
+        .expect("prepare while the rule is off");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("enable delete rule");
+    let _ = Spi::run("EXECUTE pg_strict_e2e_wipe_later;");
+}
+
+#[pg_test]
+fn test_e2e_sample_rate_zero_reports_nothing() {
+    Spi::run("CREATE TABLE pg_strict_e2e_sampled(id int);").expect("create table");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.sample_rate = 0;").expect("set sample rate");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_sampled;").expect("warned delete should run");
+
+    let recorded = Spi::get_one::<i64>(
+        "SELECT count(*) FROM strict.violations WHERE query LIKE '%pg_strict_e2e_sampled%'",
+    )
+    .expect("read violations");
+    assert_eq!(recorded, Some(0));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_sample_rate_never_skips_blocking() {
+    Spi::run("CREATE TABLE pg_strict_e2e_sampled_on(id int);").expect("create table");
+    Spi::run("SET pg_strict.sample_rate = 0;").expect("set sample rate");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_sampled_on;");
+}
+
+#[pg_test]
+fn test_e2e_rule_turns_mode_off_for_matching_table() {
+    Spi::run("CREATE TABLE pg_strict_e2e_ruled(id int);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_ruled VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run(
+        "SELECT pg_strict_add_rule(target => '*.pg_strict_e2e_ruled', \
+         operation => 'delete', mode => 'off');",
+    )
+    .expect("add rule");
+
+    Spi::run("DELETE FROM pg_strict_e2e_ruled;").expect("ruled delete should run");
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_ruled").expect("count rows");
+    assert_eq!(remaining, Some(0));
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE statement without WHERE clause detected")]
+fn test_e2e_rule_leaves_other_tables_alone() {
+    Spi::run("CREATE TABLE pg_strict_e2e_unruled(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SELECT pg_strict_add_rule(target => '*.pg_strict_e2e_ruled', mode => 'off');")
+        .expect("add rule");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_unruled;");
+}
+
+#[pg_test]
+#[should_panic(expected = "DELETE aborted after modifying more than 5 rows")]
+fn test_e2e_rule_sets_row_limit() {
+    Spi::run("CREATE TABLE pg_strict_e2e_rule_limit(id int);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_rule_limit SELECT generate_series(1, 10);")
+        .expect("seed table");
+    Spi::run(
+        "SELECT pg_strict_add_rule(target => '*.pg_strict_e2e_rule_limit', \
+         max_affected_rows => 5);",
+    )
+    .expect("add rule");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_rule_limit WHERE id > 0;");
+}
+
+#[pg_test]
+fn test_e2e_config_export_import_round_trip() {
+    Spi::run("SELECT pg_strict_add_rule(target => 'public.orders', mode => 'warn');")
+        .expect("add rule");
+    let exported = Spi::get_one::<pgrx::JsonB>("SELECT pg_strict_export_config()")
+        .expect("export config")
+        .expect("export should not be NULL");
+    assert_eq!(exported.0["version"], 1);
+    assert_eq!(exported.0["rules"][0]["target"], "public.orders");
+    assert!(exported.0["settings"]["pg_strict.require_where_on_delete"].is_string());
+
+    Spi::run("DELETE FROM strict.rules;").expect("clear rules");
+    let changes = Spi::get_one_with_args::<i64>(
+        "SELECT count(*) FROM pg_strict_import_config($1)",
+        &[exported.into()],
+    )
+    .expect("import config");
+    assert_eq!(changes, Some(1));
+    let mode = Spi::get_one::<String>("SELECT mode FROM strict.rules WHERE target = 'public.orders'")
+        .expect("read imported rule");
+    assert_eq!(mode.as_deref(), Some("warn"));
+}
+
+#[pg_test]
+fn test_e2e_config_diff_reports_changed_setting_and_rule() {
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_snapshot AS SELECT pg_strict_config_snapshot() AS s;")
+        .expect("take snapshot");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("change delete mode");
+    Spi::run("SELECT pg_strict_add_rule(target => 'public.invoices', mode => 'off');")
+        .expect("add rule");
+
+    let diff = Spi::get_one::<String>(
+        "SELECT string_agg(section || ':' || item || ':' || coalesce(before, '-') || '>' \
+         || CASE section WHEN 'rules' THEN after::jsonb->>'mode' ELSE after END, ',' \
+         ORDER BY section, item) \
+         FROM pg_strict_config_diff((SELECT s FROM pg_strict_e2e_snapshot), \
+                                    pg_strict_config_snapshot())",
+    )
+    .expect("diff snapshots");
+    assert_eq!(
+        diff.as_deref(),
+        Some("rules:public.invoices:->off,settings:pg_strict.require_where_on_delete:warn>on")
+    );
+}
+
+#[pg_test]
+#[should_panic(expected = "invalid value for parameter \"pg_strict.maintenance_windows\"")]
+fn test_e2e_malformed_maintenance_window_is_rejected_at_set() {
+    Spi::run("SET pg_strict.maintenance_windows = 'sat 02:00-04:00, someday 25:00-26:00';")
+        .unwrap();
+}
+
+#[pg_test]
+#[should_panic(expected = "invalid value for parameter \"pg_strict.escalate_at\"")]
+fn test_e2e_invalid_escalation_time_is_rejected_at_set() {
+    Spi::run("SET pg_strict.escalate_at = 'next tuesday';").unwrap();
+}
+
+#[pg_test]
+fn test_e2e_notice_and_log_modes_let_the_statement_run() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_quiet(id int primary key);")
+        .expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_quiet VALUES (1), (2);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'notice';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_e2e_quiet;").expect("delete should only raise a notice");
+    Spi::run("SET pg_strict.require_where_on_update = 'log';").expect("set update mode");
+    Spi::run("UPDATE pg_strict_e2e_quiet SET id = id + 1;").expect("update should only be logged");
+}
+
+#[pg_test]
+#[should_panic(expected = "UPDATE statement without WHERE clause detected")]
+fn test_e2e_notice_mode_escalates_to_on_after_deadline() {
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_notice_escalate(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_update = 'notice';").expect("set update mode");
+    Spi::run("SET pg_strict.escalate_at = '2000-01-01 00:00:00+00';").expect("set escalate_at");
+    let _ = Spi::run("UPDATE pg_strict_e2e_notice_escalate SET id = id;");
+}
+
+#[pg_test]
+#[should_panic(expected = "pg_strict_e2e_templated: blocked DELETE, see the runbook")]
+fn test_e2e_message_template_replaces_the_message() {
+    Spi::run("CREATE TABLE pg_strict_e2e_templated(id int primary key);").expect("create table");
+    Spi::run("SET pg_strict.message_template = '%table%: blocked %operation%, see the runbook.';")
+        .expect("set message template");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    let _ = Spi::run("DELETE FROM pg_strict_e2e_templated;");
+}
+
+#[pg_test]
+#[should_panic(expected = "invalid value for parameter \"pg_strict.error_code\"")]
+fn test_e2e_error_code_must_be_an_error_sqlstate() {
+    Spi::run("SET pg_strict.error_code = '01000';").unwrap();
+}
+
+#[pg_test]
+fn test_e2e_error_code_accepts_a_custom_class() {
+    Spi::run("SET pg_strict.error_code = 'PGS01';").expect("set error code");
+    assert_eq!(crate::guc::error_code().as_deref(), Some("PGS01"));
+}
+
+#[pg_test]
+fn test_e2e_rule_precedence_prefers_most_specific_table_rule() {
+    Spi::run(
+        "CREATE TABLE pg_strict_e2e_payments(id int);
+         CREATE TABLE pg_strict_e2e_events(id int);
+         INSERT INTO pg_strict_e2e_payments VALUES (1);
+         INSERT INTO pg_strict_e2e_events VALUES (1);",
+    )
+    .expect("create tables");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    // Added in the reverse of their precedence, so id order alone would pick the wrong ones.
+    Spi::run(
+        "SELECT pg_strict_add_rule(target => '*', mode => 'off');
+         SELECT pg_strict_add_rule(target => '*.pg_strict_e2e_*', mode => 'on');
+         SELECT pg_strict_add_rule(target => 'public.pg_strict_e2e_events', operation => 'delete',
+                                   mode => 'warn');
+         SELECT pg_strict_add_rule(target => 'public.pg_strict_e2e_events',
+                                   max_affected_rows => 100);",
+    )
+    .expect("add rules");
+
+    Spi::run("DELETE FROM pg_strict_e2e_events;").expect("events delete should only warn");
+    let payments = Spi::run(
+        "DO $$
+         BEGIN
+             DELETE FROM pg_strict_e2e_payments;
+             RAISE EXCEPTION 'DELETE was not blocked';
+         EXCEPTION WHEN SQLSTATE '2F003' THEN
+             NULL;
+         END $$;",
+    );
+    assert!(payments.is_ok(), "payments delete should be blocked by the pattern rule");
+}
+
+#[pg_test]
+fn test_e2e_lint_reports_findings_without_running_script() {
+    Spi::run("CREATE TABLE pg_strict_e2e_lint(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_e2e_lint VALUES (1);").expect("seed table");
+    Spi::run("SET pg_strict.require_where_on_update = 'off';").expect("set update mode");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+    Spi::run("SET pg_strict.guard_truncate = 'warn';").expect("set truncate mode");
+
+    let findings = Spi::get_one::<String>(
+        "SELECT string_agg(statement_index || ':' || operation || ':' || target_table || ':' \
+                           || rule || ':' || severity, ',' ORDER BY statement_index) \
+         FROM pg_strict_lint('UPDATE pg_strict_e2e_lint SET id = 2; \
+                              DELETE FROM pg_strict_e2e_lint WHERE id = 1; \
+                              DELETE FROM pg_strict_e2e_lint; \
+                              TRUNCATE pg_strict_e2e_lint;')",
+    )
+    .expect("lint script");
+    assert_eq!(
+        findings.as_deref(),
+        Some(
+            "3:DELETE:public.pg_strict_e2e_lint:pg_strict.require_where_on_delete:error,\
+             4:TRUNCATE:public.pg_strict_e2e_lint:pg_strict.guard_truncate:warning"
+        )
+    );
+    let fix = Spi::get_one::<String>(
+        "SELECT suggested_fix FROM pg_strict_lint('DELETE FROM pg_strict_e2e_lint;')",
+    )
+    .expect("lint delete");
+    assert!(fix.unwrap_or_default().contains("TRUNCATE public.pg_strict_e2e_lint"));
+
+    let remaining =
+        Spi::get_one::<i64>("SELECT count(*) FROM pg_strict_e2e_lint").expect("count rows");
+    assert_eq!(remaining, Some(1));
+}
+
+#[pg_test]
+fn test_e2e_check_batch_returns_one_verdict_per_statement() {
+    Spi::run("CREATE TABLE pg_strict_e2e_batch(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    let verdicts = Spi::get_one::<String>(
+        "SELECT string_agg(statement_index || ':' || verdict || ':' || array_to_string(rules, '+'), \
+                           ',' ORDER BY statement_index) \
+         FROM pg_strict_check_batch(ARRAY[
+             'DELETE FROM pg_strict_e2e_batch WHERE id = 1',
+             'UPDATE pg_strict_e2e_batch SET id = 2',
+             'DELETE FROM pg_strict_e2e_batch',
+             'DELET FROM pg_strict_e2e_batch',
+             NULL])",
+    )
+    .expect("check batch");
+    assert_eq!(
+        verdicts.as_deref(),
+        Some(
+            "1:allowed:,2:warned:pg_strict.require_where_on_update,\
+             3:blocked:pg_strict.require_where_on_delete,4:invalid:,5:invalid:"
+        )
+    );
+}
+
+#[pg_test]
+#[should_panic(expected = "contains UPDATE or DELETE statements without WHERE clause")]
+fn test_e2e_lint_functions_rejects_whereless_body() {
+    Spi::run("CREATE TABLE pg_strict_e2e_lint_fn(id int);").expect("create table");
+    Spi::run("SET pg_strict.lint_functions = 'on';").expect("set lint mode");
+    let _ = Spi::run(
+        "CREATE FUNCTION pg_strict_e2e_purge() RETURNS void LANGUAGE plpgsql AS $$
+         BEGIN
+             DELETE FROM pg_strict_e2e_lint_fn;
+         END
+         $$;",
+    );
+}
+
+#[pg_test]
+fn test_e2e_lint_function_counts_statements() {
+    Spi::run("CREATE TABLE pg_strict_e2e_lint_fn(id int);").expect("create table");
+    Spi::run(
+        "CREATE FUNCTION pg_strict_e2e_sql_purge() RETURNS void LANGUAGE sql AS $$
+             UPDATE pg_strict_e2e_lint_fn SET id = 0;
+             DELETE FROM pg_strict_e2e_lint_fn WHERE id = 1;
+             DELETE FROM pg_strict_e2e_lint_fn;
+         $$;",
+    )
+    .expect("lint_functions is off by default");
+    let count = Spi::get_one::<i32>(
+        "SELECT pg_strict_lint_function('pg_strict_e2e_sql_purge()'::regprocedure)",
+    )
+    .expect("lint function");
+    assert_eq!(count, Some(2));
+}
+
+#[pg_test]
+fn test_e2e_explain_describes_each_statement() {
+    Spi::run("CREATE TABLE pg_strict_e2e_explain(id int, flag bool);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_update = 'on';").expect("set update mode");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+
+    let explained = Spi::get_one::<String>(
+        "SELECT string_agg(concat_ws('|', statement_index, operation, target_schema, target_table, \
+                                     has_where, where_text, uses_from_or_using, verdict), \
+                           ',' ORDER BY statement_index) \
+         FROM pg_strict_explain('UPDATE pg_strict_e2e_explain SET flag = true; \
+                                 DELETE FROM pg_strict_e2e_explain e USING pg_class c \
+                                     WHERE c.oid = e.id RETURNING e.id; \
+                                 DELETE FROM pg_strict_e2e_explain; \
+                                 SELECT 1')",
+    )
+    .expect("explain statements");
+    assert_eq!(
+        explained.as_deref(),
+        Some(
+            "1|UPDATE|public|pg_strict_e2e_explain|false|false|blocked,\
+             2|DELETE|public|pg_strict_e2e_explain|true|c.oid = e.id|true|allowed,\
+             3|DELETE|public|pg_strict_e2e_explain|false|false|warned,\
+             4|SELECT|allowed"
+        )
+    );
+}
+
+#[pg_test]
+fn test_e2e_is_safe_follows_effective_configuration() {
+    Spi::run("CREATE TABLE pg_strict_e2e_is_safe(id int);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    let is_safe = |query: &str| {
+        Spi::get_one_with_args::<bool>("SELECT pg_strict_is_safe($1)", &[query.into()])
+            .expect("pg_strict_is_safe should not raise")
+    };
+
+    assert_eq!(is_safe("DELETE FROM pg_strict_e2e_is_safe"), Some(true));
+    Spi::run(
+        "SELECT pg_strict_add_rule(target => 'public.pg_strict_e2e_is_safe', operation => 'delete', \
+                                   mode => 'on');",
+    )
+    .expect("add rule");
+    assert_eq!(is_safe("DELETE FROM pg_strict_e2e_is_safe"), Some(false));
+    assert_eq!(is_safe("DELETE FROM pg_strict_e2e_is_safe WHERE id = 1"), Some(true));
+    assert_eq!(is_safe("DELET FROM pg_strict_e2e_is_safe"), Some(false));
+    assert_eq!(is_safe("DELETE FROM pg_strict_e2e_missing WHERE id = 1"), Some(false));
+}
+
+#[pg_test]
+fn test_e2e_audit_functions_lists_unconditional_statements() {
+    Spi::run("CREATE TABLE pg_strict_e2e_audit_fn(id int);").expect("create table");
+    Spi::run(
+        "CREATE FUNCTION pg_strict_e2e_audit_reset() RETURNS void LANGUAGE plpgsql AS $$
+         BEGIN
+             DELETE FROM pg_strict_e2e_audit_fn WHERE id = 1;
+             UPDATE pg_strict_e2e_audit_fn SET id = 0;
+         END
+         $$;",
+    )
+    .expect("create function");
+
+    let found = Spi::get_one::<String>(
+        "SELECT string_agg(concat_ws('|', routine, language, line, operation, statement), ',') \
+         FROM pg_strict_audit_functions() WHERE routine LIKE '%pg_strict_e2e_audit%'",
+    )
+    .expect("audit functions");
+    assert_eq!(
+        found.as_deref(),
+        Some("pg_strict_e2e_audit_reset()|plpgsql|4|UPDATE|UPDATE pg_strict_e2e_audit_fn SET id = 0")
+    );
+}
+
+#[pg_test]
+fn test_e2e_audit_triggers_flags_dml_on_other_tables() {
+    Spi::run(
+        "CREATE TABLE pg_strict_e2e_trg_orders(id int);
+         CREATE TABLE pg_strict_e2e_trg_totals(total int);
+         CREATE FUNCTION pg_strict_e2e_trg_refresh() RETURNS trigger LANGUAGE plpgsql AS $$
+         BEGIN
+             UPDATE pg_strict_e2e_trg_orders SET id = id;
+             DELETE FROM pg_strict_e2e_trg_totals;
+             RETURN NULL;
+         END
+         $$;
+         CREATE TRIGGER pg_strict_e2e_trg AFTER INSERT ON pg_strict_e2e_trg_orders
+         FOR EACH STATEMENT EXECUTE FUNCTION pg_strict_e2e_trg_refresh();",
+    )
+    .expect("create trigger");
+
+    let found = Spi::get_one::<String>(
+        "SELECT string_agg(concat_ws('|', table_name, trigger_name, line, operation, target_table), ',') \
+         FROM pg_strict_audit_triggers() WHERE trigger_name = 'pg_strict_e2e_trg'",
+    )
+    .expect("audit triggers");
+    assert_eq!(
+        found.as_deref(),
+        Some("public.pg_strict_e2e_trg_orders|pg_strict_e2e_trg|4|DELETE|public.pg_strict_e2e_trg_totals")
+    );
+}
+
+#[pg_test]
+fn test_e2e_audit_rules_flags_unconditional_actions() {
+    Spi::run(
+        "CREATE TABLE pg_strict_e2e_rule_orders(id int);
+         CREATE TABLE pg_strict_e2e_rule_archive(id int);
+         CREATE RULE pg_strict_e2e_rule_wipe AS ON DELETE TO pg_strict_e2e_rule_orders
+             DO ALSO DELETE FROM pg_strict_e2e_rule_archive;
+         CREATE RULE pg_strict_e2e_rule_match AS ON UPDATE TO pg_strict_e2e_rule_orders
+             DO ALSO DELETE FROM pg_strict_e2e_rule_archive a WHERE a.id = old.id;",
+    )
+    .expect("create rules");
+
+    let found = Spi::get_one::<String>(
+        "SELECT string_agg(concat_ws('|', table_name, rule_name, event, is_instead, operation, \
+                                     target_table), ',') \
+         FROM pg_strict_audit_rules() WHERE rule_name LIKE 'pg_strict_e2e_rule_%'",
+    )
+    .expect("audit rules");
+    assert_eq!(
+        found.as_deref(),
+        Some(
+            "public.pg_strict_e2e_rule_orders|pg_strict_e2e_rule_wipe|DELETE|false|DELETE|\
+             public.pg_strict_e2e_rule_archive"
+        )
+    );
+}
+
+#[pg_test]
+fn test_e2e_validate_migration_grades_dml_and_ddl() {
+    Spi::run("CREATE TABLE pg_strict_e2e_migrate(id int, note text);").expect("create table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    let findings = Spi::get_one::<String>(
+        "SELECT string_agg(statement_index || ':' || severity || ':' || rule, ',') \
+         FROM pg_strict_validate_migration('
+             CREATE TABLE pg_strict_e2e_migrate_new(id int);
+             CREATE INDEX ON pg_strict_e2e_migrate_new(id);
+             CREATE INDEX ON pg_strict_e2e_migrate(id);
+             CREATE INDEX CONCURRENTLY ON pg_strict_e2e_migrate(note);
+             ALTER TABLE pg_strict_e2e_migrate ALTER COLUMN note SET NOT NULL,
+                                               ALTER COLUMN id TYPE bigint;
+             DELETE FROM pg_strict_e2e_migrate;')",
+    )
+    .expect("validate migration");
+    assert_eq!(
+        findings.as_deref(),
+        Some(
+            "5:blocker:column_type_change,6:blocker:pg_strict.require_where_on_delete,\
+             3:warning:index_without_concurrently,5:warning:set_not_null"
+        )
+    );
+}
+
+struct ForbidPluginTarget;
+
+impl crate::StrictRule for ForbidPluginTarget {
+    fn name(&self) -> &str {
+        "forbid_plugin_target"
+    }
+
+    fn mode(&self) -> crate::StrictMode {
+        crate::StrictMode::On
+    }
+
+    fn check(&self, _query: *mut pg_sys::Query, statement: &str) -> Option<crate::Violation> {
+        statement
+            .contains("pg_strict_e2e_plugin_target")
+            .then(|| crate::Violation {
+                message: "pg_strict_e2e_plugin_target is read-only".to_string(),
+                ..Default::default()
+            })
+    }
+}
+
+#[pg_test]
+#[should_panic(expected = "pg_strict_e2e_plugin_target is read-only")]
+fn test_e2e_registered_rule_blocks_statement() {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| crate::register_rule(Box::new(ForbidPluginTarget)));
+
+    Spi::run("CREATE TEMP TABLE pg_strict_e2e_plugin_other(id int);").expect("create temp table");
+    Spi::run("INSERT INTO pg_strict_e2e_plugin_other VALUES (1);")
+        .expect("other tables are unaffected");
+    let _ = Spi::run("CREATE TEMP TABLE pg_strict_e2e_plugin_target(id int);");
+}
+
+#[pg_test]
+#[should_panic(expected = "pg_strict: pg_sleep is not allowed here")]
+fn test_e2e_custom_rule_blocks_matching_statement() {
+    Spi::run(
+        r"INSERT INTO strict.custom_rules (pattern, action, message)
+          VALUES ('\bpg_sleep\s*\(', 'block', 'pg_sleep is not allowed here');",
+    )
+    .expect("add custom rule");
+    Spi::run("SELECT 'pg_sleep(1)';").expect("literals are not matched");
+    let _ = Spi::run("SELECT PG_SLEEP(0);");
+}
+
+#[pg_test]
+#[should_panic(expected = "pg_strict: invalid pattern")]
+fn test_e2e_custom_rule_rejects_invalid_pattern() {
+    let _ = Spi::run(
+        "INSERT INTO strict.custom_rules (pattern, message) VALUES ('pg_sleep(', 'unbalanced');",
+    );
+}
+
+#[pg_test]
+fn test_e2e_analyze_describes_tables_ctes_and_findings() {
+    Spi::run("CREATE TABLE pg_strict_e2e_an_orders(id int, customer_id int);")
+        .expect("create orders");
+    Spi::run("CREATE TABLE pg_strict_e2e_an_customers(id int, closed bool);")
+        .expect("create customers");
+    Spi::run("SET pg_strict.require_where_on_update = 'on';").expect("set update mode");
+
+    let analysis = Spi::get_one::<pgrx::JsonB>(
+        "SELECT pg_strict_analyze('WITH gone AS (DELETE FROM pg_strict_e2e_an_customers WHERE closed RETURNING id)
+             DELETE FROM pg_strict_e2e_an_orders o USING gone JOIN pg_strict_e2e_an_customers c ON c.id = gone.id
+             WHERE o.customer_id = gone.id;
+         UPDATE pg_strict_e2e_an_orders SET customer_id = 0')",
+    )
+    .expect("analyze")
+    .expect("analysis")
+    .0;
+
+    assert_eq!(analysis["verdict"], "blocked");
+    let delete = &analysis["statements"][0];
+    assert_eq!(delete["command"], "DELETE");
+    assert_eq!(delete["verdict"], "allowed");
+    assert_eq!(delete["predicates"]["where"], "o.customer_id = gone.id");
+    let tables: Vec<(&str, &str)> = delete["tables"]
+        .as_array()
+        .expect("tables")
+        .iter()
+        .map(|table| {
+            (
+                table["table"].as_str().unwrap_or_default(),
+                table["role"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        tables,
+        vec![
+            ("pg_strict_e2e_an_orders", "target"),
+            ("gone", "source"),
+            ("pg_strict_e2e_an_customers", "source")
+        ]
+    );
+    assert_eq!(delete["ctes"][0]["name"], "gone");
+    assert_eq!(delete["ctes"][0]["predicates"]["has_where"], true);
+
+    let update = &analysis["statements"][1];
+    assert_eq!(update["predicates"]["has_where"], false);
+    assert_eq!(update["findings"][0]["rule"], "pg_strict.require_where_on_update");
+    assert_eq!(update["findings"][0]["severity"], "error");
+}
+
+#[pg_test]
+fn test_e2e_blocked_violation_is_recorded_by_delivery_worker() {
+    Spi::run("CREATE TABLE pg_strict_e2e_delivery(id int);").expect("create table");
+    Spi::run("SET pg_strict.audit = on;").expect("enable audit");
+    Spi::run("SET pg_strict.require_where_on_delete = 'on';").expect("set delete mode");
+
+    let blocked = crate::subxact::try_in_subtransaction(|| {
+        Spi::run("DELETE FROM pg_strict_e2e_delivery;").ok()
+    });
+    assert!(blocked.is_none());
+
+    // Nothing else runs in this session; the row must come from the worker.
+    let mut recorded = None;
+    for _ in 0..100 {
+        recorded = Spi::get_one::<String>(
+            "SELECT mode || ': ' || operation FROM strict.violations \
+             WHERE query = 'DELETE FROM pg_strict_e2e_delivery'",
+        )
+        .expect("read violations");
+        if recorded.is_some() {
+            break;
+        }
+        Spi::run("SELECT pg_sleep(0.1);").expect("sleep");
+    }
+    assert_eq!(recorded.as_deref(), Some("on: DELETE"));
+}
@@ -313,3 +313,607 @@ fn test_update_with_from_without_where_is_flagged() {
         analyze_missing_where_operations("UPDATE users SET active = false FROM accounts;");
     assert_eq!(violations, vec![Operation::Update]);
 }
+
+#[pg_test]
+fn test_exemption_glob_match() {
+    assert!(exemptions::glob_match("pg_cron", "pg_cron"));
+    assert!(exemptions::glob_match("pg_cron*", "pg_cron launcher"));
+    assert!(exemptions::glob_match("pgAgent*", "pgagent - job 12"));
+    assert!(exemptions::glob_match("nightly_?", "nightly_1"));
+    assert!(exemptions::glob_match("*cleanup*", "staging-cleanup-job"));
+    assert!(!exemptions::glob_match("pg_cron", "pg_cron launcher"));
+    assert!(!exemptions::glob_match("nightly_?", "nightly_12"));
+    assert!(!exemptions::glob_match("psql", "pg_cron"));
+}
+
+#[pg_test]
+fn test_trusted_function_pattern_normalization() {
+    assert_eq!(
+        exemptions::normalize_function_pattern("maintenance.purge_expired()"),
+        "maintenance.purge_expired"
+    );
+    assert_eq!(
+        exemptions::normalize_function_pattern(" purge_expired "),
+        "public.purge_expired"
+    );
+    assert_eq!(
+        exemptions::normalize_function_pattern("maintenance.*"),
+        "maintenance.*"
+    );
+}
+
+#[pg_test]
+fn test_preset_bundles() {
+    use crate::guc::{StrictMode, StrictPreset};
+
+    assert_eq!(
+        policy::preset_mode(StrictPreset::Production, policy::DELETE_MODE_GUC),
+        Some(StrictMode::On)
+    );
+    assert_eq!(
+        policy::preset_mode(StrictPreset::Staging, policy::UPDATE_MODE_GUC),
+        Some(StrictMode::Warn)
+    );
+    assert_eq!(
+        policy::preset_mode(StrictPreset::Development, policy::UPDATE_MODE_GUC),
+        Some(StrictMode::Off)
+    );
+    assert_eq!(
+        policy::preset_mode(StrictPreset::None, policy::DELETE_MODE_GUC),
+        None
+    );
+    assert_eq!(
+        policy::preset_mode(StrictPreset::Production, policy::GUARD_TRUNCATE_GUC),
+        Some(StrictMode::On)
+    );
+    assert_eq!(
+        policy::preset_threshold(StrictPreset::Staging, policy::MAX_AFFECTED_PERCENT_GUC),
+        Some(90.0)
+    );
+    assert_eq!(
+        policy::preset_threshold(StrictPreset::Development, policy::MAX_AFFECTED_ROWS_GUC),
+        None
+    );
+}
+
+#[pg_test]
+fn test_snooze_interval_conversion() {
+    assert_eq!(bypass::interval_usecs(0, 0, 90_000_000), 90_000_000);
+    assert_eq!(bypass::interval_usecs(0, 1, 0), 86_400_000_000);
+    assert_eq!(bypass::interval_usecs(1, 0, 0), 30 * 86_400_000_000);
+}
+
+#[pg_test]
+#[should_panic(expected = "snooze duration exceeds pg_strict.max_snooze_duration")]
+fn test_snooze_longer_than_maximum_is_rejected() {
+    Spi::run("SET pg_strict.max_snooze_duration = '10min';").expect("set max snooze");
+    Spi::run("SELECT pg_strict_snooze('1 hour', 'too long');").expect("snooze");
+}
+
+#[pg_test]
+fn test_normalize_statement() {
+    use crate::analyzer::normalize_statement;
+
+    assert_eq!(
+        normalize_statement("  DELETE FROM\n   staging.events ;  "),
+        "DELETE FROM staging.events"
+    );
+    assert_eq!(
+        normalize_statement("UPDATE t SET a = 1;"),
+        normalize_statement("UPDATE t\tSET a = 1")
+    );
+}
+
+#[pg_test]
+#[should_panic(expected = "must be approved by a role other than")]
+fn test_self_approval_is_rejected() {
+    let request_id = api::pg_strict_request_approval("DELETE FROM staging.events");
+    api::pg_strict_approve(request_id);
+}
+
+#[pg_test]
+fn test_queryid_allowlist_add_and_remove() {
+    assert!(api::pg_strict_allow_queryid(424242));
+    assert!(api::pg_strict_allowed_queryids().any(|id| id == 424242));
+    assert!(api::pg_strict_disallow_queryid(424242));
+    assert!(!api::pg_strict_disallow_queryid(424242));
+}
+
+#[cfg(not(feature = "pg13"))]
+#[pg_test]
+#[should_panic(expected = "compute_query_id is off")]
+fn test_queryid_allowlist_requires_query_ids() {
+    Spi::run("SET compute_query_id = off;").expect("disable query ids");
+    api::pg_strict_allow_queryid(424242);
+}
+
+// No module enables query identifiers in the test server, so `auto` computes none.
+#[cfg(not(feature = "pg13"))]
+#[pg_test]
+#[should_panic(expected = "or auto without a module")]
+fn test_queryid_allowlist_requires_query_ids_under_auto() {
+    Spi::run("SET compute_query_id = auto;").expect("leave query ids to modules");
+    api::pg_strict_allow_queryid(424242);
+}
+
+#[pg_test]
+fn test_maintenance_window_parsing() {
+    let saturday = schedule::parse_window("sat 02:00-04:00").expect("valid window");
+    assert!(saturday.contains(6, 2 * 60));
+    assert!(saturday.contains(6, 3 * 60 + 59));
+    assert!(!saturday.contains(6, 4 * 60));
+    assert!(!saturday.contains(5, 3 * 60));
+
+    let overnight = schedule::parse_window("mon-fri 23:00-01:00").expect("valid window");
+    assert!(overnight.contains(1, 23 * 60 + 30));
+    assert!(overnight.contains(5, 30));
+    assert!(!overnight.contains(0, 23 * 60 + 30));
+    assert!(!overnight.contains(3, 12 * 60));
+
+    let daily = schedule::parse_window("01:00-02:00").expect("valid window");
+    assert!(daily.contains(0, 90));
+
+    assert!(schedule::parse_window("sat").is_err());
+    assert!(schedule::parse_window("someday 01:00-02:00").is_err());
+    assert!(schedule::parse_window("sat 25:00-26:00").is_err());
+}
+
+#[pg_test]
+fn test_in_any_window_skips_malformed_entries() {
+    let windows = schedule::parse_windows("bogus, * 00:00-24:00");
+    assert_eq!(windows.len(), 1);
+    assert!(schedule::in_any_window(&windows, (3, 600)));
+    assert!(!schedule::in_any_window(
+        &schedule::parse_windows("sun 01:00-02:00"),
+        (3, 90)
+    ));
+}
+
+#[pg_test]
+fn test_notify_json_string_escapes_payload_values() {
+    assert_eq!(notify::json_string("plain"), "\"plain\"");
+    assert_eq!(
+        notify::json_string("quo\"te\\slash\nline"),
+        "\"quo\\\"te\\\\slash\\nline\""
+    );
+    assert_eq!(notify::json_string("\u{1}"), "\"\\u0001\"");
+}
+
+#[pg_test]
+fn test_metrics_render_prometheus_text() {
+    let body = metrics::render();
+    assert!(body.contains("# TYPE pg_strict_violations_total counter\n"));
+    assert!(body.contains("pg_strict_violations_total{action=\"blocked\"} "));
+    assert!(body.contains("pg_strict_check_seconds_total "));
+}
+
+#[pg_test]
+fn test_logging_iso8601_utc() {
+    assert_eq!(logging::iso8601_utc(0), "2000-01-01T00:00:00.000000Z");
+    // 2024-02-29 12:34:56.789 UTC, a leap day.
+    assert_eq!(
+        logging::iso8601_utc(762_525_296_789_000),
+        "2024-02-29T12:34:56.789000Z"
+    );
+    assert_eq!(logging::iso8601_utc(-1), "1999-12-31T23:59:59.999999Z");
+}
+
+#[pg_test]
+fn test_logging_expand_filename() {
+    // 2024-02-29 12:34:56.789 UTC
+    let timestamp = 762_525_296_789_000;
+    assert_eq!(
+        logging::expand_filename("pg_strict-%Y-%m-%d.log", timestamp),
+        "pg_strict-2024-02-29.log"
+    );
+    assert_eq!(
+        logging::expand_filename("strict_%H%M_100%%_%q", timestamp),
+        "strict_1234_100%_%q"
+    );
+}
+
+#[pg_test]
+fn test_redact_literals_replaces_constants() {
+    assert_eq!(
+        analyzer::redact_literals("UPDATE users SET email = 'a@b.com', age = 42"),
+        "UPDATE users SET email = $1, age = $2"
+    );
+    assert_eq!(
+        analyzer::redact_literals("UPDATE t SET s = E'it\\'s', n = 1.5e-3 WHERE id = $1"),
+        "UPDATE t SET s = $2, n = $3 WHERE id = $1"
+    );
+    assert_eq!(
+        analyzer::redact_literals("UPDATE t SET v = $$secret$$, w = 'x'::text"),
+        "UPDATE t SET v = $1, w = $2::text"
+    );
+    assert_eq!(
+        analyzer::redact_literals("DELETE FROM \"Tab 1\" /* 'x' */ WHERE col2 = x'1F'"),
+        "DELETE FROM \"Tab 1\"   WHERE col2 = $1"
+    );
+}
+
+#[pg_test]
+fn test_affected_rows_select_rewrites_dml() {
+    assert_eq!(
+        analyzer::affected_rows_select(
+            "DELETE FROM orders o USING customers c WHERE c.id = o.customer_id RETURNING o.id;",
+            "o"
+        ),
+        Ok("SELECT o.* FROM orders o, customers c WHERE c.id = o.customer_id".to_string())
+    );
+    assert_eq!(
+        analyzer::affected_rows_select(
+            "UPDATE public.t SET v = (SELECT max(v) FROM u WHERE u.id = 1) WHERE id > 5",
+            "public.t"
+        ),
+        Ok("SELECT public.t.* FROM public.t WHERE id > 5".to_string())
+    );
+    assert!(analyzer::affected_rows_select("DELETE FROM t WHERE CURRENT OF c", "t").is_err());
+}
+
+#[pg_test]
+fn test_trigger_summary_lists_row_triggers() {
+    Spi::run(
+        "CREATE TABLE pg_strict_trigger_log(id int); \
+         CREATE TABLE pg_strict_triggered(id int primary key); \
+         CREATE FUNCTION pg_strict_trigger_fn() RETURNS trigger LANGUAGE plpgsql AS \
+             $$ BEGIN INSERT INTO pg_strict_trigger_log VALUES (OLD.id); RETURN OLD; END $$; \
+         CREATE TRIGGER logs_delete BEFORE DELETE ON pg_strict_triggered \
+             FOR EACH ROW EXECUTE FUNCTION pg_strict_trigger_fn(); \
+         CREATE TRIGGER per_statement AFTER DELETE ON pg_strict_triggered \
+             FOR EACH STATEMENT EXECUTE FUNCTION pg_strict_trigger_fn();",
+    )
+    .expect("create triggers");
+    let relid = Spi::get_one::<pg_sys::Oid>("SELECT 'pg_strict_triggered'::regclass::oid")
+        .expect("resolve table")
+        .expect("table oid");
+
+    assert_eq!(
+        triggers::summary(relid, Operation::Delete).as_deref(),
+        Some("logs_delete (runs DML)")
+    );
+    assert_eq!(triggers::summary(relid, Operation::Update), None);
+}
+
+#[pg_test]
+fn test_reset_stats_zeroes_counters() {
+    Spi::run("CREATE TEMP TABLE pg_strict_reset_stats(id int primary key);")
+        .expect("create temp table");
+    Spi::run("SET pg_strict.require_where_on_delete = 'warn';").expect("set delete mode");
+    Spi::run("DELETE FROM pg_strict_reset_stats;").expect("warned delete should run");
+
+    assert!(api::pg_strict_reset_stats());
+
+    let warned = Spi::get_one::<i64>("SELECT violations_warned FROM pg_strict_stats()")
+        .expect("read stats");
+    assert_eq!(warned, Some(0));
+    assert_eq!(api::pg_strict_top_offenders(10).count(), 0);
+}
+
+#[pg_test]
+#[should_panic(expected = "pg_stat_statements is not installed in this database")]
+fn test_audit_statements_requires_pg_stat_statements() {
+    let _ = api::pg_strict_audit_statements().count();
+}
+
+#[pg_test]
+fn test_active_violations_excludes_own_backend() {
+    // The calling backend is the only one running a statement during the test.
+    assert_eq!(api::pg_strict_active_violations().count(), 0);
+}
+
+#[pg_test]
+fn test_relation_size_kb_reads_relpages() {
+    Spi::run("CREATE TABLE pg_strict_relsize(id int primary key);").expect("create table");
+    Spi::run("INSERT INTO pg_strict_relsize SELECT generate_series(1, 1000);")
+        .expect("seed table");
+    Spi::run("ANALYZE pg_strict_relsize;").expect("analyze table");
+    let relid = Spi::get_one::<pg_sys::Oid>("SELECT 'pg_strict_relsize'::regclass::oid")
+        .expect("resolve table")
+        .expect("table oid");
+    let relpages = Spi::get_one::<i32>("SELECT relpages FROM pg_class WHERE oid = 'pg_strict_relsize'::regclass")
+        .expect("read relpages")
+        .unwrap_or(0) as i64;
+
+    assert_eq!(estimate::relation_size_kb(relid), relpages * pg_sys::BLCKSZ as i64 / 1024);
+}
+
+#[pg_test]
+fn test_group_digits() {
+    assert_eq!(report::group_digits(0), "0");
+    assert_eq!(report::group_digits(999), "999");
+    assert_eq!(report::group_digits(1000), "1,000");
+    assert_eq!(report::group_digits(1204543), "1,204,543");
+}
+
+#[pg_test]
+fn test_transaction_summary_groups_by_table() {
+    let flagged = [
+        summary::FlaggedStatement {
+            operation: Operation::Delete,
+            table: Some("public.orders".to_string()),
+            executor_depth: 0,
+            rows: Some(1200),
+        },
+        summary::FlaggedStatement {
+            operation: Operation::Update,
+            table: Some("public.users".to_string()),
+            executor_depth: 0,
+            rows: Some(12),
+        },
+        summary::FlaggedStatement {
+            operation: Operation::Delete,
+            table: Some("public.orders".to_string()),
+            executor_depth: 0,
+            rows: Some(4),
+        },
+    ];
+    assert_eq!(
+        summary::describe(&flagged).as_deref(),
+        Some(
+            "this transaction ran 3 flagged statements modifying 1,216 rows: \
+             DELETE on public.orders (2 statements, 1,204 rows); UPDATE on public.users (12 rows)."
+        )
+    );
+    assert_eq!(summary::describe(&[]), None);
+}
+
+#[pg_test]
+fn test_timeout_problem() {
+    assert_eq!(
+        report::timeout_problem("lock_timeout", 0, 0).as_deref(),
+        Some("lock_timeout is disabled")
+    );
+    assert_eq!(report::timeout_problem("lock_timeout", 5000, 0), None);
+    assert_eq!(report::timeout_problem("statement_timeout", 60000, 60000), None);
+    assert_eq!(
+        report::timeout_problem("statement_timeout", 90000, 60000).as_deref(),
+        Some("statement_timeout is 90000 ms, above pg_strict.max_statement_timeout (60000 ms)")
+    );
+}
+
+#[pg_test]
+fn test_describe_backup_age() {
+    assert_eq!(backup::describe_age(45.0), "45 seconds");
+    assert_eq!(backup::describe_age(900.0), "15 minutes");
+    assert_eq!(backup::describe_age(86400.0), "24 hours");
+    assert_eq!(backup::describe_age(3.0 * 86400.0), "3 days");
+}
+
+#[pg_test]
+fn test_describe_plpgsql_origin() {
+    assert_eq!(
+        plpgsql::describe_origin("purge_table(text)", 4),
+        "EXECUTE at line 4 of function purge_table(text)"
+    );
+    assert_eq!(
+        plpgsql::describe_origin("inline_code_block", 1),
+        "EXECUTE at line 1 of a DO block"
+    );
+}
+
+#[pg_test]
+fn test_status_reports_hooks_first_in_chain() {
+    let run_hook = Spi::get_one::<String>(
+        "SELECT value FROM pg_strict_status() WHERE item = 'ExecutorRun_hook'",
+    )
+    .expect("read status");
+    assert_eq!(run_hook.as_deref(), Some("first"));
+    assert_eq!(hooks::hook_reinstalls(), 0);
+}
+
+#[pg_test]
+fn test_set_mode_scopes() {
+    assert!(api::pg_strict_set_mode("delete", "warn", "session"));
+    assert_eq!(crate::guc::current_modes().1, crate::guc::StrictMode::Warn);
+    assert!(api::pg_strict_set_mode("UPDATE", "on", "local"));
+    assert_eq!(crate::guc::current_modes().0, crate::guc::StrictMode::On);
+    assert!(api::pg_strict_set_mode("truncate", "on", "session"));
+    assert_eq!(crate::guc::guard_truncate_mode(), crate::guc::StrictMode::On);
+
+    assert!(!api::pg_strict_set_mode("insert", "on", "session"));
+    assert!(!api::pg_strict_set_mode("delete", "invalid", "session"));
+    assert!(!api::pg_strict_set_mode("delete", "on", "cluster"));
+}
+
+#[pg_test]
+fn test_set_mode_applies_superuser_settings_without_spi() {
+    assert!(api::pg_strict_set_mode("catalogs", "warn", "session"));
+    assert_eq!(crate::guc::protect_catalogs_mode(), crate::guc::StrictMode::Warn);
+    let shown = Spi::get_one::<String>("SHOW pg_strict.protect_catalogs").expect("show setting");
+    assert_eq!(shown.as_deref(), Some("warn"));
+}
+
+// Tests run inside a transaction block, where persisting is refused.
+#[pg_test]
+#[should_panic(expected = "pg_strict_persist cannot run inside a transaction block")]
+fn test_persist_refuses_transaction_block() {
+    api::pg_strict_persist("max_affected_rows", Some("12345"), false);
+}
+
+#[pg_test]
+fn test_apply_role_policy_sets_role_defaults() {
+    Spi::run("CREATE ROLE pg_strict_policy_role;").expect("create role");
+    let commands: Vec<String> =
+        api::pg_strict_apply_role_policy("pg_strict_policy_role", "warn_only").collect();
+    assert_eq!(commands.len(), 3);
+
+    let settings = Spi::get_one::<String>(
+        "SELECT array_to_string(s.setconfig, ',') FROM pg_db_role_setting s \
+         JOIN pg_roles r ON r.oid = s.setrole WHERE r.rolname = 'pg_strict_policy_role'",
+    )
+    .expect("read role settings")
+    .unwrap_or_default();
+    assert!(settings.contains("pg_strict.require_where_on_delete=warn"));
+    assert!(settings.contains("pg_strict.guard_truncate=warn"));
+}
+
+#[pg_test]
+fn test_status_reports_health() {
+    let status = Spi::get_one::<String>(
+        "SELECT string_agg(item || '=' || value, ',') FROM pg_strict_status() \
+         WHERE item IN ('initialized', 'shared_memory', 'delete_mode')",
+    )
+    .expect("read status")
+    .unwrap_or_default();
+    assert!(status.contains("initialized=yes"));
+    assert!(status.contains("shared_memory=allocated"));
+    assert!(status.contains("delete_mode=off"));
+}
+
+#[pg_test]
+fn test_status_reports_how_the_library_was_loaded() {
+    assert_eq!(crate::loaded_via(), "shared_preload_libraries");
+    let features =
+        Spi::get_one::<String>("SELECT value FROM pg_strict_status() WHERE item = 'features'")
+            .expect("read status");
+    assert_eq!(features.as_deref(), Some("full"));
+}
+
+#[pg_test]
+fn test_effective_config_reports_sources() {
+    Spi::run("SET pg_strict.require_where_on_update = 'warn';").expect("set update mode");
+    Spi::run(
+        "SELECT pg_strict_add_rule(target => 'public.orders', operation => 'delete', \
+         mode => 'on', max_affected_rows => 50);",
+    )
+    .expect("add rule");
+
+    let effective = Spi::get_one::<String>(
+        "SELECT string_agg(setting || '|' || scope || '|' || value || '|' || source, ',') \
+         FROM pg_strict_effective_config() \
+         WHERE setting IN ('pg_strict.require_where_on_update', 'pg_strict.require_where_on_delete', \
+                           'pg_strict.max_affected_rows')",
+    )
+    .expect("read effective config")
+    .unwrap_or_default();
+    assert!(effective.contains("pg_strict.require_where_on_update|*|warn|SET"));
+    assert!(effective.contains("pg_strict.require_where_on_delete|*|off|default"));
+    assert!(effective.contains("pg_strict.require_where_on_delete|public.orders|on|strict.rules id"));
+    assert!(effective.contains("pg_strict.max_affected_rows|public.orders (DELETE)|50|strict.rules id"));
+}
+
+#[pg_test]
+fn test_message_template_placeholders() {
+    let violation = report::Violation::missing_where(Operation::Delete, Some("public.orders"))
+        .with_rows_estimated(Some(1204543.0));
+    assert_eq!(
+        violation.render("%operation% on %table% would touch ~%rows_estimated% rows. %hint%"),
+        format!("DELETE on public.orders would touch ~1,204,543 rows. {}", violation.hint)
+    );
+
+    let without_details = report::Violation::costly_statement("SELECT", 10.0, 1.0);
+    assert_eq!(
+        without_details.render("[%table%/%rows_estimated%] %message%"),
+        format!("[unknown table/unknown] {}", without_details.message)
+    );
+}
+
+#[pg_test]
+fn test_sqlstate_encoding_matches_postgres() {
+    assert_eq!(sqlstate::encode("2F003"), Some(report::VIOLATION_SQLSTATE as i32));
+    assert_eq!(sqlstate::encode("42501"), Some(pgrx::PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE as i32));
+    assert_eq!(sqlstate::encode("PGS1"), None);
+}
+
+#[pg_test]
+fn test_message_catalog_translations_keep_placeholders() {
+    let placeholders = |text: &str| {
+        let mut names: Vec<String> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name.to_string()))
+            .collect();
+        names.sort();
+        names
+    };
+    for (key, texts) in messages::CATALOG {
+        for text in texts {
+            assert!(!text.is_empty(), "empty translation of {key}");
+            assert_eq!(placeholders(text), placeholders(texts[0]), "placeholders of {key}");
+        }
+    }
+}
+
+#[pg_test]
+fn test_message_language_selects_translation() {
+    Spi::run("SET pg_strict.message_language = 'de';").expect("set message language");
+    let violation = report::Violation::missing_where(Operation::Delete, Some("public.orders"));
+    assert_eq!(violation.detail, "DELETE auf Tabelle public.orders hat keine WHERE-Klausel.");
+    Spi::run("RESET pg_strict.message_language;").expect("reset message language");
+    let violation = report::Violation::missing_where(Operation::Delete, Some("public.orders"));
+    assert_eq!(violation.detail, "DELETE on table public.orders has no WHERE clause.");
+}
+
+#[pg_test]
+fn test_missing_where_hint_uses_table_catalog() {
+    Spi::run(
+        "CREATE TABLE pg_strict_hint_parent(tenant int, id int, PRIMARY KEY (tenant, id));
+         CREATE TABLE pg_strict_hint_child(
+             tenant int, parent int,
+             FOREIGN KEY (tenant, parent) REFERENCES pg_strict_hint_parent);
+         CREATE TABLE pg_strict_hint_heap(note text);",
+    )
+    .expect("create tables");
+    let oid = |name: &str| {
+        Spi::get_one::<pg_sys::Oid>(&format!("SELECT '{name}'::regclass::oid"))
+            .expect("resolve table")
+            .expect("table oid")
+    };
+
+    let update = report::Violation::missing_where(Operation::Update, None)
+        .with_table_hint(Operation::Update, oid("pg_strict_hint_parent"));
+    assert!(update.hint.contains("WHERE tenant = ... AND id = ..."));
+
+    let referenced = report::Violation::missing_where(Operation::Delete, None)
+        .with_table_hint(Operation::Delete, oid("pg_strict_hint_parent"));
+    assert!(referenced.hint.contains("TRUNCATE would fail"));
+    assert!(referenced.hint.contains("pg_strict_delete_in_batches('public.pg_strict_hint_parent'"));
+
+    let child = report::Violation::missing_where(Operation::Delete, None)
+        .with_rows_estimated(Some(1500.0))
+        .with_table_hint(Operation::Delete, oid("pg_strict_hint_child"));
+    assert!(child.hint.contains("TRUNCATE public.pg_strict_hint_child is faster"));
+    assert!(child.hint.ends_with("about 1,500 rows."));
+
+    Spi::run(r#"CREATE TABLE "pg_strict hint's" (id int);"#).expect("create quoted table");
+    let quoted = report::Violation::missing_where(Operation::Delete, None)
+        .with_table_hint(Operation::Delete, oid(r#""pg_strict hint''s""#));
+    assert!(quoted.hint.contains(r#"TRUNCATE public."pg_strict hint's" is faster"#));
+    assert!(quoted.hint.contains(r#"pg_strict_delete_in_batches('public."pg_strict hint''s"'"#));
+
+    let generic = report::Violation::missing_where(Operation::Update, None);
+    let heap = report::Violation::missing_where(Operation::Update, None)
+        .with_table_hint(Operation::Update, oid("pg_strict_hint_heap"));
+    assert_eq!(heap.hint, generic.hint);
+}
+
+#[pg_test]
+fn test_body_statements_finds_update_and_delete() {
+    let statements = analyzer::body_statements(
+        "DECLARE n int; BEGIN
+             IF n > 0 THEN DELETE FROM a; ELSE UPDATE b SET x = 1 WHERE id = n; END IF;
+             PERFORM 1 FROM c FOR UPDATE;
+             RAISE NOTICE 'DELETE FROM d;';
+             EXECUTE 'DELETE FROM e';
+         END",
+    );
+    assert_eq!(
+        statements,
+        vec![
+            (2, "DELETE FROM a".to_string()),
+            (2, "UPDATE b SET x = 1 WHERE id = n".to_string())
+        ]
+    );
+}
+
+#[pg_test]
+fn test_listed_in_accepts_windows_library_paths() {
+    Spi::run(r#"SET session_preload_libraries = 'auto_explain, "C:\Program Files\PostgreSQL\17\lib\pg_strict.dll"';"#)
+        .expect("set session_preload_libraries");
+    assert!(listed_in(c"session_preload_libraries"));
+
+    Spi::run("SET session_preload_libraries = '$libdir/pg_strict_extra.so';")
+        .expect("set session_preload_libraries");
+    assert!(!listed_in(c"session_preload_libraries"));
+}
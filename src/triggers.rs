@@ -0,0 +1,36 @@
+use crate::analyzer::Operation;
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+
+// pg_trigger.tgtype bits, from catalog/pg_trigger.h.
+const TRIGGER_TYPE_ROW: i32 = 1 << 0;
+const TRIGGER_TYPE_DELETE: i32 = 1 << 3;
+const TRIGGER_TYPE_UPDATE: i32 = 1 << 4;
+
+/// Enabled row-level triggers that fire for `operation` on `relid`, as
+/// `name (runs DML)` when the trigger function's source looks like it modifies
+/// other tables. `None` when there are none.
+pub fn summary(relid: pg_sys::Oid, operation: Operation) -> Option<String> {
+    let event = match operation {
+        Operation::Update => TRIGGER_TYPE_UPDATE,
+        Operation::Delete => TRIGGER_TYPE_DELETE,
+    };
+
+    Spi::get_one_with_args::<String>(
+        "SELECT string_agg(t.tgname || CASE \
+                    WHEN l.lanname IN ('plpgsql', 'sql') \
+                     AND p.prosrc ~* '\\m(insert|update|delete|merge)\\M' THEN ' (runs DML)' \
+                    ELSE '' END, ', ' ORDER BY t.tgname) \
+         FROM pg_catalog.pg_trigger t \
+         JOIN pg_catalog.pg_proc p ON p.oid = t.tgfoid \
+         JOIN pg_catalog.pg_language l ON l.oid = p.prolang \
+         WHERE t.tgrelid = $1 \
+           AND NOT t.tgisinternal \
+           AND t.tgenabled <> 'D' \
+           AND t.tgtype & $2 <> 0 \
+           AND t.tgtype & $3 <> 0",
+        &[relid.into(), TRIGGER_TYPE_ROW.into(), event.into()],
+    )
+    .ok()
+    .flatten()
+}
@@ -0,0 +1,115 @@
+use crate::catalog;
+use crate::guc::{self, StrictMode};
+use crate::policy;
+use crate::report::Violation;
+use pgrx::PgList;
+use pgrx::pg_sys;
+use std::ffi::CStr;
+
+/// A check for one kind of utility statement, run from the ProcessUtility hook.
+struct UtilityRule {
+    tag: pg_sys::NodeTag,
+    command: &'static str,
+    setting: &'static str,
+    mode: fn() -> StrictMode,
+    check: unsafe fn(*mut pg_sys::Node) -> Option<Finding>,
+}
+
+/// What a rule found wrong with a utility statement.
+pub struct Finding {
+    pub table: Option<String>,
+    pub violation: Violation,
+}
+
+/// A finding together with the rule that produced it.
+pub struct RuleMatch {
+    pub command: &'static str,
+    /// The setting that holds the rule's mode.
+    pub setting: &'static str,
+    pub mode: StrictMode,
+    pub finding: Finding,
+}
+
+// Rules are looked up by the statement's node tag. Each carries its own mode
+// GUC, so adding a rule means adding an entry here, a GUC and a Violation.
+static RULES: &[UtilityRule] = &[UtilityRule {
+    tag: pg_sys::NodeTag::T_TruncateStmt,
+    command: "TRUNCATE",
+    setting: "pg_strict.guard_truncate",
+    mode: guc::guard_truncate_mode,
+    check: check_truncate,
+}];
+
+/// Runs the rule registered for the statement's node type, if its mode is on.
+pub unsafe fn check(pstmt: *mut pg_sys::PlannedStmt) -> Option<RuleMatch> {
+    unsafe { check_node((*pstmt).utilityStmt) }
+}
+
+/// Runs the rule registered for a raw or planned utility statement's node
+/// type, if its mode is on.
+pub unsafe fn check_node(node: *mut pg_sys::Node) -> Option<RuleMatch> {
+    if node.is_null() {
+        return None;
+    }
+    let tag = unsafe { (*node).type_ };
+    let rule = RULES.iter().find(|rule| rule.tag == tag)?;
+    let mode = policy::resolve(rule.setting, (rule.mode)());
+    if matches!(mode, StrictMode::Off | StrictMode::Learn) {
+        return None;
+    }
+    let finding = unsafe { (rule.check)(node) }?;
+    Some(RuleMatch {
+        command: rule.command,
+        setting: rule.setting,
+        mode,
+        finding,
+    })
+}
+
+unsafe fn check_truncate(node: *mut pg_sys::Node) -> Option<Finding> {
+    let stmt = node as *mut pg_sys::TruncateStmt;
+    let relations = unsafe { PgList::<pg_sys::RangeVar>::from_pg((*stmt).relations) };
+    let names: Vec<String> = relations
+        .iter_ptr()
+        .map(|relation| match unsafe { relation_oid(relation) } {
+            Some(relid) => catalog::qualified_relation_name(relid),
+            None => unsafe { range_var_name(relation) },
+        })
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+
+    let tables = names.join(", ");
+    Some(Finding {
+        table: names.first().cloned(),
+        violation: Violation::truncate(&tables),
+    })
+}
+
+pub unsafe fn relation_oid(relation: *mut pg_sys::RangeVar) -> Option<pg_sys::Oid> {
+    let relid = unsafe {
+        pg_sys::RangeVarGetRelidExtended(
+            relation,
+            pg_sys::NoLock as pg_sys::LOCKMODE,
+            pg_sys::RVROption::RVR_MISSING_OK,
+            None,
+            std::ptr::null_mut(),
+        )
+    };
+    (relid != pg_sys::InvalidOid).then_some(relid)
+}
+
+pub unsafe fn range_var_name(relation: *mut pg_sys::RangeVar) -> String {
+    let name = unsafe { CStr::from_ptr((*relation).relname) }.to_string_lossy();
+    let schema = unsafe { (*relation).schemaname };
+    if schema.is_null() {
+        name.into_owned()
+    } else {
+        format!(
+            "{}.{}",
+            unsafe { CStr::from_ptr(schema) }.to_string_lossy(),
+            name
+        )
+    }
+}
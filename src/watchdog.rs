@@ -0,0 +1,87 @@
+use crate::activity;
+use crate::exemptions::glob_match;
+use crate::guc::{
+    WatchdogAction, exempt_application_names, watchdog_action, watchdog_database,
+    watchdog_max_duration,
+};
+use crate::shmem;
+use pgrx::bgworkers::{BackgroundWorker, BackgroundWorkerBuilder, SignalWakeFlags};
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use std::time::Duration;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Starts the watchdog when pg_strict is preloaded with
+/// `pg_strict.watchdog_max_duration` set.
+pub fn register_worker() {
+    if !shmem::available() || watchdog_max_duration() == 0 {
+        return;
+    }
+
+    BackgroundWorkerBuilder::new("pg_strict watchdog")
+        .set_type("pg_strict watchdog")
+        .set_function("pg_strict_watchdog_main")
+        .set_library("pg_strict")
+        .enable_spi_access()
+        .set_restart_time(Some(Duration::from_secs(10)))
+        .load();
+}
+
+#[pg_guard]
+#[unsafe(no_mangle)]
+pub extern "C-unwind" fn pg_strict_watchdog_main(_arg: pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+    BackgroundWorker::connect_worker_to_spi(Some(&watchdog_database()), None);
+
+    while BackgroundWorker::wait_latch(Some(CHECK_INTERVAL)) {
+        if BackgroundWorker::sighup_received() {
+            unsafe { pg_sys::ProcessConfigFile(pg_sys::GucContext::PGC_SIGHUP) };
+        }
+
+        let max_duration = watchdog_max_duration();
+        if max_duration == 0 {
+            continue;
+        }
+        BackgroundWorker::transaction(|| stop_runaway_statements(max_duration as f64));
+    }
+}
+
+fn stop_runaway_statements(max_seconds: f64) {
+    let exempt = exempt_application_names();
+    let (function, verb) = match watchdog_action() {
+        WatchdogAction::Cancel => ("pg_cancel_backend", "cancelled"),
+        WatchdogAction::Terminate => ("pg_terminate_backend", "terminated"),
+    };
+
+    for active in activity::active_violations() {
+        if active.running_seconds <= max_seconds {
+            continue;
+        }
+        let application_name = active.application_name.as_deref().unwrap_or_default();
+        if exempt
+            .iter()
+            .any(|pattern| glob_match(pattern, application_name))
+        {
+            continue;
+        }
+
+        let stopped =
+            Spi::get_one_with_args::<bool>(&format!("SELECT {function}($1)"), &[active.pid.into()])
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+        if stopped {
+            pgrx::log!(
+                "pg_strict: watchdog {} backend {} ({}@{}) after {:.0}s running {} without WHERE clause: {}",
+                verb,
+                active.pid,
+                active.username.as_deref().unwrap_or_default(),
+                active.database.as_deref().unwrap_or_default(),
+                active.running_seconds,
+                active.operation,
+                active.query
+            );
+        }
+    }
+}
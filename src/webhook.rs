@@ -0,0 +1,185 @@
+use crate::guc::webhook_url;
+use crate::shmem;
+use pgrx::bgworkers::{BackgroundWorker, BackgroundWorkerBuilder, SignalWakeFlags};
+use pgrx::pg_sys;
+use pgrx::prelude::*;
+use pgrx::shmem::PGRXSharedMemory;
+use std::time::Duration;
+
+pub const WEBHOOK_QUEUE_LEN: usize = 128;
+pub const WEBHOOK_PAYLOAD_LEN: usize = 512;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// Posts of one event before it is given up; retries are a poll interval apart.
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Copy, Clone)]
+struct WebhookEvent {
+    len: usize,
+    payload: [u8; WEBHOOK_PAYLOAD_LEN],
+}
+
+impl Default for WebhookEvent {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            payload: [0; WEBHOOK_PAYLOAD_LEN],
+        }
+    }
+}
+
+/// Ring buffer of violation payloads waiting to be posted by the webhook worker.
+/// Unlike tables and NOTIFY it is not transactional, so blocked statements are
+/// delivered too.
+#[derive(Copy, Clone)]
+pub struct WebhookQueue {
+    head: usize,
+    len: usize,
+    dropped: u64,
+    events: [WebhookEvent; WEBHOOK_QUEUE_LEN],
+}
+
+impl Default for WebhookQueue {
+    fn default() -> Self {
+        Self {
+            head: 0,
+            len: 0,
+            dropped: 0,
+            events: [WebhookEvent::default(); WEBHOOK_QUEUE_LEN],
+        }
+    }
+}
+
+unsafe impl PGRXSharedMemory for WebhookQueue {}
+
+impl WebhookQueue {
+    fn push(&mut self, payload: &str) {
+        // Payloads are single-line JSON objects; one that does not fit is not sent
+        // truncated, which would make it unparseable.
+        if payload.len() > WEBHOOK_PAYLOAD_LEN || self.len == WEBHOOK_QUEUE_LEN {
+            self.dropped += 1;
+            return;
+        }
+
+        let slot = &mut self.events[(self.head + self.len) % WEBHOOK_QUEUE_LEN];
+        slot.payload[..payload.len()].copy_from_slice(payload.as_bytes());
+        slot.len = payload.len();
+        self.len += 1;
+    }
+
+    /// The oldest payload. It stays queued until `pop` removes it, so an
+    /// event is only lost once it was posted or given up on.
+    fn front(&self) -> Option<String> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = &self.events[self.head];
+        Some(String::from_utf8_lossy(&event.payload[..event.len]).into_owned())
+    }
+
+    fn pop(&mut self) {
+        if self.len > 0 {
+            self.head = (self.head + 1) % WEBHOOK_QUEUE_LEN;
+            self.len -= 1;
+        }
+    }
+
+    fn take_dropped(&mut self) -> u64 {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+/// Starts the webhook worker when pg_strict is preloaded with
+/// `pg_strict.webhook_url` set.
+pub fn register_worker() {
+    if !shmem::available() || webhook_url().is_none() {
+        return;
+    }
+
+    BackgroundWorkerBuilder::new("pg_strict webhook")
+        .set_type("pg_strict webhook")
+        .set_function("pg_strict_webhook_main")
+        .set_library("pg_strict")
+        .enable_shmem_access(None)
+        .set_restart_time(Some(Duration::from_secs(10)))
+        .load();
+}
+
+/// Queues a violation for the webhook worker.
+pub fn enqueue(payload: &str) {
+    if !shmem::available() || webhook_url().is_none() {
+        return;
+    }
+    shmem::WEBHOOK_QUEUE.exclusive().push(payload);
+}
+
+#[pg_guard]
+#[unsafe(no_mangle)]
+pub extern "C-unwind" fn pg_strict_webhook_main(_arg: pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+
+    // Failed posts of the event at the head of the queue.
+    let mut attempts = 0;
+    while BackgroundWorker::wait_latch(Some(Duration::from_secs(1))) {
+        if BackgroundWorker::sighup_received() {
+            unsafe { pg_sys::ProcessConfigFile(pg_sys::GucContext::PGC_SIGHUP) };
+        }
+
+        let dropped = shmem::WEBHOOK_QUEUE.exclusive().take_dropped();
+        if dropped > 0 {
+            pgrx::log!(
+                "pg_strict: webhook queue overflowed, {} violation events were dropped.",
+                dropped
+            );
+        }
+
+        let Some(url) = webhook_url() else {
+            continue;
+        };
+        // Only this worker removes events, so the head stays put while it is
+        // posted without holding the lock.
+        while let Some(payload) = shmem::WEBHOOK_QUEUE.share().front() {
+            // Each post may take up to the request timeout; stop promptly at
+            // shutdown, leaving the rest queued.
+            if BackgroundWorker::sigterm_received() {
+                pgrx::log!(
+                    "pg_strict: shutting down with {} violation events not yet posted to the webhook.",
+                    shmem::WEBHOOK_QUEUE.share().len
+                );
+                return;
+            }
+            match post(&url, &payload) {
+                Ok(()) => attempts = 0,
+                Err(error) if attempts + 1 < MAX_ATTEMPTS => {
+                    attempts += 1;
+                    pgrx::log!(
+                        "pg_strict: webhook delivery to {} failed (attempt {} of {}), retrying: {}",
+                        url,
+                        attempts,
+                        MAX_ATTEMPTS,
+                        error
+                    );
+                    break;
+                }
+                Err(error) => {
+                    attempts = 0;
+                    pgrx::log!(
+                        "pg_strict: webhook delivery to {} failed {} times, dropping the event: {}",
+                        url,
+                        MAX_ATTEMPTS,
+                        error
+                    );
+                }
+            }
+            shmem::WEBHOOK_QUEUE.exclusive().pop();
+        }
+    }
+}
+
+fn post(url: &str, payload: &str) -> Result<(), ureq::Error> {
+    ureq::post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(payload)
+        .map(|_| ())
+}
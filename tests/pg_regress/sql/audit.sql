@@ -0,0 +1,29 @@
+-- Blocked violations are written by a background worker, so they are kept
+-- even when the session disconnects right after the error. Needs pg_strict
+-- in shared_preload_libraries.
+
+CREATE TABLE pg_strict_audit_test(id int);
+INSERT INTO pg_strict_audit_test VALUES (1), (2);
+
+\set VERBOSITY terse
+SET pg_strict.audit = on;
+SET pg_strict.require_where_on_delete = 'on';
+DELETE FROM pg_strict_audit_test;
+
+-- Disconnect without running anything else in that session
+\set QUIET on
+\c
+\set QUIET off
+
+DO $$
+BEGIN
+    FOR attempt IN 1..100 LOOP
+        EXIT WHEN EXISTS (SELECT 1 FROM strict.violations WHERE query = 'DELETE FROM pg_strict_audit_test');
+        PERFORM pg_sleep(0.1);
+    END LOOP;
+END
+$$;
+
+SELECT mode, operation, query FROM strict.violations WHERE query = 'DELETE FROM pg_strict_audit_test';
+
+DROP TABLE pg_strict_audit_test;
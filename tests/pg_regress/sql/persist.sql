@@ -0,0 +1,11 @@
+-- pg_strict_persist() refuses to run inside a transaction block, and
+-- #[pg_test] functions always run in one, so writing postgresql.auto.conf is
+-- tested here.
+
+SELECT pg_strict_persist('max_affected_rows', '12345', false);
+SELECT setting FROM pg_file_settings
+WHERE name = 'pg_strict.max_affected_rows' AND sourcefile LIKE '%postgresql.auto.conf';
+
+SELECT pg_strict_persist('pg_strict.max_affected_rows', NULL, false);
+SELECT count(*) FROM pg_file_settings
+WHERE name = 'pg_strict.max_affected_rows' AND sourcefile LIKE '%postgresql.auto.conf';